@@ -0,0 +1,55 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, Index, parse_macro_input};
+
+/// Implements `DebugText` by writing every field as its own debug line,
+/// keyed `"{prefix}_{field}"` and formatted with `Debug`.
+#[proc_macro_derive(DebugText)]
+pub fn derive_debug_text(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return syn::Error::new_spanned(&input.ident, "DebugText can only be derived for structs")
+            .to_compile_error()
+            .into();
+    };
+
+    let writes = match &data.fields {
+        Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|field| {
+                let ident = field.ident.as_ref().unwrap();
+                let label = ident.to_string();
+                quote! {
+                    writer.write(format!("{}_{}", prefix, #label), format!("{:?}", self.#ident));
+                }
+            })
+            .collect::<Vec<_>>(),
+        Fields::Unnamed(fields) => (0..fields.unnamed.len())
+            .map(|idx| {
+                let index = Index::from(idx);
+                let label = idx.to_string();
+                quote! {
+                    writer.write(format!("{}_{}", prefix, #label), format!("{:?}", self.#index));
+                }
+            })
+            .collect(),
+        Fields::Unit => vec![quote! { let _ = (writer, prefix); }],
+    };
+
+    quote! {
+        impl #impl_generics ::bevy_stability_test::debug_vis::DebugText for #name #ty_generics #where_clause {
+            fn debug_dump(
+                &self,
+                writer: &mut ::bevy_stability_test::debug_vis::DebugTextWriter<'_, '_>,
+                prefix: &str,
+            ) {
+                #(#writes)*
+            }
+        }
+    }
+    .into()
+}
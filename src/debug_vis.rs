@@ -1,26 +1,279 @@
 use bevy::{
-    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
-    ecs::system::SystemParam,
+    diagnostic::{
+        Diagnostic, DiagnosticPath, Diagnostics, DiagnosticsStore, FrameCount,
+        FrameTimeDiagnosticsPlugin, RegisterDiagnostic,
+    },
+    ecs::{archetype::Archetypes, system::SystemParam},
     prelude::*,
-    text::{TextColor, TextFont},
+    text::{TextColor, TextFont, TextLayoutInfo},
     ui::Node,
+    camera::{
+        RenderTarget,
+        primitives::{Frustum, HalfSpace},
+        visibility::{RenderLayers, VisibilitySystems},
+    },
+    window::{PresentMode, PrimaryWindow, WindowRef, WindowResized},
 };
+pub use asset_reloads::DebugAssetReloadsAppExt;
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+pub use baseline::{STABILITY_REPORT_VERSION, StabilityReport};
+pub use bevy_stability_test_macros::DebugText;
+pub use filter::DebugLineFilter;
+pub use inspect::{DebugDisplay, DebugDisplayAppExt, DebugInspectTarget};
+pub use keybindings::{DebugAction, DebugKeybindings, KeyBinding};
+pub use pacing::{BimodalPacing, BimodalPacingDetected, PacingMode};
+pub use palette::{DebugCommand, DebugCommandRegistry};
+#[cfg(all(feature = "battery", target_os = "linux"))]
+pub use power::PowerState;
+pub use profiles::{DebugProfile, DebugProfiles};
+pub use query_stats::{DebugQuery, QueryStats};
+#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+pub use remote::RemoteAddress;
+pub use scenario::{
+    ActiveScenario, ScenarioAppExt, ScenarioEntity, ScenarioFailed, ScenarioRegistry,
+    scenario_active,
+};
+#[cfg(not(target_arch = "wasm32"))]
+pub use soak::SoakConfig;
+pub use shapes::{
+    DebugShape, DebugShapeKind, DebugWorldGizmoGroup, debug_capsule, debug_capsule_for, debug_hit,
+    debug_hit_for, debug_ray, debug_ray_for, debug_shape,
+};
+pub use spikes::{FrameSpike, SpikeAttribution};
+pub use states::DebugStateAppExt;
+#[cfg(not(target_arch = "wasm32"))]
+pub use sweep::{SWEEP_REPORT_VERSION, ScenarioResult, SweepConfig, SweepReport};
+pub use throttle::ThrottleDetector;
+pub use timeline::{TimelineCategories, TimelineEvent, TimelineEventKind, TimelineEvents};
+#[cfg(all(feature = "report_upload", not(target_arch = "wasm32")))]
+pub use upload::REPORT_AUTH_ENV;
+pub use vsync::{VsyncClass, VsyncMisses};
+pub use widgets::DebugWidgets;
+use bevy::platform::time::Instant;
 use std::{
-    collections::{HashMap, VecDeque},
-    sync::{Mutex, OnceLock},
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    sync::{
+        Mutex, OnceLock,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
 };
 
+mod asset_reloads;
+mod auto_anchor;
+#[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+mod baseline;
+mod dial;
+#[cfg(not(target_arch = "wasm32"))]
+mod capture;
+#[cfg(not(target_arch = "wasm32"))]
+mod crash_dump;
+#[cfg(feature = "egui")]
+mod egui_settings;
+#[cfg(feature = "egui")]
+mod egui_table;
+mod filter;
+#[cfg(feature = "wgpu_timestamps")]
+mod gpu_timestamps;
+mod help_panel;
+mod inspect;
+#[cfg(feature = "hot_reload")]
+mod hot_reload;
+mod keybindings;
+mod pacing;
+mod palette;
+mod picking;
+#[cfg(not(target_arch = "wasm32"))]
+mod pipelines;
+#[cfg(all(feature = "battery", target_os = "linux"))]
+mod power;
+mod profiles;
+mod query_stats;
+#[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+mod remote;
+#[cfg(not(target_arch = "wasm32"))]
+mod replay;
+mod scenario;
+mod schedules;
+mod shapes;
+#[cfg(not(target_arch = "wasm32"))]
+mod soak;
+mod spikes;
+mod states;
+#[cfg(not(target_arch = "wasm32"))]
+mod sweep;
+mod throttle;
+mod timeline;
+#[cfg(all(feature = "report_upload", not(target_arch = "wasm32")))]
+mod upload;
+mod vsync;
+mod widgets;
+
+/// Diagnostic holding [`DebugTexts::line_count`], measured every frame.
+pub const DEBUG_VIS_LINE_COUNT: DiagnosticPath = DiagnosticPath::const_new("debug_vis/line_count");
+/// Diagnostic holding how many line slots have been handed out. Slots stick to their key, so
+/// this only grows; a steady climb means keys are being made up on the fly.
+pub const DEBUG_VIS_LINES_ALLOCATED: DiagnosticPath =
+    DiagnosticPath::const_new("debug_vis/lines_allocated");
+
 const LINE_HEIGHT: f32 = 20.0;
+/// Where lines stacked from a top corner start, clear of the FPS lines and default chart.
+const TOP_STACK_OFFSET: f32 = 204.0;
+const LINE_OVERFLOW_WARN_INTERVAL: Duration = Duration::from_secs(10);
+const CHART_HEIGHT: f32 = 50.0;
+const CHART_MARGIN: f32 = 8.0;
+/// Room kept right of the chart for the percentile labels when placing it on the right.
+const CHART_LABEL_WIDTH: f32 = 96.0;
+/// Line index of debug lines pinned to a pixel position rather than stacked.
+const PINNED_LINE: usize = usize::MAX;
 const LEFT_PADDING: f32 = 12.0;
+/// Advance of one glyph of the default (monospace) font at the debug text size, for fitting
+/// lines to a width without waiting for text layout.
+const DEBUG_CHAR_WIDTH: f32 = 16.0 * 0.6;
 const FRAME_DELTA_WINDOW: usize = 300;
 const FPS_AVG_WINDOW_SECONDS: f64 = 0.25;
+/// Relative deviation from the running average that counts toward a step change.
+const FPS_STEP_THRESHOLD: f64 = 0.3;
+/// Consecutive deviating frames before the FPS window is thrown away.
+const FPS_STEP_FRAMES: usize = 10;
+const DEBUG_TEXT_COLOR: Color = Color::srgb(0.0, 1.0, 0.0);
+const DIFF_HIGHLIGHT_FRAMES: u8 = 10;
+/// Ahead of any camera the host app is likely to spawn.
+const OVERLAY_CAMERA_ORDER: isize = 1_000;
+/// Weight of the newest sample in the smoothed series when the source has no smoothing of its
+/// own; matches the diagnostics store's default.
+const SMOOTHING_FACTOR: f64 = 2.0 / 21.0;
+const SMOOTHED_LINE_COLOR: Color = Color::srgb(0.2, 0.6, 1.0);
+const CHART_CURSOR_COLOR: Color = Color::WHITE;
+const WORST_BAR_MARKER_COLOR: Color = Color::srgb(1.0, 0.5, 0.0);
+const WORST_BAR_MARKER_SIZE: f32 = 4.0;
+/// Frustum face colors, in [`Frustum::half_spaces`] order: left, right, top, bottom, near, far.
+const FRUSTUM_FACE_COLORS: [Color; 6] = [
+    Color::srgb(1.0, 0.0, 0.0),
+    Color::srgb(0.0, 1.0, 0.0),
+    Color::srgb(0.0, 0.4, 1.0),
+    Color::srgb(1.0, 1.0, 0.0),
+    Color::srgb(0.0, 1.0, 1.0),
+    Color::srgb(1.0, 0.0, 1.0),
+];
+/// Reference lines drawn across the frametime chart, as (percentile, color).
+const CHART_PERCENTILES: [(f64, Color); 3] = [
+    (50.0, Color::srgb(0.3, 0.5, 1.0)),
+    (95.0, Color::srgb(1.0, 0.6, 0.0)),
+    (99.0, Color::srgb(1.0, 0.0, 0.0)),
+];
+/// Horizontal px between table columns.
+const TABLE_COLUMN_GAP: f32 = 12.0;
+const FLASH_DURATION_SECONDS: f32 = 0.4;
+const FLASH_COLOR: Color = Color::WHITE;
+const HEALTH_DOT_SIZE: f32 = 12.0;
+const HEALTH_GOOD_COLOR: Color = Color::srgb(0.0, 1.0, 0.0);
+const HEALTH_WARN_COLOR: Color = Color::srgb(1.0, 1.0, 0.0);
+const HEALTH_BAD_COLOR: Color = Color::srgb(1.0, 0.0, 0.0);
+/// Radius of the circle marking the cursor on its plane, in world units.
+const CURSOR_CIRCLE_RADIUS: f32 = 0.25;
+const LABEL_COLUMN_HYSTERESIS: f32 = 24.0;
+const MAX_RECORDED_SNAPSHOTS: usize = 1000;
+const QUEUE_LATENCY_WINDOW: usize = 1000;
+/// One-second buckets kept per [`DebugTextWriter::write_rate`] line, for its sparkline.
+const RATE_HISTORY_SECONDS: usize = 16;
+
+#[derive(Default)]
+pub struct DebugVisPlugin {
+    initial_level: Option<DebugLevel>,
+    keybindings: Option<DebugKeybindings>,
+    window: Option<Entity>,
+    #[cfg(not(target_arch = "wasm32"))]
+    soak: Option<SoakConfig>,
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    baseline: Option<std::path::PathBuf>,
+    #[cfg(not(target_arch = "wasm32"))]
+    replay: Option<std::path::PathBuf>,
+    #[cfg(not(target_arch = "wasm32"))]
+    sweep: Option<SweepConfig>,
+}
+
+impl DebugVisPlugin {
+    /// Starts the overlay at `level` instead of [`DebugLevel::default`].
+    pub fn with_level(level: DebugLevel) -> Self {
+        Self {
+            initial_level: Some(level),
+            ..default()
+        }
+    }
+
+    /// Shows the overlay on `window` instead of the primary window: overlay UI targets the
+    /// camera rendering to it, and the chart and cursor readout use that camera.
+    pub fn for_window(window: Entity) -> Self {
+        Self {
+            window: Some(window),
+            ..default()
+        }
+    }
+
+    /// Replaces the default shortcuts.
+    pub fn with_keybindings(mut self, keybindings: DebugKeybindings) -> Self {
+        self.keybindings = Some(keybindings);
+        self
+    }
 
-pub struct DebugVisPlugin;
+    /// Sets the overlay up for an unattended run: periodic exports, a spike log and a
+    /// report when FPS collapses, all under [`SoakConfig::dir`]. Native only.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_soak(mut self, soak: SoakConfig) -> Self {
+        self.soak = Some(soak);
+        self
+    }
+
+    /// Compares the live avg, P95 and P99 against a [`StabilityReport`] saved by an earlier
+    /// run, and warns when it was measured at another resolution or present mode. Native
+    /// only, with the `serde` feature.
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    pub fn with_baseline(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.baseline = Some(path.into());
+        self
+    }
+
+    /// Plays a recorded frame time trace through the overlay instead of recording live
+    /// frames: a CSV with a `frame_time_ms` column, such as a soak export, or with the `serde`
+    /// feature a JSON file with a `frame_times_ms` array, such as a saved stability report.
+    /// Native only.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_replay(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.replay = Some(path.into());
+        self
+    }
+
+    /// Runs every scenario registered with [`ScenarioAppExt::register_scenario`] in turn,
+    /// prints a table of their statistics, writes a [`SweepReport`] to
+    /// [`SweepConfig::report`] with the `serde` feature, and exits. Native only.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_sweep(mut self, sweep: SweepConfig) -> Self {
+        self.sweep = Some(sweep);
+        self
+    }
+}
 
 #[derive(Default, Reflect, GizmoConfigGroup)]
 struct DebugTopGizmoGroup;
 
+/// A physics contact to visualize, e.g. copied from your physics engine's contact events.
+/// Drawn as an arrow along `normal` when [`DebugVisConfig::show_contact_normals`] is set.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct ContactNormal {
+    pub point: Vec3,
+    pub normal: Vec3,
+    /// Penetration depth; deeper contacts draw longer and redder.
+    pub depth: f32,
+}
+
+/// The overlay-only camera spawned when [`DebugVisConfig::overlay_camera_layer`] is set.
+#[derive(Component)]
+pub struct DebugOverlayCamera;
+
 #[derive(Resource, Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DebugLevel {
     Hidden,
     FpsOnly,
@@ -28,499 +281,4464 @@ pub enum DebugLevel {
     Full,
 }
 
+/// A value that can be stepped through in place, for [`DebugTextWriter::write_enum_cycle`].
+pub trait Cycle: Copy + Send + Sync + 'static {
+    /// The value after this one, wrapping back to the first.
+    fn next(&self) -> Self;
+    fn label(&self) -> &str;
+}
+
+impl Cycle for DebugLevel {
+    fn next(&self) -> Self {
+        match self {
+            DebugLevel::Hidden => DebugLevel::FpsOnly,
+            DebugLevel::FpsOnly => DebugLevel::Full,
+            DebugLevel::Full => DebugLevel::Hidden,
+        }
+    }
+
+    fn label(&self) -> &str {
+        match self {
+            DebugLevel::Hidden => "Hidden",
+            DebugLevel::FpsOnly => "FpsOnly",
+            DebugLevel::Full => "Full",
+        }
+    }
+}
+
+/// Sent whenever [`DebugLevel`] changes, whatever changed it.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugLevelChanged {
+    pub from: DebugLevel,
+    pub to: DebugLevel,
+}
+
+/// Run condition that passes while the overlay is at `level`.
+pub fn on_debug_level(level: DebugLevel) -> impl FnMut(Res<DebugLevel>) -> bool + Clone {
+    move |current: Res<DebugLevel>| *current == level
+}
+
+/// Where [`FrameTimeHistory`] samples come from.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameTimeSource {
+    /// Frame time from [`FrameTimeDiagnosticsPlugin`], with the plugin's own smoothed value
+    /// kept as the smoothed series.
+    #[default]
+    Diagnostics,
+    /// Raw per-frame delta from `Time<Real>`, without any smoothing.
+    RealTime,
+    /// Samples pushed into [`ManualFrameTimes`], e.g. by tests or a replayed trace. All
+    /// queued samples are recorded on the next update, and drive the FPS readout too.
+    Manual,
+}
+
+/// Series drawn by the frametime chart. Raw samples are bars; the smoothed series is a line
+/// drawn over them, which shows how much the smoothing hides.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChartSeries {
+    #[default]
+    Raw,
+    Smoothed,
+    Both,
+}
+
+/// What the frametime chart's green-to-red bar colors are measured against. A bar at the
+/// reference is green, one at twice the reference or more is red.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChartColorRamp {
+    /// [`DebugVisConfig::frame_budget_ms`], so the colors mean the same from run to run.
+    #[default]
+    Budget,
+    /// The window's average, which shows hitches relative to the current load.
+    Average,
+}
+
+impl ChartColorRamp {
+    pub const ALL: [Self; 2] = [Self::Budget, Self::Average];
+
+    /// Legend shown under the chart.
+    fn legend(self, budget_ms: f64) -> String {
+        match self {
+            Self::Budget => format!("green ≤ {budget_ms:.1}ms budget, red ≥ 2× budget"),
+            Self::Average => "green ≤ avg, red ≥ 2× avg".to_string(),
+        }
+    }
+}
+
+/// Where the frametime chart sits. Presets follow the viewport as it resizes; dragging the
+/// chart with Alt held switches to `Custom`.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChartPlacement {
+    /// Under the FPS and frametime lines.
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomRight,
+    BottomCenter,
+    /// Bottom-left corner of the bars, in logical px from the viewport's top-left.
+    Custom(Vec2),
+}
+
+impl ChartPlacement {
+    pub const PRESETS: [Self; 4] = [
+        Self::TopLeft,
+        Self::TopRight,
+        Self::BottomRight,
+        Self::BottomCenter,
+    ];
+
+    /// The preset after this one, wrapping around; `Custom` goes back to the first.
+    pub fn next(self) -> Self {
+        let index = Self::PRESETS.iter().position(|&preset| preset == self);
+        Self::PRESETS[index.map_or(0, |index| (index + 1) % Self::PRESETS.len())]
+    }
+}
+
+/// Queue of frame times consumed when [`FrameTimeSource::Manual`] is selected.
+#[derive(Resource, Default, Debug)]
+pub struct ManualFrameTimes(pub VecDeque<f64>);
+
+impl ManualFrameTimes {
+    pub fn push(&mut self, frame_time_ms: f64) {
+        self.0.push_back(frame_time_ms);
+    }
+}
+
+/// Whether frame times are recorded at all. Recording is independent of [`DebugLevel`]: a
+/// hidden overlay keeps collecting statistics, while `RecordingEnabled(false)` stops the
+/// pipeline entirely for zero overhead. Manual frame times keep queueing while paused.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordingEnabled(pub bool);
+
+impl Default for RecordingEnabled {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+fn recording_enabled(recording: Res<RecordingEnabled>) -> bool {
+    recording.0
+}
+
+/// Tunables for the debug overlay. Insert before adding [`DebugVisPlugin`] to override defaults.
+///
+/// Every position and size here, like the overlay's own layout, is in logical pixels. Bevy's UI
+/// multiplies them by the window's scale factor (and [`UiScale`]), so the overlay keeps the same
+/// apparent size on HiDPI displays instead of shrinking.
+#[derive(Resource, Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
+pub struct DebugVisConfig {
+    /// Extra px insets applied on top of every overlay node's position, for notches and bezels.
+    pub safe_area: UiRect,
+    /// Move the debug lines to the first screen corner, starting bottom-left, that no UI of
+    /// the app's own covers. Rechecked when the window resizes or UI nodes come and go.
+    pub auto_anchor: bool,
+    pub frame_time_source: FrameTimeSource,
+    /// Color used by [`DebugTextWriter::write_diff`] while a changed value is highlighted.
+    pub diff_color: Color,
+    /// Apply [`DebugTextWriter::write_flash`] behavior to every write.
+    pub flash_on_change: bool,
+    /// Compare every line with the previous frame's and highlight what changed in
+    /// `diff_color` and what is new in `new_entry_color`, whichever way it was written.
+    pub show_diff: bool,
+    /// Color of lines that appeared since the previous frame, with `show_diff`.
+    pub new_entry_color: Color,
+    /// Frames a `show_diff` highlight lingers after the change.
+    pub diff_linger_frames: u8,
+    /// Split `"label: value"` lines so values line up in a shared column.
+    pub align_columns: bool,
+    /// On panic, write the frame time window to `crash_frametimes.txt`. Ignored on wasm.
+    pub crash_dump: bool,
+    /// Offset of the shadow behind overlay text. `Vec2::ZERO` hides it behind the text.
+    pub text_shadow_offset: Vec2,
+    pub text_shadow_color: Color,
+    /// Serve overlay stats, history and commands over HTTP on `remote_port`, with the `remote`
+    /// feature. Read once, when [`DebugVisPlugin`] is added. Ignored on wasm.
+    pub remote_enabled: bool,
+    /// Localhost port of the server started by `remote_enabled`.
+    pub remote_port: u16,
+    /// Draw a crosshair where the cursor ray meets the cursor plane and print the cursor's
+    /// viewport and world positions.
+    pub show_cursor_world_pos: bool,
+    /// Distance of the cursor plane from the origin along `cursor_plane_normal`; with the
+    /// default normal, its height.
+    pub cursor_plane_height: f32,
+    /// Normal of the plane the cursor ray is intersected with. Falls back to +Y if zero.
+    pub cursor_plane_normal: Vec3,
+    /// Print the number of ECS archetypes and the most seen since startup.
+    pub show_archetype_count: bool,
+    /// Print how many query iterations [`DebugQuery`]s started in the last frame.
+    pub show_query_stats: bool,
+    /// Print the [`Time<Virtual>`] relative speed, in yellow whenever it isn't 1.
+    pub show_time_scale: bool,
+    /// Print how long [`debug_text`] requests wait in the queue, as the average plus P50 and
+    /// P99 over [`QueueLatencyHistory`].
+    pub show_queue_latency: bool,
+    /// List assets hot-reloaded in the last few seconds, for types registered with
+    /// [`DebugAssetReloadsAppExt::debug_asset_reloads`].
+    pub show_hot_reload_status: bool,
+    /// Show how many systems the core schedules hold, and ambiguity conflicts if the host
+    /// enabled ambiguity detection. Also logged whenever the counts change.
+    pub show_schedule_counts: bool,
+    /// Show queued, ready and failed render pipeline counts, for watching shader warm-up.
+    /// Native only.
+    pub show_pipeline_cache: bool,
+    /// Draw an arrow for every [`ContactNormal`].
+    pub show_contact_normals: bool,
+    /// Arrow length per unit of penetration depth.
+    pub contact_normal_scale: f32,
+    /// Longest a contact arrow gets, however deep the contact.
+    pub contact_normal_max_length: f32,
+    /// Draw every camera's view frustum as a wireframe, one color per face, in
+    /// [`DebugLevel::Full`]. Cameras without a far plane are skipped.
+    pub show_camera_frustum: bool,
+    /// When set, log every active debug line at this interval.
+    pub log_interval: Option<Duration>,
+    /// Only log lines whose message changed since the previous periodic log.
+    pub log_only_changed: bool,
+    /// Color keys and values in periodic log lines. Defaults to on outside wasm.
+    pub ansi_colors: bool,
+    /// Placeholder [`DebugTextWriter::write_optional`] shows for a missing value.
+    pub none_str: String,
+    /// Overlay self-cost above this many ms per frame is shown in yellow.
+    pub overlay_cost_warn_ms: f64,
+    /// Horizontal px left empty between frametime chart bars.
+    pub chart_bar_gap: f32,
+    /// Width in px of the frametime chart. A window with more frames than the chart has
+    /// physical pixels is downsampled to one bar per pixel, keeping each bar's worst frame.
+    pub chart_width_px: f32,
+    /// Which frame time series the chart draws.
+    pub chart_series: ChartSeries,
+    pub chart_color_ramp: ChartColorRamp,
+    pub chart_placement: ChartPlacement,
+    /// Window event categories recorded into [`TimelineEvents`] and marked on the chart.
+    pub timeline_categories: TimelineCategories,
+    /// Width in px of the frametime chart's bars and axes.
+    pub gizmo_line_width: f32,
+    /// Append a [`DebugSnapshot`] to [`DebugRecording`] every frame.
+    pub record_snapshots: bool,
+    /// How many evicted line entities to keep hidden for reuse instead of despawning.
+    pub max_pooled_lines: usize,
+    /// Lines wider than this many px are cut off with "…", or wrapped when written with
+    /// [`DebugTextWriter::write_wrapped`]. Clicking a cut-off line expands it. `None` uses
+    /// the window width minus the margins.
+    pub max_line_width_px: Option<f32>,
+    /// Line slots allocated beyond this log a warning, at most every 10 seconds. Each new key
+    /// takes a slot for good, so keys with e.g. entity ids in them grow the stack forever.
+    pub max_debug_lines: usize,
+    /// Rows a [`DebugTextWriter::write_table`] table shows before the rest collapse into a
+    /// "… +N more" footer.
+    pub table_max_rows: usize,
+    /// Frame rate the overlay's thresholds are set against: the "Frames >Xms" budget line,
+    /// the chart colors, the FPS text and health dot colors and the FPS dial's scale.
+    pub fps_target: f64,
+    /// Refresh interval in ms that vsync near misses are measured against. `None` reads it
+    /// from the primary monitor.
+    pub refresh_interval_ms: Option<f64>,
+    /// Show frametime avg and max on two lines, as before min was added, instead of one
+    /// "min / avg / max" line. For scripts that parse overlay screenshots.
+    pub split_frametime_lines: bool,
+    /// Show a dot in the top-right corner: green at or above `fps_target`, yellow above half
+    /// of it, pulsing red below that.
+    pub show_health_dot: bool,
+    /// Center of the [`DebugWidgets::FPS_DIAL`] gauge, in px from the top-left. Its scale
+    /// runs to 1.5× `fps_target`.
+    pub fps_dial_center: Vec2,
+    pub fps_dial_radius: f32,
+    /// Standard deviations above the window average a frame time has to be to count as a
+    /// [`FrameSpike`].
+    pub spike_k_factor: f64,
+    /// Minutes of per-minute medians compared by the throttling check.
+    pub throttle_window_minutes: usize,
+    /// FPS drop, in percent, across a steadily slowing window before throttling is flagged.
+    pub throttle_warn_percent: f64,
+    /// [`GlobalZIndex`] given to every overlay root node so it stacks above the host's UI.
+    pub overlay_z_index: i32,
+    /// Render the overlay through its own 2D camera on this [`RenderLayers`] layer.
+    ///
+    /// The camera has a higher order than anything the host is likely to spawn, so text and
+    /// the frametime chart land on top even when the host drives its UI from another camera.
+    /// Off by default: the extra camera costs a full render pass, and a host relying on the
+    /// implicit default UI camera should mark its own with [`IsDefaultUiCamera`] so its UI
+    /// isn't picked up by the overlay camera instead.
+    pub overlay_camera_layer: Option<usize>,
+}
+
+impl DebugVisConfig {
+    /// Frame time at `fps_target`, in ms.
+    pub fn frame_budget_ms(&self) -> f64 {
+        1000.0 / self.fps_target
+    }
+}
+
+impl Default for DebugVisConfig {
+    fn default() -> Self {
+        Self {
+            safe_area: UiRect::default(),
+            auto_anchor: false,
+            frame_time_source: FrameTimeSource::default(),
+            diff_color: Color::srgb(1.0, 1.0, 0.0),
+            flash_on_change: false,
+            show_diff: false,
+            new_entry_color: Color::srgb(0.0, 1.0, 1.0),
+            diff_linger_frames: DIFF_HIGHLIGHT_FRAMES,
+            align_columns: true,
+            crash_dump: false,
+            remote_enabled: false,
+            remote_port: 15702,
+            text_shadow_offset: Vec2::new(1.0, 1.0),
+            text_shadow_color: Color::srgb(0.0, 0.0, 0.0),
+            show_cursor_world_pos: false,
+            show_archetype_count: false,
+            show_query_stats: false,
+            show_time_scale: false,
+            show_queue_latency: false,
+            show_hot_reload_status: false,
+            show_pipeline_cache: false,
+            show_schedule_counts: false,
+            show_contact_normals: false,
+            show_camera_frustum: false,
+            contact_normal_scale: 10.0,
+            contact_normal_max_length: 1.0,
+            cursor_plane_height: 0.0,
+            cursor_plane_normal: Vec3::Y,
+            log_interval: None,
+            log_only_changed: false,
+            ansi_colors: cfg!(not(target_arch = "wasm32")),
+            none_str: "--".to_string(),
+            overlay_cost_warn_ms: 0.5,
+            chart_bar_gap: 0.0,
+            chart_width_px: 300.0,
+            chart_series: ChartSeries::Raw,
+            chart_color_ramp: ChartColorRamp::Budget,
+            chart_placement: ChartPlacement::TopLeft,
+            timeline_categories: TimelineCategories::default(),
+            gizmo_line_width: 2.0,
+            record_snapshots: false,
+            max_pooled_lines: 32,
+            max_line_width_px: None,
+            table_max_rows: 10,
+            max_debug_lines: 256,
+            fps_target: 60.0,
+            refresh_interval_ms: None,
+            split_frametime_lines: false,
+            show_health_dot: false,
+            fps_dial_center: Vec2::new(360.0, 60.0),
+            fps_dial_radius: 40.0,
+            spike_k_factor: 2.0,
+            throttle_window_minutes: 10,
+            throttle_warn_percent: 10.0,
+            overlay_z_index: 10_000,
+            overlay_camera_layer: None,
+        }
+    }
+}
+
 impl Plugin for DebugVisPlugin {
     fn build(&self, app: &mut App) {
+        if cfg!(feature = "debug-vis") {
+            self.build_overlay(app);
+        } else {
+            // Keep `DebugTextWriter` usable so call sites don't need their own cfg.
+            app.init_resource::<DebugTexts>()
+                .init_resource::<DebugLevel>()
+                .init_resource::<DebugVisConfig>()
+                .init_resource::<DebugWidgets>()
+                .init_resource::<DebugLineFilter>();
+        }
+    }
+}
+
+impl DebugVisPlugin {
+    fn build_overlay(&self, app: &mut App) {
+        if let Some(level) = self.initial_level {
+            app.insert_resource(level);
+        }
+        if let Some(keybindings) = &self.keybindings {
+            app.insert_resource(keybindings.clone());
+        }
+        app.insert_resource(OverlayWindow(self.window));
+
+        // Hosts often register the diagnostics plugin themselves, and adding it twice panics.
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+        }
+
         app.init_resource::<DebugTexts>()
             .init_resource::<DebugLevel>()
+            .init_resource::<DebugVisConfig>()
             .init_resource::<FrameTimeHistory>()
+            .init_resource::<SessionStats>()
+            .init_resource::<ManualFrameTimes>()
+            .init_resource::<RecordingEnabled>()
+            .init_resource::<ThrottleDetector>()
+            .init_resource::<inspect::DebugDisplayRegistry>()
+            .init_resource::<asset_reloads::AssetReloadLog>()
+            .init_resource::<picking::InspectPicking>()
+            .init_resource::<DebugLineFilter>()
+            .init_resource::<DebugProfiles>()
+            .register_debug_display::<Name>()
+            .register_debug_display::<Transform>()
+            .register_debug_display::<GlobalTransform>()
+            .register_debug_display::<Visibility>()
+            .init_resource::<FpsWindow>()
+            .init_resource::<QueueLatencyHistory>()
+            .init_resource::<QueryStats>()
+            .init_resource::<TimelineEvents>()
+            .init_resource::<vsync::RefreshInterval>()
+            .init_resource::<OverlayCost>()
+            .init_resource::<DebugRecording>()
+            .init_resource::<DebugKeybindings>()
+            .init_resource::<DebugWidgets>()
+            .init_resource::<help_panel::HelpPanelState>()
+            .init_resource::<help_panel::HelpHighlight>()
+            .init_resource::<DebugCommandRegistry>()
+            .init_resource::<palette::PaletteState>()
+            .init_resource::<shapes::DebugShapes>()
+            .init_resource::<ScenarioRegistry>()
+            .init_resource::<ActiveScenario>()
+            .add_message::<DebugLevelChanged>()
+            .add_message::<DebugAction>()
+            .add_message::<FrameSpike>()
+            .add_message::<BimodalPacingDetected>()
+            .add_message::<ScenarioFailed>()
             .init_gizmo_group::<DebugTopGizmoGroup>()
-            .add_plugins(FrameTimeDiagnosticsPlugin::default())
-            .add_systems(Startup, (spawn_fps_display, setup_debug_top_gizmo_config))
+            .init_gizmo_group::<DebugWorldGizmoGroup>()
+            .add_observer(layer_overlay_root::<OverlayAnchor>)
+            .add_observer(layer_overlay_root::<help_panel::HelpPanel>)
+            .add_observer(layer_overlay_root::<palette::CommandPalette>)
+            .add_observer(click_line)
+            .add_systems(
+                Startup,
+                (
+                    spawn_overlay_camera,
+                    spawn_fps_display.after(spawn_overlay_camera),
+                ),
+            )
             .add_systems(
                 Update,
                 (
-                    update_frame_time_history,
-                    update_fps_display,
+                    emit_debug_level_changed,
+                    update_frame_time_history.run_if(recording_enabled),
+                    update_fps_window.run_if(recording_enabled),
+                    update_fps_display.after(update_fps_window),
                     update_frametime_consistency_display.after(update_frame_time_history),
-                    // drain_debug_queue,
-                    // cleanup_stale_debug_texts,
-                    // toggle_debug_level,
-                    // apply_debug_visibility,
+                    apply_frametime_layout.before(apply_safe_area),
+                    apply_safe_area,
+                    tick_diff_highlights,
+                    tick_flashes,
+                    update_label_column_width,
+                    write_time_scale,
+                    vsync::update_refresh_interval,
+                    vsync::write_vsync_misses
+                        .after(vsync::update_refresh_interval)
+                        .after(update_frame_time_history),
+                    periodic_log_summary,
+                    drain_debug_queue,
+                    cleanup_stale_debug_texts,
+                    keybindings::warn_keybinding_conflicts,
+                    keybindings::read_debug_keys,
+                    toggle_debug_level.after(keybindings::read_debug_keys),
+                    apply_debug_visibility.after(toggle_debug_level),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    help_panel::handle_help_actions.after(keybindings::read_debug_keys),
+                    help_panel::sync_help_panel.after(help_panel::handle_help_actions),
+                    help_panel::tick_help_highlight.after(help_panel::sync_help_panel),
+                    write_archetype_count,
+                    update_frame_clock_display,
+                    update_health_dot.after(update_fps_window),
+                    throttle::analyze_throttling.after(update_frame_time_history),
+                    inspect::write_inspected_components,
+                    asset_reloads::write_asset_reloads,
+                    filter::write_line_filter,
+                    spikes::detect_frame_spikes.after(update_frame_time_history),
+                    spikes::write_frame_spikes.after(spikes::detect_frame_spikes),
+                    profiles::cycle_profile.after(keybindings::read_debug_keys),
+                    profiles::apply_active_profile
+                        .after(profiles::cycle_profile)
+                        .before(apply_debug_visibility),
+                    profiles::write_active_profile.after(profiles::apply_active_profile),
+                    picking::toggle_picking.after(keybindings::read_debug_keys),
+                    picking::pick_inspect_target
+                        .after(picking::toggle_picking)
+                        .before(inspect::write_inspected_components),
+                    toggle_recording.after(keybindings::read_debug_keys),
+                    setup_debug_top_gizmo_config.run_if(resource_changed::<DebugVisConfig>),
+                    widgets::toggle_widget_chords
+                        .after(keybindings::read_debug_keys)
+                        .before(apply_debug_visibility),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    palette::toggle_palette.after(keybindings::read_debug_keys),
+                    palette::navigate_palette.after(palette::toggle_palette),
+                    palette::sync_palette.after(palette::navigate_palette),
+                    drag_chart,
+                    timeline::record_timeline_events,
+                    pacing::detect_bimodal_pacing.after(update_frame_time_history),
+                    apply_text_shadow_changes.run_if(resource_changed::<DebugVisConfig>),
+                    auto_anchor::auto_anchor_debug_lines.after(cleanup_stale_debug_texts),
+                    scenario::write_active_scenario,
+                    update_session_stats.after(spikes::detect_frame_spikes),
+                    replay_deferred_writes
+                        .after(toggle_debug_level)
+                        .after(profiles::apply_active_profile),
                 ),
             )
             .add_systems(
                 PostUpdate,
-                draw_frametime_barchart
+                (
+                    draw_frametime_barchart,
+                    dial::draw_fps_dial,
+                    draw_cursor_gizmo,
+                    draw_contact_normals,
+                    draw_camera_frustum.after(VisibilitySystems::UpdateFrusta),
+                    picking::draw_picked_bounds,
+                    shapes::draw_debug_shapes,
+                ),
+            )
+            .register_diagnostic(Diagnostic::new(DEBUG_VIS_LINE_COUNT))
+            .register_diagnostic(Diagnostic::new(DEBUG_VIS_LINES_ALLOCATED))
+            .add_systems(
+                Last,
+                (
+                    schedules::write_schedule_counts.before(highlight_frame_diff),
+                    update_debug_rates.before(highlight_frame_diff),
+                    query_stats::write_query_stats.before(fit_debug_lines),
+                    fit_debug_lines
+                        .after(schedules::write_schedule_counts)
+                        .after(update_debug_rates),
+                    highlight_frame_diff,
+                    update_overlay_cost_display,
+                    record_debug_snapshot,
+                    measure_line_count,
+                    warn_line_overflow,
+                ),
             );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.init_resource::<capture::OverlayCapture>().add_systems(
+            Update,
+            capture::capture_overlay.after(keybindings::read_debug_keys),
+        );
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_plugins(pipelines::DebugVisPipelinesPlugin);
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(soak) = &self.soak {
+            app.add_plugins(soak::DebugVisSoakPlugin(soak.clone()));
+        }
+
+        #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+        app.add_plugins(baseline::DebugVisBaselinePlugin(self.baseline.clone()));
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = &self.replay {
+            app.add_plugins(replay::DebugVisReplayPlugin(path.clone()));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(sweep) = &self.sweep {
+            app.add_plugins(sweep::DebugVisSweepPlugin(sweep.clone()));
+        }
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if app.world().resource::<DebugVisConfig>().crash_dump {
+            crash_dump::install(app);
+        }
+
+        #[cfg(feature = "egui")]
+        app.add_plugins(egui_table::DebugVisEguiPlugin);
+
+        #[cfg(all(feature = "remote", not(target_arch = "wasm32")))]
+        app.add_plugins(remote::DebugVisRemotePlugin);
+
+        #[cfg(all(feature = "battery", target_os = "linux"))]
+        app.add_plugins(power::DebugVisPowerPlugin);
+
+        #[cfg(feature = "hot_reload")]
+        app.add_plugins(hot_reload::DebugVisHotReloadPlugin);
+
+        #[cfg(feature = "wgpu_timestamps")]
+        app.add_plugins(gpu_timestamps::DebugVisGpuTimestampsPlugin);
     }
 }
 
+/// Every debug line currently written through [`DebugTextWriter`].
 #[derive(Resource, Default)]
 struct DebugTexts {
+    /// [`FrameCount`] as of the last cleanup, so line ages agree with frame numbers
+    /// everywhere else.
     frame: u64,
     next_line: usize,
     line_lookup: HashMap<String, usize>,
     entries: HashMap<String, DebugEntry>,
+    label_column_width: f32,
+    /// Hidden line entities from evicted keys, reused by the next new key.
+    pool: Vec<PooledLine>,
+    /// Click handlers for lines written with [`DebugTextWriter::write_enum_cycle`].
+    cycles: HashMap<String, Box<dyn Fn() + Send + Sync>>,
+    tables: HashMap<String, DebugTable>,
+    /// Lines taller than one row (tables), keyed by line, with how many rows they take.
+    line_spans: HashMap<usize, usize>,
+    /// Top-left px positions of lines written with [`DebugTextWriter::write_at_pixel`].
+    pinned: HashMap<String, Vec2>,
+    /// Pinned lines drawn as part of a widget, such as the chart's axis labels. They follow
+    /// that widget's toggle rather than [`DebugWidgets::DEBUG_LINES`] and the line filter.
+    widget_lines: HashMap<String, DebugWidgets>,
+    rates: HashMap<String, RateCounter>,
+    /// Cut-off lines clicked open, shown wrapped until clicked again.
+    expanded: HashSet<String>,
+    /// Latest write per key while [`DebugLevel::Hidden`], replayed onto entities once the
+    /// overlay is shown again.
+    deferred: HashMap<String, DeferredWrite>,
+    /// Screen corner the lines stack from; moved by [`DebugVisConfig::auto_anchor`].
+    corner: StackCorner,
 }
 
-struct DebugEntry {
-    entity: Entity,
-    line: usize,
-    last_frame: u64,
-    persistent: bool,
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+enum StackCorner {
+    #[default]
+    BottomLeft,
+    BottomRight,
+    TopLeft,
+    TopRight,
 }
 
-#[derive(Component)]
-struct DebugLabel(String);
+impl StackCorner {
+    /// In order of preference, nearest the default first.
+    const ALL: [Self; 4] = [
+        Self::BottomLeft,
+        Self::BottomRight,
+        Self::TopLeft,
+        Self::TopRight,
+    ];
 
-/// System param helper to write/update debug text lines.
-#[derive(SystemParam)]
-pub struct DebugTextWriter<'w, 's> {
-    commands: Commands<'w, 's>,
-    texts: ResMut<'w, DebugTexts>,
-    level: Res<'w, DebugLevel>,
+    fn is_right(self) -> bool {
+        matches!(self, Self::BottomRight | Self::TopRight)
+    }
+
+    fn is_top(self) -> bool {
+        matches!(self, Self::TopLeft | Self::TopRight)
+    }
 }
 
-impl<'w, 's> DebugTextWriter<'w, 's> {
-    pub fn write(&mut self, key: impl Into<String>, message: impl Into<String>) {
-        self.write_with_persistence(key, message, false);
+impl DebugTexts {
+    /// Number of active debug lines, also published as [`DEBUG_VIS_LINE_COUNT`].
+    pub(super) fn line_count(&self) -> usize {
+        self.entries.len()
     }
 
-    pub fn write_with_persistence(
-        &mut self,
-        key: impl Into<String>,
-        message: impl Into<String>,
-        persistent: bool,
-    ) {
-        let key = key.into();
-        let message = message.into();
-        let frame = self.texts.frame;
+    /// Removes the line under `key`, parking its entity in the pool while there is room.
+    fn evict(&mut self, key: &str, commands: &mut Commands, max_pooled_lines: usize) {
+        self.cycles.remove(key);
+        self.pinned.remove(key);
+        self.widget_lines.remove(key);
+        self.rates.remove(key);
+        self.expanded.remove(key);
+        let Some(entry) = self.entries.remove(key) else {
+            return;
+        };
 
-        if let Some(entry) = self.texts.entries.get_mut(&key) {
-            self.commands
+        if self.pool.len() < max_pooled_lines {
+            commands
                 .entity(entry.entity)
-                .insert(Text::new(message));
-            entry.last_frame = frame;
-            entry.persistent |= persistent;
+                .insert((PooledDebugLine, Visibility::Hidden));
+            self.pool.push(PooledLine {
+                entity: entry.entity,
+                label_entity: entry.label_entity,
+                value_entity: entry.value_entity,
+            });
         } else {
-            let line = if let Some(line) = self.texts.line_lookup.get(&key) {
-                *line
-            } else {
-                let line = self.texts.next_line;
-                self.texts.next_line += 1;
-                self.texts.line_lookup.insert(key.clone(), line);
-                line
-            };
+            commands.entity(entry.entity).despawn();
+        }
+    }
 
-            let visibility = if *self.level == DebugLevel::Full {
-                Visibility::Inherited
-            } else {
-                Visibility::Hidden
-            };
+    /// Evicts every line whose key starts with `prefix`.
+    fn evict_prefix(&mut self, prefix: &str, commands: &mut Commands, max_pooled_lines: usize) {
+        let keys: Vec<String> = self
+            .entries
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect();
+        for key in keys {
+            self.evict(&key, commands, max_pooled_lines);
+        }
+    }
 
-            let entity = self
-                .commands
-                .spawn((
-                    DebugLabel(key.clone()),
-                    Text::new(message),
-                    TextFont {
-                        font_size: 16.0,
-                        ..default()
-                    },
-                    TextColor(Color::srgb(0.0, 1.0, 0.0)),
-                    TextShadow{
-                        offset: Vec2::new(1.0, 1.0),
-                        color: Color::srgb(0.0, 0.0, 0.0),
-                    },
-                    Node {
-                        position_type: PositionType::Absolute,
-                        bottom: Val::Px(line as f32 * LINE_HEIGHT),
-                        left: Val::Px(LEFT_PADDING),
-                        ..default()
-                    },
-                    visibility,
-                ))
-                .id();
+    fn line_for(&mut self, key: &str) -> usize {
+        if let Some(line) = self.line_lookup.get(key) {
+            return *line;
+        }
+        let line = self.next_line;
+        self.next_line += 1;
+        self.line_lookup.insert(key.to_string(), line);
+        line
+    }
 
-            self.texts.entries.insert(
-                key,
-                DebugEntry {
-                    entity,
-                    line,
-                    last_frame: frame,
-                    persistent,
-                },
-            );
+    /// Lines stack away from the [`StackCorner`], so each line sits past every row taken by
+    /// the lines before it.
+    fn line_anchor(&self, line: usize) -> UiRect {
+        let extra_rows: usize = self
+            .line_spans
+            .iter()
+            .filter(|(spanned, _)| **spanned < line)
+            .map(|(_, span)| span - 1)
+            .sum();
+        let offset = (line + extra_rows) as f32 * LINE_HEIGHT;
+        let (left, right) = if self.corner.is_right() {
+            (Val::Auto, Val::Px(LEFT_PADDING))
+        } else {
+            (Val::Px(LEFT_PADDING), Val::Auto)
+        };
+        let (top, bottom) = if self.corner.is_top() {
+            (Val::Px(TOP_STACK_OFFSET + offset), Val::Auto)
+        } else {
+            (Val::Auto, Val::Px(offset))
+        };
+        UiRect::new(left, right, top, bottom)
+    }
+
+    /// Moves every line after `line` to its current anchor, after a table above it grew or
+    /// shrank.
+    fn reposition_after(&self, line: usize, commands: &mut Commands, safe_area: &UiRect) {
+        self.reposition_where(|other_line| other_line > line, commands, safe_area);
+    }
+
+    fn reposition_where(
+        &self,
+        moves: impl Fn(usize) -> bool,
+        commands: &mut Commands,
+        safe_area: &UiRect,
+    ) {
+        let entries = self.entries.values().map(|entry| (entry.line, entry.entity));
+        let tables = self.tables.values().map(|table| (table.line, table.entity));
+        for (other_line, entity) in entries.chain(tables) {
+            if other_line == PINNED_LINE || !moves(other_line) {
+                continue;
+            }
+            let anchor = self.line_anchor(other_line);
+            let (anchored, _) = anchored_node(anchor, safe_area);
+            commands
+                .entity(entity)
+                .insert(OverlayAnchor(anchor))
+                .entry::<Node>()
+                .and_modify(move |mut node| {
+                    node.left = anchored.left;
+                    node.right = anchored.right;
+                    node.top = anchored.top;
+                    node.bottom = anchored.bottom;
+                });
+        }
+    }
+}
+
+struct DeferredWrite {
+    label: Option<String>,
+    message: String,
+    persistent: bool,
+    last_frame: u64,
+}
+
+impl DeferredWrite {
+    fn text(&self) -> String {
+        match &self.label {
+            Some(label) => format!("{label}{}", self.message),
+            None => self.message.clone(),
         }
     }
 }
 
+/// Event counts behind a [`DebugTextWriter::write_rate`] line, bucketed per second.
+struct RateCounter {
+    label: String,
+    /// Events counted since the current bucket started.
+    pending: u32,
+    bucket_start: Option<Duration>,
+    /// Completed buckets, oldest first.
+    per_second: VecDeque<u32>,
+}
+
+struct DebugTable {
+    entity: Entity,
+    line: usize,
+    last_frame: u64,
+    columns: usize,
+    /// Cell text entities in row-major order, header row first, with their current text.
+    cells: Vec<(Entity, String)>,
+    footer: Option<(Entity, String)>,
+}
+
+struct PooledLine {
+    entity: Entity,
+    label_entity: Option<Entity>,
+    value_entity: Entity,
+}
+
+/// Marks a line entity parked in [`DebugTexts::pool`].
 #[derive(Component)]
-struct FpsText;
+struct PooledDebugLine;
+
+struct DebugEntry {
+    /// Row entity carrying the line's position and visibility.
+    entity: Entity,
+    /// Label child of the row, when the line is split into columns.
+    label_entity: Option<Entity>,
+    /// Entity holding the value text; the row itself when the line isn't split.
+    value_entity: Entity,
+    line: usize,
+    last_frame: u64,
+    persistent: bool,
+    label: Option<String>,
+    message: String,
+    diff_countdown: u8,
+    flash_remaining: f32,
+    /// Fixed row width set by [`DebugTextWriter::write_with_size_hint`].
+    size_hint: Option<f32>,
+    /// Written with [`DebugTextWriter::write_wrapped`].
+    wrap: bool,
+    /// Too wide for [`DebugVisConfig::max_line_width_px`] as of the last fit.
+    overflows: bool,
+    /// Last value written with [`DebugTextWriter::write_bool`], so the color is only set when
+    /// it flips.
+    bool_value: Option<bool>,
+}
+
+impl DebugEntry {
+    /// Color the value returns to once a highlight or flash ends.
+    fn base_color(&self) -> Color {
+        match self.bool_value {
+            Some(value) => bool_color(value),
+            None => DEBUG_TEXT_COLOR,
+        }
+    }
+
+    fn text(&self) -> String {
+        match &self.label {
+            Some(label) => format!("{label}{}", self.message),
+            None => self.message.clone(),
+        }
+    }
+}
+
+/// How a write should call attention to a changed message.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Highlight {
+    None,
+    Diff,
+    Flash,
+}
 
 #[derive(Component)]
-struct FrametimeConsistencyText;
+struct DebugLabel(String);
 
+/// Label half of a line split by [`DebugVisConfig::align_columns`].
 #[derive(Component)]
-struct FrametimeMaxDeltaText;
+struct DebugLabelColumn;
 
-#[derive(Resource, Default)]
-struct FrameTimeHistory {
-    frame_times_ms: VecDeque<f64>,
-    sum_seconds: f64,
+/// Unadjusted position of an overlay node, before the safe area is applied.
+#[derive(Component, Clone, Copy)]
+struct OverlayAnchor(UiRect);
+
+fn sparkline(data: &[f32]) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let (min, max) = data
+        .iter()
+        .copied()
+        .filter(|value| value.is_finite())
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), value| {
+            (min.min(value), max.max(value))
+        });
+    let range = max - min;
+
+    data.iter()
+        .map(|&value| {
+            if !value.is_finite() {
+                return ' ';
+            }
+            // A flat series sits on the baseline rather than dividing by zero.
+            let ratio = if range > 0.0 { (value - min) / range } else { 0.0 };
+            BLOCKS[(ratio * (BLOCKS.len() - 1) as f32).round() as usize]
+        })
+        .collect()
 }
 
-fn spawn_fps_display(mut commands: Commands, level: Res<DebugLevel>) {
-    let visibility = if *level == DebugLevel::Hidden {
-        Visibility::Hidden
+/// Anchor for a line pinned at `position` by [`DebugTextWriter::write_at_pixel`].
+fn pixel_anchor(position: Vec2) -> UiRect {
+    UiRect::new(
+        Val::Px(position.x),
+        Val::Auto,
+        Val::Px(position.y),
+        Val::Auto,
+    )
+}
+
+/// Green for `true`, red for `false`, for yes/no values written with
+/// [`DebugTextWriter::write_bool`].
+fn bool_color(value: bool) -> Color {
+    if value {
+        HEALTH_GOOD_COLOR
     } else {
-        Visibility::Inherited
-    };
+        HEALTH_BAD_COLOR
+    }
+}
 
-    commands.spawn((
-        FpsText,
-        Text::new("FPS: --"),
+/// `18204` as `"18,204"`.
+fn group_thousands(value: u32) -> String {
+    let digits = value.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+fn offset_val(base: Val, inset: Val) -> Val {
+    match (base, inset) {
+        (Val::Px(base), Val::Px(inset)) => Val::Px(base + inset),
+        (base, _) => base,
+    }
+}
+
+fn anchored_node(anchor: UiRect, safe_area: &UiRect) -> (Node, OverlayAnchor) {
+    (
+        Node {
+            position_type: PositionType::Absolute,
+            left: offset_val(anchor.left, safe_area.left),
+            right: offset_val(anchor.right, safe_area.right),
+            top: offset_val(anchor.top, safe_area.top),
+            bottom: offset_val(anchor.bottom, safe_area.bottom),
+            ..default()
+        },
+        OverlayAnchor(anchor),
+    )
+}
+
+/// Read-only view of the debug lines, for systems that inspect overlay state without
+/// writing to it.
+#[derive(SystemParam)]
+pub struct DebugTextsReader<'w> {
+    texts: Res<'w, DebugTexts>,
+}
+
+impl DebugTextsReader<'_> {
+    /// The [`FrameCount`] as of the last cleanup; lines remember the frame they were last
+    /// written in.
+    pub fn frame(&self) -> u64 {
+        self.texts.frame
+    }
+
+    /// Number of active debug lines, also published as [`DEBUG_VIS_LINE_COUNT`].
+    pub fn entry_count(&self) -> usize {
+        self.texts.line_count()
+    }
+
+    /// Text of the line under `key` as shown, label column included. While the overlay is
+    /// hidden, the text it will show once visible again.
+    pub fn get(&self, key: &str) -> Option<String> {
+        match self.texts.deferred.get(key) {
+            Some(write) => Some(write.text()),
+            None => self.texts.entries.get(key).map(DebugEntry::text),
+        }
+    }
+
+    /// Every line as `(key, text)`, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, String)> + '_ {
+        let deferred = &self.texts.deferred;
+        let shown = self
+            .texts
+            .entries
+            .iter()
+            .filter(|(key, _)| !deferred.contains_key(*key))
+            .map(|(key, entry)| (key.as_str(), entry.text()));
+        shown.chain(deferred.iter().map(|(key, write)| (key.as_str(), write.text())))
+    }
+}
+
+/// Window the overlay is shown on; `None` for the primary window.
+#[derive(Resource)]
+struct OverlayWindow(Option<Entity>);
+
+/// The window the overlay is shown on and the scene camera rendering to it.
+#[derive(SystemParam)]
+struct OverlayView<'w, 's> {
+    overlay_window: Res<'w, OverlayWindow>,
+    primary_window: Query<'w, 's, Entity, With<PrimaryWindow>>,
+    windows: Query<'w, 's, &'static Window>,
+    cameras: Query<
+        'w,
+        's,
+        (Entity, &'static Camera, &'static GlobalTransform, &'static RenderTarget),
+        Without<DebugOverlayCamera>,
+    >,
+}
+
+impl OverlayView<'_, '_> {
+    fn window_entity(&self) -> Option<Entity> {
+        self.overlay_window
+            .0
+            .or_else(|| self.primary_window.single().ok())
+    }
+
+    fn window(&self) -> Option<&Window> {
+        self.windows.get(self.window_entity()?).ok()
+    }
+
+    /// Camera overlay UI has to target explicitly when there is no overlay camera. Only a
+    /// non-primary window needs one; the default UI camera already covers the primary.
+    fn ui_camera(&self) -> Option<Entity> {
+        self.overlay_window.0?;
+        self.camera().map(|(camera, _, _)| camera)
+    }
+
+    /// Highest-order active camera rendering to the overlay's window.
+    fn camera(&self) -> Option<(Entity, &Camera, &GlobalTransform)> {
+        let window = self.window_entity();
+        let primary = self.primary_window.single().ok();
+        self.cameras
+            .iter()
+            .filter(|(_, camera, _, target)| {
+                camera.is_active
+                    && match target {
+                        RenderTarget::Window(WindowRef::Primary) => primary == window,
+                        RenderTarget::Window(WindowRef::Entity(entity)) => Some(*entity) == window,
+                        _ => false,
+                    }
+            })
+            .max_by_key(|(_, camera, _, _)| camera.order)
+            .map(|(entity, camera, transform, _)| (entity, camera, transform))
+    }
+}
+
+/// System param helper to write/update debug text lines.
+///
+/// Keys are flat snake_case, like the overlay's own ("time_scale", "chart_p99"). Lines that
+/// belong together share a prefix ("ai_enemy_3_state"), so a module's lines can be cleared
+/// with [`clear_prefix`](Self::clear_prefix) or singled out with a [`DebugLineFilter`].
+#[derive(SystemParam)]
+pub struct DebugTextWriter<'w, 's> {
+    commands: Commands<'w, 's>,
+    texts: ResMut<'w, DebugTexts>,
+    level: Res<'w, DebugLevel>,
+    widgets: Res<'w, DebugWidgets>,
+    filter: Res<'w, DebugLineFilter>,
+    config: Res<'w, DebugVisConfig>,
+}
+
+impl<'w, 's> DebugTextWriter<'w, 's> {
+    /// Writes `message` under `key`. With column alignment enabled, everything before the
+    /// first `": "` is treated as the label.
+    pub fn write(&mut self, key: impl Into<String>, message: impl Into<String>) {
+        self.write_with_persistence(key, message, false);
+    }
+
+    /// Removes every line whose key starts with `prefix`, e.g. `"ai_"`. Lines written under
+    /// the prefix later in the same frame are kept.
+    pub fn clear_prefix(&mut self, prefix: &str) {
+        let max_pooled_lines = self.config.max_pooled_lines;
+        self.texts
+            .evict_prefix(prefix, &mut self.commands, max_pooled_lines);
+    }
+
+    /// Writes a line whose `value` is aligned into the shared value column.
+    pub fn write_kv(
+        &mut self,
+        key: impl Into<String>,
+        label: impl Into<String>,
+        value: impl Into<String>,
+    ) {
+        let highlight = self.default_highlight();
+        self.write_entry(
+            key.into(),
+            Some(label.into()),
+            value.into(),
+            false,
+            highlight,
+        );
+    }
+
+    /// Writes "label: value", with floats to two decimals, or "label: --" (see
+    /// [`DebugVisConfig::none_str`]) when there is no value yet.
+    pub fn write_optional<T: fmt::Debug>(
+        &mut self,
+        key: impl Into<String>,
+        label: &str,
+        value: Option<T>,
+    ) {
+        let message = match value {
+            Some(value) => format!("{label}: {value:.2?}"),
+            None => format!("{label}: {}", self.config.none_str),
+        };
+        self.write(key, message);
+    }
+
+    /// Writes "label: ✓" with the mark in green, or "label: ✗" in red.
+    pub fn write_bool(&mut self, key: impl Into<String>, label: &str, value: bool) {
+        let key = key.into();
+        let mark = if value { "✓" } else { "✗" };
+        let highlight = self.default_highlight();
+        self.write_split(key.clone(), format!("{label}: {mark}"), false, highlight);
+        // Missing while hidden; the color catches up on the first write once shown.
+        let Some(entry) = self.texts.entries.get_mut(&key) else {
+            return;
+        };
+        if *self.level != DebugLevel::Hidden && entry.bool_value != Some(value) {
+            entry.bool_value = Some(value);
+            if entry.flash_remaining <= 0.0 && entry.diff_countdown == 0 {
+                self.commands
+                    .entity(entry.value_entity)
+                    .insert(TextColor(bool_color(value)));
+            }
+        }
+    }
+
+    /// Like [`write`](Self::write), but highlights the line in `diff_color` for a few frames
+    /// whenever `message` differs from the previous one written under `key`.
+    pub fn write_diff(&mut self, key: impl Into<String>, message: impl Into<String>) {
+        self.write_split(key.into(), message.into(), false, Highlight::Diff);
+    }
+
+    /// Like [`write`](Self::write), but briefly brightens the line whenever `message`
+    /// differs from the previous one written under `key`.
+    pub fn write_flash(&mut self, key: impl Into<String>, message: impl Into<String>) {
+        self.write_split(key.into(), message.into(), false, Highlight::Flash);
+    }
+
+    /// Like [`write`](Self::write), but pins the line to `width_px` and clips anything past
+    /// it, so a value whose length changes every frame doesn't force the layout to resize
+    /// the node. The width sticks to `key` until it is written with a different hint.
+    pub fn write_with_size_hint(
+        &mut self,
+        key: impl Into<String>,
+        message: impl Into<String>,
+        width_px: f32,
+    ) {
+        let key = key.into();
+        let highlight = self.default_highlight();
+        self.write_split(key.clone(), message.into(), false, highlight);
+
+        let Some(entry) = self.texts.entries.get_mut(&key) else {
+            return;
+        };
+        if entry.size_hint == Some(width_px) {
+            return;
+        }
+        entry.size_hint = Some(width_px);
+        self.commands
+            .entity(entry.entity)
+            .entry::<Node>()
+            .and_modify(move |mut node| {
+                node.width = Val::Px(width_px);
+                node.overflow = Overflow::clip();
+            });
+    }
+
+    /// Like [`write`](Self::write), but places the line with its top-left corner at
+    /// `position`, in px from the window's top-left, instead of in the stack of lines. Used
+    /// to label things drawn elsewhere in the overlay. The line isn't split into columns.
+    pub fn write_at_pixel(
+        &mut self,
+        key: impl Into<String>,
+        message: impl Into<String>,
+        position: Vec2,
+    ) {
+        let key = key.into();
+        let previous = self.texts.pinned.insert(key.clone(), position);
+        if previous != Some(position)
+            && let Some(entry) = self.texts.entries.get_mut(&key)
+        {
+            let anchor = pixel_anchor(position);
+            let (anchored, _) = anchored_node(anchor, &self.config.safe_area);
+            entry.line = PINNED_LINE;
+            self.commands
+                .entity(entry.entity)
+                .insert(OverlayAnchor(anchor))
+                .entry::<Node>()
+                .and_modify(move |mut node| {
+                    node.left = anchored.left;
+                    node.top = anchored.top;
+                    node.bottom = anchored.bottom;
+                });
+        }
+        let highlight = self.default_highlight();
+        self.write_entry(key, None, message.into(), false, highlight);
+    }
+
+    /// [`Self::write_at_pixel`] for a label that belongs to `widget`: it shows and hides with
+    /// the widget, and the line filter leaves it alone.
+    fn write_widget_label(
+        &mut self,
+        widget: DebugWidgets,
+        key: impl Into<String>,
+        message: impl Into<String>,
+        position: Vec2,
+    ) {
+        let key = key.into();
+        self.texts.widget_lines.insert(key.clone(), widget);
+        self.write_at_pixel(key, message, position);
+    }
+
+    /// Writes "label: CURRENT [←→]". Clicking the line calls `on_change` with
+    /// `current.next()`; the caller applies it, and the next write shows the new value.
+    pub fn write_enum_cycle<T: Cycle>(
+        &mut self,
+        key: impl Into<String>,
+        label: impl Into<String>,
+        current: T,
+        on_change: impl Fn(T) + Send + Sync + 'static,
+    ) {
+        let key = key.into();
+        let highlight = self.default_highlight();
+        self.write_split(
+            key.clone(),
+            format!("{}: {} [←→]", label.into(), current.label()),
+            false,
+            highlight,
+        );
+        self.texts
+            .cycles
+            .insert(key, Box::new(move || on_change(current.next())));
+    }
+
+    /// Writes `data` as a one-line sparkline of block characters, scaled to its own min/max.
+    pub fn write_sparkline(&mut self, key: impl Into<String>, data: &[f32]) {
+        let highlight = self.default_highlight();
+        self.write_entry(key.into(), None, sparkline(data), false, highlight);
+    }
+
+    /// Like [`write`](Self::write), but a message too wide for
+    /// [`DebugVisConfig::max_line_width_px`] wraps onto more rows instead of being cut off,
+    /// pushing the lines above it up.
+    pub fn write_wrapped(&mut self, key: impl Into<String>, message: impl Into<String>) {
+        let key = key.into();
+        self.write(key.clone(), message);
+        if let Some(entry) = self.texts.entries.get_mut(&key) {
+            entry.wrap = true;
+        }
+    }
+
+    /// Counts `count` events towards a "label: N" events-per-second line with a sparkline
+    /// of the last few seconds. Call it wherever the events happen, once per event or with a
+    /// batch; the line keeps updating, down to 0, when the calls stop.
+    pub fn write_rate(&mut self, key: impl Into<String>, label: impl Into<String>, count: u32) {
+        if !cfg!(feature = "debug-vis") {
+            return;
+        }
+        let counter = self
+            .texts
+            .rates
+            .entry(key.into())
+            .or_insert_with(|| RateCounter {
+                label: label.into(),
+                pending: 0,
+                bucket_start: None,
+                per_second: VecDeque::new(),
+            });
+        counter.pending = counter.pending.saturating_add(count);
+    }
+
+    pub fn write_with_persistence(
+        &mut self,
+        key: impl Into<String>,
+        message: impl Into<String>,
+        persistent: bool,
+    ) {
+        let highlight = self.default_highlight();
+        self.write_split(key.into(), message.into(), persistent, highlight);
+    }
+
+    /// Writes an aligned table spanning several lines under one key. Column widths follow the
+    /// content, rows beyond [`DebugVisConfig::table_max_rows`] collapse into a footer, and
+    /// the lines after the table move whenever its row count changes.
+    pub fn write_table(&mut self, key: impl Into<String>, headers: &[&str], rows: &[Vec<String>]) {
+        if !cfg!(feature = "debug-vis") {
+            return;
+        }
+
+        let key = key.into();
+        let frame = self.texts.frame;
+        let columns = rows
+            .iter()
+            .map(Vec::len)
+            .chain([headers.len(), 1])
+            .max()
+            .unwrap_or(1);
+        let shown = rows.len().min(self.config.table_max_rows);
+        let hidden = rows.len() - shown;
+
+        let padded = |row: &mut dyn Iterator<Item = String>| {
+            row.chain(std::iter::repeat(String::new())).take(columns).collect::<Vec<_>>()
+        };
+        let mut cells = padded(&mut headers.iter().map(|header| header.to_string()));
+        for row in &rows[..shown] {
+            cells.extend(padded(&mut row.iter().cloned()));
+        }
+        let footer = (hidden > 0).then(|| format!("… +{hidden} more"));
+        let span = shown + 1 + usize::from(footer.is_some());
+
+        let line = self.texts.line_for(&key);
+        let entity = match self.texts.tables.get_mut(&key) {
+            Some(table) => {
+                table.last_frame = frame;
+                let same_shape = table.columns == columns
+                    && table.cells.len() == cells.len()
+                    && table.footer.is_some() == footer.is_some();
+                if same_shape {
+                    for ((entity, current), new) in table.cells.iter_mut().zip(cells) {
+                        if *current != new {
+                            self.commands.entity(*entity).insert(Text::new(new.clone()));
+                            *current = new;
+                        }
+                    }
+                    if let (Some((entity, current)), Some(new)) = (&mut table.footer, footer)
+                        && *current != new
+                    {
+                        self.commands.entity(*entity).insert(Text::new(new.clone()));
+                        *current = new;
+                    }
+                    return;
+                }
+                self.commands
+                    .entity(table.entity)
+                    .despawn_related::<Children>();
+                table.entity
+            }
+            None => self
+                .commands
+                .spawn((
+                    DebugLabel(key.clone()),
+                    anchored_node(self.texts.line_anchor(line), &self.config.safe_area),
+                    self.line_visibility(&key),
+                ))
+                .id(),
+        };
+
+        self.commands
+            .entity(entity)
+            .entry::<Node>()
+            .and_modify(move |mut node| {
+                node.display = Display::Grid;
+                node.grid_template_columns = RepeatedGridTrack::auto(columns as u16);
+                node.grid_auto_rows = vec![GridTrack::px(LINE_HEIGHT)];
+                node.column_gap = Val::Px(TABLE_COLUMN_GAP);
+            });
+
+        let cells: Vec<(Entity, String)> = cells
+            .into_iter()
+            .map(|cell| {
+                let cell_entity = self
+                    .commands
+                    .spawn(debug_text_bundle(cell.clone(), &self.config))
+                    .id();
+                (cell_entity, cell)
+            })
+            .collect();
+        let footer = footer.map(|footer| {
+            let footer_entity = self
+                .commands
+                .spawn((
+                    debug_text_bundle(footer.clone(), &self.config),
+                    Node {
+                        grid_column: GridPlacement::span(columns as u16),
+                        ..default()
+                    },
+                ))
+                .id();
+            (footer_entity, footer)
+        });
+        let children: Vec<Entity> = cells
+            .iter()
+            .chain(&footer)
+            .map(|(cell_entity, _)| *cell_entity)
+            .collect();
+        self.commands.entity(entity).add_children(&children);
+
+        self.texts.tables.insert(
+            key,
+            DebugTable {
+                entity,
+                line,
+                last_frame: frame,
+                columns,
+                cells,
+                footer,
+            },
+        );
+
+        if self.texts.line_spans.insert(line, span) != Some(span) {
+            self.texts
+                .reposition_after(line, &mut self.commands, &self.config.safe_area);
+        }
+    }
+
+    /// Writes a line whose value is drawn in `color` instead of the usual green.
+    fn write_colored(&mut self, key: impl Into<String>, message: impl Into<String>, color: Color) {
+        let key = key.into();
+        self.write_split(key.clone(), message.into(), false, Highlight::None);
+        self.set_value_color(&key, color);
+    }
+
+    fn set_value_color(&mut self, key: &str, color: Color) {
+        if let Some(entry) = self.texts.entries.get(key) {
+            self.commands.entity(entry.value_entity).insert(TextColor(color));
+        }
+    }
+
+    fn line_visibility(&self, key: &str) -> Visibility {
+        let shown = match self.texts.widget_lines.get(key) {
+            Some(&widget) => self.widgets.contains(widget),
+            None => self.widgets.contains(DebugWidgets::DEBUG_LINES) && self.filter.shows(key),
+        };
+        if *self.level == DebugLevel::Full && shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    }
+
+    fn default_highlight(&self) -> Highlight {
+        if self.config.flash_on_change {
+            Highlight::Flash
+        } else {
+            Highlight::None
+        }
+    }
+
+    fn write_split(&mut self, key: String, message: String, persistent: bool, highlight: Highlight) {
+        match message.split_once(": ") {
+            Some((label, value)) if self.config.align_columns => {
+                let (label, value) = (format!("{label}: "), value.to_string());
+                self.write_entry(key, Some(label), value, persistent, highlight);
+            }
+            _ => self.write_entry(key, None, message, persistent, highlight),
+        }
+    }
+
+    fn write_entry(
+        &mut self,
+        key: String,
+        label: Option<String>,
+        message: String,
+        persistent: bool,
+        highlight: Highlight,
+    ) {
+        if !cfg!(feature = "debug-vis") {
+            return;
+        }
+
+        let frame = self.texts.frame;
+        let label = label.filter(|_| self.config.align_columns);
+
+        // Hidden, nothing is spawned or restyled: the write is only recorded, and existing
+        // lines are kept alive so they don't churn through the pool.
+        if *self.level == DebugLevel::Hidden {
+            match self.texts.entries.get_mut(&key) {
+                Some(entry) => {
+                    entry.last_frame = frame;
+                    entry.persistent |= persistent;
+                }
+                None => {
+                    self.texts.line_for(&key);
+                }
+            }
+            let persistent = persistent
+                || self
+                    .texts
+                    .deferred
+                    .get(&key)
+                    .is_some_and(|write| write.persistent);
+            self.texts.deferred.insert(
+                key,
+                DeferredWrite {
+                    label,
+                    message,
+                    persistent,
+                    last_frame: frame,
+                },
+            );
+            return;
+        }
+        // Newer than anything recorded while hidden.
+        self.texts.deferred.remove(&key);
+
+        if let Some(entry) = self.texts.entries.get_mut(&key) {
+            if entry.label.is_some() != label.is_some() {
+                // Switching between split and single-string layout needs a fresh entity.
+                self.commands.entity(entry.entity).despawn();
+                self.texts.entries.remove(&key);
+                self.write_entry(key, label, message, persistent, highlight);
+                return;
+            }
+
+            if let (Some(label_entity), Some(new_label)) = (entry.label_entity, &label)
+                && entry.label.as_ref() != Some(new_label)
+            {
+                self.commands
+                    .entity(label_entity)
+                    .insert(Text::new(new_label.clone()));
+                entry.label = label;
+            }
+
+            let mut value = self.commands.entity(entry.value_entity);
+            if entry.message != message {
+                match highlight {
+                    Highlight::None => {}
+                    Highlight::Diff => {
+                        entry.diff_countdown = DIFF_HIGHLIGHT_FRAMES;
+                        value.insert(TextColor(self.config.diff_color));
+                    }
+                    Highlight::Flash => {
+                        entry.flash_remaining = FLASH_DURATION_SECONDS;
+                        value.insert(TextColor(FLASH_COLOR));
+                    }
+                }
+            }
+            value.insert(Text::new(message.clone()));
+            entry.message = message;
+            entry.last_frame = frame;
+            entry.persistent |= persistent;
+        } else {
+            // Pinned lines sit outside the stack and don't take up a slot in it.
+            let (line, anchor) = match self.texts.pinned.get(&key) {
+                Some(&position) => (PINNED_LINE, pixel_anchor(position)),
+                None => {
+                    let line = self.texts.line_for(&key);
+                    (line, self.texts.line_anchor(line))
+                }
+            };
+            let row = (
+                DebugLabel(key.clone()),
+                anchored_node(anchor, &self.config.safe_area),
+                self.line_visibility(&key),
+            );
+
+            let pooled = self
+                .texts
+                .pool
+                .iter()
+                .position(|pooled| pooled.label_entity.is_some() == label.is_some())
+                .map(|idx| self.texts.pool.swap_remove(idx));
+
+            let (entity, label_entity, value_entity) = match (pooled, &label) {
+                (Some(pooled), label) => {
+                    self.commands
+                        .entity(pooled.entity)
+                        .remove::<PooledDebugLine>()
+                        .insert(row);
+                    if let (Some(label_entity), Some(label)) = (pooled.label_entity, label) {
+                        self.commands
+                            .entity(label_entity)
+                            .insert((Text::new(label.clone()), TextColor(DEBUG_TEXT_COLOR)));
+                    }
+                    self.commands
+                        .entity(pooled.value_entity)
+                        .insert((Text::new(message.clone()), TextColor(DEBUG_TEXT_COLOR)));
+                    (pooled.entity, pooled.label_entity, pooled.value_entity)
+                }
+                (None, Some(label)) => {
+                    let label_entity = self
+                        .commands
+                        .spawn((
+                            DebugLabelColumn,
+                            debug_text_bundle(label.clone(), &self.config),
+                            Node {
+                                min_width: Val::Px(self.texts.label_column_width),
+                                ..default()
+                            },
+                        ))
+                        .id();
+                    let value_entity = self
+                        .commands
+                        .spawn(debug_text_bundle(message.clone(), &self.config))
+                        .id();
+                    let entity = self
+                        .commands
+                        .spawn(row)
+                        .add_children(&[label_entity, value_entity])
+                        .id();
+                    (entity, Some(label_entity), value_entity)
+                }
+                (None, None) => {
+                    let entity = self
+                        .commands
+                        .spawn((row, debug_text_bundle(message.clone(), &self.config)))
+                        .id();
+                    (entity, None, entity)
+                }
+            };
+
+            self.texts.entries.insert(
+                key,
+                DebugEntry {
+                    entity,
+                    label_entity,
+                    value_entity,
+                    line,
+                    last_frame: frame,
+                    persistent,
+                    label,
+                    message,
+                    diff_countdown: 0,
+                    flash_remaining: 0.0,
+                    size_hint: None,
+                    wrap: false,
+                    overflows: false,
+                    bool_value: None,
+                },
+            );
+        }
+    }
+}
+
+fn debug_text_bundle(message: String, config: &DebugVisConfig) -> impl Bundle {
+    (
+        Text::new(message),
         TextFont {
             font_size: 16.0,
             ..default()
         },
-        TextColor(Color::srgb(0.0, 1.0, 0.0)),
-        TextShadow{
-            offset: Vec2::new(1.0, 1.0),
-            color: Color::srgb(0.0, 0.0, 0.0),
-        },
-        Node {
-            position_type: PositionType::Absolute,
-            top: Val::Px(8.0),
-            left: Val::Px(8.0),
-            ..default()
+        TextColor(DEBUG_TEXT_COLOR),
+        overlay_text_shadow(config),
+    )
+}
+
+fn overlay_text_shadow(config: &DebugVisConfig) -> impl Bundle {
+    (
+        OverlayTextShadow,
+        TextShadow {
+            offset: config.text_shadow_offset,
+            color: config.text_shadow_color,
         },
-        visibility,
-    ));
+    )
+}
+
+/// Marks the overlay's own [`TextShadow`]s, so config changes leave the app's alone.
+#[derive(Component)]
+struct OverlayTextShadow;
+
+/// Restyles every overlay text shadow after [`DebugVisConfig`] changes.
+fn apply_text_shadow_changes(
+    config: Res<DebugVisConfig>,
+    mut shadows: Query<&mut TextShadow, With<OverlayTextShadow>>,
+) {
+    for mut shadow in shadows.iter_mut() {
+        shadow.set_if_neq(TextShadow {
+            offset: config.text_shadow_offset,
+            color: config.text_shadow_color,
+        });
+    }
+}
+
+/// Dumps a value's fields as debug lines. Usually implemented via `#[derive(DebugText)]`.
+pub trait DebugText {
+    fn debug_dump(&self, writer: &mut DebugTextWriter, prefix: &str);
+}
+
+/// Writes every `C` in the world each frame, keyed by component type and entity.
+pub fn dump_debug_text_components<C: DebugText + Component>(
+    query: Query<(Entity, &C)>,
+    mut writer: DebugTextWriter,
+) {
+    for (entity, component) in query.iter() {
+        let prefix = format!("{}[{}]", ShortName::of::<C>(), entity);
+        component.debug_dump(&mut writer, &prefix);
+    }
+}
+
+/// Overlay state captured for one frame.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DebugSnapshot {
+    pub frame: u64,
+    /// `(key, text)` for every active debug line, in display order.
+    pub entries: Vec<(String, String)>,
+    pub fps: f64,
+    pub avg_ms: f64,
+}
+
+/// Most recent [`DebugSnapshot`]s, oldest first, when [`DebugVisConfig::record_snapshots`]
+/// is enabled. Capped at the last 1000 frames.
+#[derive(Resource, Debug, Default)]
+pub struct DebugRecording(pub VecDeque<DebugSnapshot>);
+
+#[cfg(feature = "serde")]
+impl DebugRecording {
+    pub fn save_to_file(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(&self.0)?;
+        std::fs::write(path, json)
+    }
+}
+
+fn record_debug_snapshot(
+    config: Res<DebugVisConfig>,
+    frame_count: Res<FrameCount>,
+    texts: Res<DebugTexts>,
+    fps_window: Res<FpsWindow>,
+    history: Res<FrameTimeHistory>,
+    mut recording: ResMut<DebugRecording>,
+) {
+    if !config.record_snapshots {
+        return;
+    }
+
+    let mut entries: Vec<_> = texts.entries.iter().collect();
+    entries.sort_by_key(|(_, entry)| entry.line);
+    let entries = entries
+        .into_iter()
+        .map(|(key, entry)| {
+            let text = match &entry.label {
+                Some(label) => format!("{label}{}", entry.message),
+                None => entry.message.clone(),
+            };
+            (key.clone(), text)
+        })
+        .collect();
+
+    recording.0.push_back(DebugSnapshot {
+        frame: frame_count.0 as u64,
+        entries,
+        fps: fps_window.fps().unwrap_or_default(),
+        avg_ms: history.avg_ms().unwrap_or_default(),
+    });
+    while recording.0.len() > MAX_RECORDED_SNAPSHOTS {
+        recording.0.pop_front();
+    }
+}
+
+#[derive(Component)]
+struct FpsText;
+
+#[derive(Component)]
+struct FrametimeConsistencyText;
+
+#[derive(Component)]
+struct FrametimeMaxDeltaText;
+
+#[derive(Component)]
+struct OverlayCostText;
+
+#[derive(Component)]
+struct FrameBudgetText;
+
+/// Frame number and session time, for lining the overlay up with screen recordings.
+#[derive(Component)]
+struct FrameClockText;
+
+/// Corner dot that turns yellow or red as FPS falls below [`DebugVisConfig::fps_target`].
+#[derive(Component)]
+struct HealthDot;
+
+/// Time spent inside the overlay's own systems. Systems add to `accumulated_nanos` through
+/// [`OverlayCost::measure`] (atomically, so instrumented systems don't conflict), and
+/// [`update_overlay_cost_display`] folds it into `last_frame` once per frame.
+#[derive(Resource, Default)]
+struct OverlayCost {
+    accumulated_nanos: AtomicU64,
+    last_frame: Duration,
+}
+
+impl OverlayCost {
+    fn measure(&self) -> OverlayCostGuard<'_> {
+        OverlayCostGuard {
+            start: Instant::now(),
+            total: &self.accumulated_nanos,
+        }
+    }
+}
+
+struct OverlayCostGuard<'a> {
+    start: Instant,
+    total: &'a AtomicU64,
+}
+
+impl Drop for OverlayCostGuard<'_> {
+    fn drop(&mut self) {
+        let nanos = self.start.elapsed().as_nanos() as u64;
+        self.total.fetch_add(nanos, Ordering::Relaxed);
+    }
+}
+
+/// Rolling window of recent frame times, in ms. Query it from game code or tests for the
+/// same numbers the overlay shows.
+#[derive(Resource)]
+pub struct FrameTimeHistory {
+    frame_times_ms: VecDeque<f64>,
+    /// Smoothed counterpart of each sample in `frame_times_ms`, evicted together with it.
+    smoothed_ms: VecDeque<f64>,
+    /// [`FrameCount`] each sample in `frame_times_ms` was recorded at, evicted together with
+    /// it, for matching samples with frame indices in external captures.
+    frames: VecDeque<u32>,
+    sum_seconds: f64,
+    /// Sliding-window maximum candidates as (sample number, frame time): sample numbers
+    /// increase and frame times strictly decrease from front to back.
+    max_candidates: VecDeque<(u64, f64)>,
+    /// Sliding-window minimum candidates, the mirror image of `max_candidates`: frame times
+    /// strictly increase from front to back.
+    min_candidates: VecDeque<(u64, f64)>,
+    /// Samples pushed since startup, used to number them for the candidate deques.
+    pushed: u64,
+}
+
+impl Default for FrameTimeHistory {
+    /// Allocates the whole window up front. A sample is pushed before the oldest one is
+    /// evicted, hence the extra slot; allocating mid-run would show up in the very frame
+    /// times being recorded.
+    fn default() -> Self {
+        Self {
+            frame_times_ms: VecDeque::with_capacity(FRAME_DELTA_WINDOW + 1),
+            smoothed_ms: VecDeque::with_capacity(FRAME_DELTA_WINDOW + 1),
+            frames: VecDeque::with_capacity(FRAME_DELTA_WINDOW + 1),
+            sum_seconds: 0.0,
+            max_candidates: VecDeque::with_capacity(FRAME_DELTA_WINDOW + 1),
+            min_candidates: VecDeque::with_capacity(FRAME_DELTA_WINDOW + 1),
+            pushed: 0,
+        }
+    }
+}
+
+impl FrameTimeHistory {
+    /// Records a raw sample. Without a `smoothed_ms` from the source, the smoothed series
+    /// carries on as an exponential moving average of the raw one. The plugin pushes once a
+    /// frame; call this directly only on a history of your own.
+    pub fn push(&mut self, frame_time_ms: f64, smoothed_ms: Option<f64>, frame: u32) {
+        let capacities = (
+            self.frame_times_ms.capacity(),
+            self.smoothed_ms.capacity(),
+            self.frames.capacity(),
+            self.max_candidates.capacity(),
+            self.min_candidates.capacity(),
+        );
+
+        let smoothed_ms = smoothed_ms.unwrap_or_else(|| match self.smoothed_ms.back() {
+            Some(previous) => previous + (frame_time_ms - previous) * SMOOTHING_FACTOR,
+            None => frame_time_ms,
+        });
+
+        self.frame_times_ms.push_back(frame_time_ms);
+        self.smoothed_ms.push_back(smoothed_ms);
+        self.frames.push_back(frame);
+        self.sum_seconds += frame_time_ms / 1000.0;
+        if self.frame_times_ms.len() > FRAME_DELTA_WINDOW {
+            if let Some(removed) = self.frame_times_ms.pop_front() {
+                self.sum_seconds -= removed / 1000.0;
+            }
+            self.smoothed_ms.pop_front();
+            self.frames.pop_front();
+        }
+
+        // A sample can never be the max again once a newer, larger one arrives.
+        while self
+            .max_candidates
+            .back()
+            .is_some_and(|&(_, candidate)| candidate <= frame_time_ms)
+        {
+            self.max_candidates.pop_back();
+        }
+        self.max_candidates.push_back((self.pushed, frame_time_ms));
+        // Likewise, a newer, smaller sample rules out older ones as the min.
+        while self
+            .min_candidates
+            .back()
+            .is_some_and(|&(_, candidate)| candidate >= frame_time_ms)
+        {
+            self.min_candidates.pop_back();
+        }
+        self.min_candidates.push_back((self.pushed, frame_time_ms));
+        self.pushed += 1;
+        let oldest = self.pushed - self.frame_times_ms.len() as u64;
+        while self
+            .max_candidates
+            .front()
+            .is_some_and(|&(sample, _)| sample < oldest)
+        {
+            self.max_candidates.pop_front();
+        }
+        while self
+            .min_candidates
+            .front()
+            .is_some_and(|&(sample, _)| sample < oldest)
+        {
+            self.min_candidates.pop_front();
+        }
+
+        debug_assert_eq!(
+            capacities,
+            (
+                self.frame_times_ms.capacity(),
+                self.smoothed_ms.capacity(),
+                self.frames.capacity(),
+                self.max_candidates.capacity(),
+                self.min_candidates.capacity(),
+            ),
+            "FrameTimeHistory reallocated while recording"
+        );
+    }
+
+    /// Drops the whole window, e.g. when conditions change enough that old samples would
+    /// skew the statistics.
+    fn clear(&mut self) {
+        self.frame_times_ms.clear();
+        self.smoothed_ms.clear();
+        self.frames.clear();
+        self.sum_seconds = 0.0;
+        self.max_candidates.clear();
+        self.min_candidates.clear();
+    }
+
+    /// Samples in the current window, oldest first, paired with their index in the window.
+    pub fn iter_indexed(&self) -> impl Iterator<Item = (usize, f64)> + '_ {
+        self.frame_times_ms.iter().copied().enumerate()
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.frame_times_ms.len()
+    }
+
+    /// The window squeezed into `n` equal buckets, oldest first, keeping each bucket's largest
+    /// frame time so a spike survives the downsampling. Windows of `n` samples or fewer come
+    /// back unchanged.
+    pub fn resample(&self, n: usize) -> Vec<f64> {
+        let len = self.frame_times_ms.len();
+        if len <= n {
+            return self.frame_times_ms.iter().copied().collect();
+        }
+        (0..n)
+            .map(|bucket| {
+                let (start, end) = (bucket * len / n, (bucket + 1) * len / n);
+                self.frame_times_ms
+                    .range(start..end)
+                    .copied()
+                    .fold(f64::NEG_INFINITY, f64::max)
+            })
+            .collect()
+    }
+
+    /// [`FrameCount`]s of the oldest and newest samples in the window.
+    pub fn frame_range(&self) -> Option<(u32, u32)> {
+        Some((*self.frames.front()?, *self.frames.back()?))
+    }
+
+    /// Samples in the window, oldest first, with the [`FrameCount`] each was recorded at.
+    pub fn iter_with_frames(&self) -> impl DoubleEndedIterator<Item = (u32, f64)> + '_ {
+        self.frames
+            .iter()
+            .copied()
+            .zip(self.frame_times_ms.iter().copied())
+    }
+
+    pub fn avg_ms(&self) -> Option<f64> {
+        if self.frame_times_ms.is_empty() {
+            return None;
+        }
+        Some((self.sum_seconds * 1000.0) / self.frame_times_ms.len() as f64)
+    }
+
+    /// Population variance of the window, in ms². 0 with fewer than two samples.
+    ///
+    /// Computed in one pass with Welford's running mean, which avoids both an allocation and
+    /// the cancellation error of the sum-of-squares formula.
+    pub fn variance(&self) -> f64 {
+        let mut mean = 0.0;
+        let mut sum_sq_diff = 0.0;
+        for (index, frame_time_ms) in self.frame_times_ms.iter().enumerate() {
+            let delta = frame_time_ms - mean;
+            mean += delta / (index + 1) as f64;
+            sum_sq_diff += delta * (frame_time_ms - mean);
+        }
+        match self.frame_times_ms.len() {
+            0 => 0.0,
+            len => sum_sq_diff / len as f64,
+        }
+    }
+
+    /// Largest frame time in the window.
+    pub fn max_ms(&self) -> Option<f64> {
+        self.moving_max(FRAME_DELTA_WINDOW)
+    }
+
+    /// Largest of the last `window` frame times, from a monotonic deque kept up to date on
+    /// every push. O(1) amortized for the full window; shorter windows skip the older
+    /// candidates first.
+    pub fn moving_max(&self, window: usize) -> Option<f64> {
+        if window == 0 {
+            return None;
+        }
+        let start = self.pushed - window.min(self.frame_times_ms.len()) as u64;
+        self.max_candidates
+            .iter()
+            .find(|&&(sample, _)| sample >= start)
+            .map(|&(_, frame_time_ms)| frame_time_ms)
+    }
+
+    pub fn min_ms(&self) -> Option<f64> {
+        self.moving_min(FRAME_DELTA_WINDOW)
+    }
+
+    /// Smallest of the last `window` frame times; see [`moving_max`](Self::moving_max).
+    pub fn moving_min(&self, window: usize) -> Option<f64> {
+        if window == 0 {
+            return None;
+        }
+        let start = self.pushed - window.min(self.frame_times_ms.len()) as u64;
+        self.min_candidates
+            .iter()
+            .find(|&&(sample, _)| sample >= start)
+            .map(|&(_, frame_time_ms)| frame_time_ms)
+    }
+
+    /// Nearest-rank percentile (`p` in 0..=100) over the window.
+    ///
+    /// Sorts a copy of the window on every call, so this is O(n log n); cache the result
+    /// (or take a [`snapshot`](Self::snapshot)) rather than calling it repeatedly per frame.
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        self.percentiles([p]).map(|[value]| value)
+    }
+
+    /// Several [`percentile`](Self::percentile)s at once, for the cost of a single sort.
+    pub fn percentiles<const N: usize>(&self, ps: [f64; N]) -> Option<[f64; N]> {
+        if self.frame_times_ms.is_empty() {
+            return None;
+        }
+
+        let mut sorted: Vec<f64> = self.frame_times_ms.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        Some(ps.map(|p| {
+            let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        }))
+    }
+
+    /// Frames per second over the most recent second of samples.
+    pub fn fps_1s(&self) -> Option<f64> {
+        let mut window_ms = 0.0;
+        let mut frames = 0usize;
+        for frame_time_ms in self.frame_times_ms.iter().rev() {
+            window_ms += frame_time_ms;
+            frames += 1;
+            if window_ms >= 1000.0 {
+                break;
+            }
+        }
+
+        (window_ms > 0.0).then(|| frames as f64 * 1000.0 / window_ms)
+    }
+
+    /// Fraction (0.0–1.0) of frames in the window that took longer than `threshold_ms`.
+    pub fn time_above_threshold(&self, threshold_ms: f64) -> f64 {
+        if self.frame_times_ms.is_empty() {
+            return 0.0;
+        }
+        let above = self
+            .frame_times_ms
+            .iter()
+            .filter(|&&frame_time_ms| frame_time_ms > threshold_ms)
+            .count();
+        above as f64 / self.frame_times_ms.len() as f64
+    }
+
+    /// The window's frames classed against a vsync refresh interval.
+    pub fn vsync_misses(&self, refresh_interval_ms: f64) -> VsyncMisses {
+        VsyncMisses::count(self.frame_times_ms.iter().copied(), refresh_interval_ms)
+    }
+
+    pub fn snapshot(&self) -> FrameStatsSnapshot {
+        let percentiles = self.percentiles([50.0, 95.0, 99.0]);
+        FrameStatsSnapshot {
+            sample_count: self.sample_count(),
+            avg_ms: self.avg_ms(),
+            max_ms: self.max_ms(),
+            p50_ms: percentiles.map(|[p50, _, _]| p50),
+            p95_ms: percentiles.map(|[_, p95, _]| p95),
+            p99_ms: percentiles.map(|[_, _, p99]| p99),
+            fps_1s: self.fps_1s(),
+        }
+    }
+}
+
+/// Point-in-time copy of the [`FrameTimeHistory`] statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrameStatsSnapshot {
+    pub sample_count: usize,
+    pub avg_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+    pub p50_ms: Option<f64>,
+    pub p95_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub fps_1s: Option<f64>,
+}
+
+/// Totals since startup, where [`FrameTimeHistory`] only keeps a rolling window.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionStats {
+    /// Samples recorded into the history.
+    pub frames: u64,
+    /// Sum of those samples, in ms.
+    pub total_ms: f64,
+    /// Worst frame time recorded.
+    pub max_ms: Option<f64>,
+    /// [`FrameSpike`]s detected.
+    pub spikes: u64,
+}
+
+impl SessionStats {
+    pub fn avg_ms(&self) -> Option<f64> {
+        (self.frames > 0).then(|| self.total_ms / self.frames as f64)
+    }
+}
+
+fn update_session_stats(
+    history: Res<FrameTimeHistory>,
+    mut spikes: MessageReader<FrameSpike>,
+    mut session: ResMut<SessionStats>,
+) {
+    let new_samples = (history.pushed - session.frames) as usize;
+    for &frame_time_ms in history.frame_times_ms.iter().rev().take(new_samples) {
+        session.total_ms += frame_time_ms;
+        session.max_ms = Some(session.max_ms.map_or(frame_time_ms, |max| max.max(frame_time_ms)));
+    }
+    session.frames = history.pushed;
+    session.spikes += spikes.read().count() as u64;
+}
+
+fn spawn_fps_display(
+    mut commands: Commands,
+    level: Res<DebugLevel>,
+    config: Res<DebugVisConfig>,
+) {
+    let visibility = if *level == DebugLevel::Hidden {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+
+    commands.spawn((
+        FpsText,
+        Text::new("FPS: --"),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.0, 1.0, 0.0)),
+        overlay_text_shadow(&config),
+        anchored_node(
+            UiRect::new(Val::Px(8.0), Val::Auto, Val::Px(8.0), Val::Auto),
+            &config.safe_area,
+        ),
+        visibility,
+    ));
+
+    let consistency_visibility = if *level == DebugLevel::Full {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    commands.spawn((
+        FrametimeConsistencyText,
+        Text::new(format!("Frametime avg ({}): --", FRAME_DELTA_WINDOW)),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.0, 1.0, 0.0)),
+        overlay_text_shadow(&config),
+        anchored_node(
+            UiRect::new(Val::Px(8.0), Val::Auto, Val::Px(28.0), Val::Auto),
+            &config.safe_area,
+        ),
+        consistency_visibility,
+    ));
+
+    commands.spawn((
+        FrametimeMaxDeltaText,
+        Text::new(format!("Frametime max ({}): --", FRAME_DELTA_WINDOW)),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgb(0.0, 1.0, 0.0)),
+        overlay_text_shadow(&config),
+        anchored_node(
+            UiRect::new(Val::Px(8.0), Val::Auto, Val::Px(48.0), Val::Auto),
+            &config.safe_area,
+        ),
+        consistency_visibility,
+    ));
+
+    commands.spawn((
+        OverlayCostText,
+        Text::new("Overlay cost: --"),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(DEBUG_TEXT_COLOR),
+        overlay_text_shadow(&config),
+        anchored_node(
+            UiRect::new(Val::Px(8.0), Val::Auto, Val::Px(68.0), Val::Auto),
+            &config.safe_area,
+        ),
+        consistency_visibility,
+    ));
+
+    commands.spawn((
+        FrameBudgetText,
+        Text::new("Frames >--ms: --"),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(DEBUG_TEXT_COLOR),
+        overlay_text_shadow(&config),
+        anchored_node(
+            UiRect::new(Val::Px(8.0), Val::Auto, Val::Px(88.0), Val::Auto),
+            &config.safe_area,
+        ),
+        consistency_visibility,
+    ));
+
+    commands.spawn((
+        FrameClockText,
+        Text::new("Frame --"),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(DEBUG_TEXT_COLOR),
+        overlay_text_shadow(&config),
+        anchored_node(
+            UiRect::new(Val::Auto, Val::Px(8.0), Val::Auto, Val::Px(8.0)),
+            &config.safe_area,
+        ),
+        visibility,
+    ));
+
+    let (mut dot_node, dot_anchor) = anchored_node(
+        UiRect::new(Val::Auto, Val::Px(8.0), Val::Px(8.0), Val::Auto),
+        &config.safe_area,
+    );
+    dot_node.width = Val::Px(HEALTH_DOT_SIZE);
+    dot_node.height = Val::Px(HEALTH_DOT_SIZE);
+    dot_node.border_radius = BorderRadius::MAX;
+    dot_node.display = if config.show_health_dot {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    commands.spawn((
+        HealthDot,
+        dot_node,
+        dot_anchor,
+        BackgroundColor(HEALTH_GOOD_COLOR),
+        visibility,
+    ));
+}
+
+fn emit_debug_level_changed(
+    level: Res<DebugLevel>,
+    mut previous: Local<Option<DebugLevel>>,
+    mut changes: MessageWriter<DebugLevelChanged>,
+) {
+    let current = *level;
+    match previous.replace(current) {
+        Some(from) if from != current => {
+            changes.write(DebugLevelChanged { from, to: current });
+        }
+        _ => {}
+    }
+}
+
+/// Collapses the frametime max line into the avg one unless
+/// [`DebugVisConfig::split_frametime_lines`] is set, moving the lines below it up to match.
+fn apply_frametime_layout(
+    config: Res<DebugVisConfig>,
+    mut max_query: Query<&mut Node, With<FrametimeMaxDeltaText>>,
+    mut below_query: Query<
+        (&mut OverlayAnchor, Has<OverlayCostText>),
+        Or<(With<OverlayCostText>, With<FrameBudgetText>)>,
+    >,
+) {
+    if !config.is_changed() {
+        return;
+    }
+
+    let split = config.split_frametime_lines;
+    for mut node in max_query.iter_mut() {
+        node.display = if split { Display::Flex } else { Display::None };
+    }
+    for (mut anchor, is_cost) in below_query.iter_mut() {
+        let top = match (split, is_cost) {
+            (true, true) => 68.0,
+            (true, false) => 88.0,
+            (false, true) => 48.0,
+            (false, false) => 68.0,
+        };
+        anchor.0.top = Val::Px(top);
+    }
+}
+
+fn apply_safe_area(
+    config: Res<DebugVisConfig>,
+    mut resized: MessageReader<WindowResized>,
+    mut query: Query<(&OverlayAnchor, &mut Node)>,
+) {
+    let resized = resized.read().count() > 0;
+    if !resized && !config.is_changed() {
+        return;
+    }
+
+    for (anchor, mut node) in query.iter_mut() {
+        let (anchored, _) = anchored_node(anchor.0, &config.safe_area);
+        node.left = anchored.left;
+        node.right = anchored.right;
+        node.top = anchored.top;
+        node.bottom = anchored.bottom;
+    }
+}
+
+fn update_frame_time_history(
+    config: Res<DebugVisConfig>,
+    diagnostics: Res<DiagnosticsStore>,
+    time: Res<Time<Real>>,
+    frame_count: Res<FrameCount>,
+    mut manual: ResMut<ManualFrameTimes>,
+    mut history: ResMut<FrameTimeHistory>,
+    mut fps_window: ResMut<FpsWindow>,
+    mut throttle: ResMut<ThrottleDetector>,
+    #[cfg(not(target_arch = "wasm32"))] crash_dump: Option<Res<crash_dump::CrashDumpHistory>>,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let mut record = |frame_time_ms: f64, smoothed_ms: Option<f64>| {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(crash_dump) = &crash_dump {
+            crash_dump.record(frame_count.0, frame_time_ms);
+        }
+        history.push(frame_time_ms, smoothed_ms, frame_count.0);
+        throttle.record(frame_time_ms);
+    };
+
+    match config.frame_time_source {
+        FrameTimeSource::Diagnostics => {
+            if let Some(diagnostic) = diagnostics.get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+                && let Some(frame_time_ms) = diagnostic.value()
+            {
+                record(frame_time_ms, diagnostic.smoothed());
+            }
+        }
+        FrameTimeSource::RealTime => {
+            let frame_time_ms = time.delta_secs_f64() * 1000.0;
+            if frame_time_ms > 0.0 {
+                record(frame_time_ms, None);
+            }
+        }
+        FrameTimeSource::Manual => {
+            for frame_time_ms in manual.0.drain(..) {
+                record(frame_time_ms, None);
+                fps_window.push(frame_time_ms);
+            }
+        }
+    }
+}
+
+/// Raw frame times backing the FPS readout. Kept apart from [`FrameTimeHistory`] so the
+/// readout can drop stale samples when the frame rate shifts without touching the chart.
+#[derive(Resource, Default)]
+struct FpsWindow {
+    frame_times_ms: VecDeque<f64>,
+    sum_ms: f64,
+    step_streak: usize,
+}
+
+impl FpsWindow {
+    fn push(&mut self, frame_time_ms: f64) {
+        if let Some(avg) = self.avg_ms() {
+            if (frame_time_ms / avg - 1.0).abs() > FPS_STEP_THRESHOLD {
+                self.step_streak += 1;
+            } else {
+                self.step_streak = 0;
+            }
+        }
+
+        self.frame_times_ms.push_back(frame_time_ms);
+        self.sum_ms += frame_time_ms;
+
+        if self.step_streak >= FPS_STEP_FRAMES {
+            // Sustained step change: keep only the frames from the new regime.
+            self.retain_latest(FPS_STEP_FRAMES);
+            self.step_streak = 0;
+        }
+
+        while self.sum_ms - self.frame_times_ms.front().copied().unwrap_or_default()
+            >= FPS_AVG_WINDOW_SECONDS * 1000.0
+        {
+            let Some(removed) = self.frame_times_ms.pop_front() else {
+                break;
+            };
+            self.sum_ms -= removed;
+        }
+    }
+
+    fn retain_latest(&mut self, count: usize) {
+        while self.frame_times_ms.len() > count {
+            if let Some(removed) = self.frame_times_ms.pop_front() {
+                self.sum_ms -= removed;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.frame_times_ms.clear();
+        self.sum_ms = 0.0;
+        self.step_streak = 0;
+    }
+
+    fn avg_ms(&self) -> Option<f64> {
+        (!self.frame_times_ms.is_empty()).then(|| self.sum_ms / self.frame_times_ms.len() as f64)
+    }
+
+    fn fps(&self) -> Option<f64> {
+        (self.sum_ms > 0.0).then(|| self.frame_times_ms.len() as f64 * 1000.0 / self.sum_ms)
+    }
+}
+
+/// Feeds the FPS readout from real frame deltas. With [`FrameTimeSource::Manual`] the
+/// readout follows the queued samples instead, so a replayed trace shows its own FPS.
+fn update_fps_window(
+    config: Res<DebugVisConfig>,
+    time: Res<Time<Real>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut present_mode: Local<Option<PresentMode>>,
+    mut fps_window: ResMut<FpsWindow>,
+) {
+    if let Ok(window) = windows.single()
+        && present_mode.replace(window.present_mode) != Some(window.present_mode)
+    {
+        fps_window.reset();
+    }
+    if config.frame_time_source == FrameTimeSource::Manual {
+        return;
+    }
+
+    let frame_time_ms = time.delta_secs_f64() * 1000.0;
+    if frame_time_ms > 0.0 {
+        fps_window.push(frame_time_ms);
+    }
+}
+
+/// Green at or above `fps_target`, yellow above half of it, red below that.
+fn fps_color(fps: f64, fps_target: f64) -> Color {
+    if fps < fps_target * 0.5 {
+        HEALTH_BAD_COLOR
+    } else if fps < fps_target {
+        HEALTH_WARN_COLOR
+    } else {
+        HEALTH_GOOD_COLOR
+    }
+}
+
+fn update_fps_display(
+    level: Res<DebugLevel>,
+    config: Res<DebugVisConfig>,
+    recording: Res<RecordingEnabled>,
+    fps_window: Res<FpsWindow>,
+    mut query: Query<(&mut Text, &mut TextColor), With<FpsText>>,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    if *level == DebugLevel::Hidden {
+        return;
+    }
+
+    let Ok((mut text, mut color)) = query.single_mut() else {
+        return;
+    };
+
+    let fps = match fps_window.fps() {
+        Some(fps) => {
+            color.set_if_neq(TextColor(fps_color(fps, config.fps_target)));
+            format!("{:.0}", fps)
+        }
+        None => "--".to_string(),
+    };
+    // Keep the last value on screen but make it obvious it is no longer live.
+    text.0 = if recording.0 {
+        format!("FPS: {fps}")
+    } else {
+        format!("FPS: {fps} (recording paused)")
+    };
+}
+
+fn update_health_dot(
+    config: Res<DebugVisConfig>,
+    fps_window: Res<FpsWindow>,
+    time: Res<Time<Real>>,
+    mut query: Query<(&mut Node, &mut BackgroundColor), With<HealthDot>>,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let Ok((mut node, mut background)) = query.single_mut() else {
+        return;
+    };
+
+    let display = if config.show_health_dot {
+        Display::Flex
+    } else {
+        Display::None
+    };
+    if node.display != display {
+        node.display = display;
+    }
+    if !config.show_health_dot {
+        return;
+    }
+
+    let color = match fps_window.fps().map(|fps| fps_color(fps, config.fps_target)) {
+        Some(color) if color == HEALTH_BAD_COLOR => {
+            let pulse = 0.65 + 0.35 * (time.elapsed_secs() * std::f32::consts::TAU).sin();
+            HEALTH_BAD_COLOR.with_alpha(pulse)
+        }
+        Some(color) => color,
+        None => HEALTH_GOOD_COLOR,
+    };
+    background.set_if_neq(BackgroundColor(color));
+}
+
+fn update_frame_clock_display(
+    level: Res<DebugLevel>,
+    frame_count: Res<FrameCount>,
+    time: Res<Time<Real>>,
+    mut query: Query<&mut Text, With<FrameClockText>>,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    if *level == DebugLevel::Hidden {
+        return;
+    }
+
+    let Ok(mut text) = query.single_mut() else {
+        return;
+    };
+
+    let elapsed = time.elapsed().as_secs();
+    text.0 = format!(
+        "Frame {}  {:02}:{:02}:{:02}",
+        frame_count.0,
+        elapsed / 3600,
+        elapsed / 60 % 60,
+        elapsed % 60
+    );
+}
+
+fn update_frametime_consistency_display(
+    level: Res<DebugLevel>,
+    config: Res<DebugVisConfig>,
+    history: Res<FrameTimeHistory>,
+    mut text_queries: ParamSet<(
+        Query<&mut Text, (With<FrametimeConsistencyText>, Without<FpsText>)>,
+        Query<&mut Text, (With<FrametimeMaxDeltaText>, Without<FpsText>)>,
+        Query<&mut Text, (With<FrameBudgetText>, Without<FpsText>)>,
+    )>,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    if *level != DebugLevel::Full {
+        return;
+    }
+
+    let (avg_label, max_label) = match (history.avg_ms(), history.min_ms(), history.max_ms()) {
+        (Some(avg), _, Some(max)) if config.split_frametime_lines => (
+            format!("Frametime avg ({}): {:.2}", FRAME_DELTA_WINDOW, avg),
+            format!("Frametime max ({}): {:.2}", FRAME_DELTA_WINDOW, max),
+        ),
+        (Some(avg), Some(min), Some(max)) => (
+            format!(
+                "Frametime ({}): min {:.1} / avg {:.1} / max {:.1} ms",
+                FRAME_DELTA_WINDOW, min, avg, max
+            ),
+            String::new(),
+        ),
+        _ if config.split_frametime_lines => (
+            format!("Frametime avg ({}): --", FRAME_DELTA_WINDOW),
+            format!("Frametime max ({}): --", FRAME_DELTA_WINDOW),
+        ),
+        _ => (
+            format!("Frametime ({}): --", FRAME_DELTA_WINDOW),
+            String::new(),
+        ),
+    };
+
+    let mut avg_query = text_queries.p0();
+    let Ok(mut avg_text) = avg_query.single_mut() else {
+        return;
+    };
+    avg_text.0 = avg_label;
+
+    let mut max_query = text_queries.p1();
+    let Ok(mut max_text) = max_query.single_mut() else {
+        return;
+    };
+    max_text.0 = max_label;
+
+    let budget_label = format!(
+        "Frames >{:.2}ms: {:.1}%",
+        config.frame_budget_ms(),
+        history.time_above_threshold(config.frame_budget_ms()) * 100.0
+    );
+    let mut budget_query = text_queries.p2();
+    let Ok(mut budget_text) = budget_query.single_mut() else {
+        return;
+    };
+    budget_text.0 = budget_label;
+}
+
+fn toggle_debug_level(
+    mut debug_reader: MessageReader<DebugAction>,
+    mut level: ResMut<DebugLevel>,
+) {
+    for event in debug_reader.read() {
+        if *event == DebugAction::ToggleDebugLevel {
+            *level = level.next();
+        }
+    }
+}
+
+fn toggle_recording(
+    mut debug_reader: MessageReader<DebugAction>,
+    mut recording: ResMut<RecordingEnabled>,
+) {
+    for event in debug_reader.read() {
+        if *event == DebugAction::ToggleRecording {
+            recording.0 = !recording.0;
+        }
+    }
+}
+
+fn apply_debug_visibility(
+    level: Res<DebugLevel>,
+    widgets: Res<DebugWidgets>,
+    filter: Res<DebugLineFilter>,
+    texts: Res<DebugTexts>,
+    mut fps_query: Query<
+        &mut Visibility,
+        (
+            Or<(With<FpsText>, With<FrameClockText>, With<HealthDot>)>,
+            Without<FrametimeConsistencyText>,
+        ),
+    >,
+    mut consistency_query: Query<
+        &mut Visibility,
+        (
+            Or<(
+                With<FrametimeConsistencyText>,
+                With<FrametimeMaxDeltaText>,
+                With<OverlayCostText>,
+                With<FrameBudgetText>,
+            )>,
+            Without<FpsText>,
+            Without<FrameClockText>,
+            Without<HealthDot>,
+        ),
+    >,
+    mut debug_query: Query<
+        (&DebugLabel, &mut Visibility),
+        (
+            Without<PooledDebugLine>,
+            Without<FpsText>,
+            Without<FrametimeConsistencyText>,
+            Without<FrametimeMaxDeltaText>,
+            Without<OverlayCostText>,
+            Without<FrameBudgetText>,
+            Without<FrameClockText>,
+            Without<HealthDot>,
+        ),
+    >,
+) {
+    if !level.is_changed() && !widgets.is_changed() && !filter.is_changed() {
+        return;
+    }
+
+    let shown = |visible: bool, widget: DebugWidgets| {
+        if visible && widgets.contains(widget) {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        }
+    };
+    let fps_vis = shown(*level != DebugLevel::Hidden, DebugWidgets::FPS_TEXT);
+    let consistency_vis = shown(*level == DebugLevel::Full, DebugWidgets::CONSISTENCY_TEXT);
+    let debug_vis = shown(*level == DebugLevel::Full, DebugWidgets::DEBUG_LINES);
+
+    for mut vis in fps_query.iter_mut() {
+        if *vis != fps_vis {
+            *vis = fps_vis;
+        }
+    }
+    for mut vis in consistency_query.iter_mut() {
+        if *vis != consistency_vis {
+            *vis = consistency_vis;
+        }
+    }
+    for (label, mut vis) in debug_query.iter_mut() {
+        let vis_for_line = match texts.widget_lines.get(&label.0) {
+            Some(&widget) => shown(*level == DebugLevel::Full, widget),
+            None if filter.shows(&label.0) => debug_vis,
+            None => Visibility::Hidden,
+        };
+        if *vis != vis_for_line {
+            *vis = vis_for_line;
+        }
+    }
+}
+
+/// Queues a debug line from anywhere, formatting the message only when the `debug-vis`
+/// feature is enabled. Without it, the arguments are type-checked but never evaluated.
+///
+/// ```ignore
+/// dbg_text!("player_vel", "Velocity: {:?}", velocity);
+/// ```
+#[cfg(feature = "debug-vis")]
+#[macro_export]
+macro_rules! dbg_text {
+    ($key:expr, $($arg:tt)+) => {
+        $crate::debug_vis::debug_text($key, ::std::format!($($arg)+))
+    };
+}
+
+#[cfg(not(feature = "debug-vis"))]
+#[macro_export]
+macro_rules! dbg_text {
+    ($key:expr, $($arg:tt)+) => {{
+        // Never called, but keeps the arguments "used" so call sites don't warn.
+        let _ = || {
+            let _ = &$key;
+            let _ = ::std::format_args!($($arg)+);
+        };
+    }};
+}
+
+pub fn debug_text(key: impl Into<String>, message: impl Into<String>) {
+    enqueue_request(DebugRequest {
+        key: key.into(),
+        message: message.into(),
+        persistent: false,
+        enqueued: Instant::now(),
+    });
+}
+
+pub fn debug_text_persistent(key: impl Into<String>, message: impl Into<String>) {
+    enqueue_request(DebugRequest {
+        key: key.into(),
+        message: message.into(),
+        persistent: true,
+        enqueued: Instant::now(),
+    });
+}
+
+/// Writes a persistent line the first time `key` is seen and ignores every later call, for
+/// one-off events such as a shader compiling or an asset failing to load.
+pub fn debug_text_once(key: impl Into<String>, message: impl Into<String>) {
+    if !cfg!(feature = "debug-vis") {
+        return;
+    }
+    let key = key.into();
+    let Ok(mut seen) = DEBUG_ONCE_KEYS.get_or_init(|| Mutex::new(HashSet::new())).lock() else {
+        return;
+    };
+    if !seen.insert(key.clone()) {
+        return;
+    }
+    drop(seen);
+    debug_text_persistent(key, message);
+}
+
+struct DebugRequest {
+    key: String,
+    message: String,
+    persistent: bool,
+    enqueued: Instant,
+}
+
+/// How long [`debug_text`] requests waited in the queue before being written, over the last
+/// [`QUEUE_LATENCY_WINDOW`] requests. Rising latency means callers are outpacing the drain.
+#[derive(Resource, Default, Debug)]
+pub struct QueueLatencyHistory {
+    latencies_us: VecDeque<f64>,
+    sum_us: f64,
+}
+
+impl QueueLatencyHistory {
+    fn record(&mut self, latency: Duration) {
+        let latency_us = latency.as_secs_f64() * 1_000_000.0;
+        self.latencies_us.push_back(latency_us);
+        self.sum_us += latency_us;
+        if self.latencies_us.len() > QUEUE_LATENCY_WINDOW
+            && let Some(removed) = self.latencies_us.pop_front()
+        {
+            self.sum_us -= removed;
+        }
+    }
+
+    pub fn avg_us(&self) -> Option<f64> {
+        (!self.latencies_us.is_empty()).then(|| self.sum_us / self.latencies_us.len() as f64)
+    }
+
+    /// Nearest-rank percentiles of the window, in µs, e.g. `[50.0, 99.0]`. `None` while empty.
+    pub fn percentiles_us<const N: usize>(&self, ps: [f64; N]) -> Option<[f64; N]> {
+        if self.latencies_us.is_empty() {
+            return None;
+        }
+
+        let mut sorted: Vec<f64> = self.latencies_us.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        Some(ps.map(|p| {
+            let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        }))
+    }
+}
+
+static DEBUG_QUEUE: OnceLock<Mutex<Vec<DebugRequest>>> = OnceLock::new();
+/// Keys already written through [`debug_text_once`].
+static DEBUG_ONCE_KEYS: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+
+fn enqueue_request(req: DebugRequest) {
+    if !cfg!(feature = "debug-vis") {
+        return;
+    }
+    if let Ok(mut queue) = DEBUG_QUEUE.get_or_init(|| Mutex::new(Vec::new())).lock() {
+        queue.push(req);
+    }
+}
+
+fn drain_debug_queue(
+    mut writer: DebugTextWriter,
+    mut latency: ResMut<QueueLatencyHistory>,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let Some(queue) = DEBUG_QUEUE.get() else {
+        return;
+    };
+    let mut queue = queue.lock().unwrap();
+    let now = Instant::now();
+    for req in queue.drain(..) {
+        latency.record(now.saturating_duration_since(req.enqueued));
+        writer.write_with_persistence(req.key, req.message, req.persistent);
+    }
+    drop(queue);
+
+    if !writer.config.show_queue_latency {
+        return;
+    }
+    let Some(avg_us) = latency.avg_us() else {
+        return;
+    };
+    let Some([p50_us, p99_us]) = latency.percentiles_us([50.0, 99.0]) else {
+        return;
+    };
+    writer.write(
+        "queue_latency",
+        format!("Queue latency avg: {avg_us:.0} µs (P50 {p50_us:.0}, P99 {p99_us:.0})"),
+    );
+}
+
+/// Writes the lines recorded while the overlay was hidden, once it isn't.
+fn replay_deferred_writes(mut writer: DebugTextWriter) {
+    if *writer.level == DebugLevel::Hidden || writer.texts.deferred.is_empty() {
+        return;
+    }
+    let frame = writer.texts.frame;
+    for (key, write) in std::mem::take(&mut writer.texts.deferred) {
+        if write.persistent || write.last_frame + 1 >= frame {
+            writer.write_entry(key, write.label, write.message, write.persistent, Highlight::None);
+        }
+    }
+}
+
+fn cleanup_stale_debug_texts(
+    mut texts: ResMut<DebugTexts>,
+    config: Res<DebugVisConfig>,
+    frame_count: Res<FrameCount>,
+    mut commands: Commands,
+) {
+    texts.frame = u64::from(frame_count.0);
+    let frame = texts.frame;
+    texts
+        .deferred
+        .retain(|_, write| write.persistent || write.last_frame + 1 >= frame);
+
+    let mut to_remove = Vec::new();
+    for (key, entry) in texts.entries.iter() {
+        if !entry.persistent && entry.last_frame + 1 < frame {
+            to_remove.push(key.clone());
+        }
+    }
+
+    for key in to_remove {
+        texts.evict(&key, &mut commands, config.max_pooled_lines);
+    }
+
+    let stale_tables: Vec<String> = texts
+        .tables
+        .iter()
+        .filter(|(_, table)| table.last_frame + 1 < frame)
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in stale_tables {
+        let Some(table) = texts.tables.remove(&key) else {
+            continue;
+        };
+        commands.entity(table.entity).despawn();
+        texts.line_spans.remove(&table.line);
+        texts.reposition_after(table.line, &mut commands, &config.safe_area);
+    }
+}
+
+fn measure_line_count(texts: Res<DebugTexts>, mut diagnostics: Diagnostics) {
+    diagnostics.add_measurement(&DEBUG_VIS_LINE_COUNT, || texts.line_count() as f64);
+    diagnostics.add_measurement(&DEBUG_VIS_LINES_ALLOCATED, || texts.next_line as f64);
+}
+
+fn warn_line_overflow(
+    config: Res<DebugVisConfig>,
+    texts: Res<DebugTexts>,
+    time: Res<Time<Real>>,
+    mut last_warning: Local<Option<Duration>>,
+) {
+    if texts.next_line <= config.max_debug_lines {
+        return;
+    }
+    let now = time.elapsed();
+    if last_warning.is_some_and(|last| now.saturating_sub(last) < LINE_OVERFLOW_WARN_INTERVAL) {
+        return;
+    }
+    *last_warning = Some(now);
+    warn!(
+        "bevy_stability_test: debug line overflow, {} lines allocated",
+        texts.next_line
+    );
+}
+
+/// Cycles lines written with [`DebugTextWriter::write_enum_cycle`], and expands or collapses
+/// lines cut off by [`fit_debug_lines`].
+fn click_line(
+    click: On<Pointer<Click>>,
+    labels: Query<&DebugLabel>,
+    mut texts: ResMut<DebugTexts>,
+) {
+    // Clicks bubble up from the label/value children to the row, which carries the key.
+    let Ok(label) = labels.get(click.entity) else {
+        return;
+    };
+    if let Some(cycle) = texts.cycles.get(&label.0) {
+        cycle();
+        return;
+    }
+    let overflows = texts
+        .entries
+        .get(&label.0)
+        .is_some_and(|entry| entry.overflows);
+    if !texts.expanded.remove(&label.0) && overflows {
+        texts.expanded.insert(label.0.clone());
+    }
+}
+
+/// `text` cut to `max_chars` with a trailing "…", or wrapped into rows of `max_chars`, with
+/// the number of rows it takes.
+fn fit_text(text: &str, max_chars: usize, wrap: bool) -> (String, usize) {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= max_chars {
+        return (text.to_string(), 1);
+    }
+    if !wrap {
+        let cut: String = chars[..max_chars.saturating_sub(1)].iter().collect();
+        return (format!("{cut}…"), 1);
+    }
+    let rows: Vec<String> = chars
+        .chunks(max_chars.max(1))
+        .map(|row| row.iter().collect())
+        .collect();
+    let row_count = rows.len();
+    (rows.join("\n"), row_count)
+}
+
+/// Cuts every line to [`DebugVisConfig::max_line_width_px`], or wraps it when it was written
+/// with [`DebugTextWriter::write_wrapped`] or clicked open. Wrapped lines take extra rows the
+/// same way tables do. Runs after the writers in `Last`, so it sees each line's final text;
+/// widths are estimated from [`DEBUG_CHAR_WIDTH`] rather than measured.
+fn fit_debug_lines(
+    mut commands: Commands,
+    config: Res<DebugVisConfig>,
+    ui_scale: Res<UiScale>,
+    view: OverlayView,
+    mut texts: ResMut<DebugTexts>,
+    mut values: Query<&mut Text>,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let max_width = match config.max_line_width_px {
+        Some(width) => width,
+        None => {
+            let Some(window) = view.window() else {
+                return;
+            };
+            window.width() / ui_scale.0 - 2.0 * LEFT_PADDING
+        }
+    };
+    let line_chars = (max_width / DEBUG_CHAR_WIDTH).max(1.0) as usize;
+    let label_column_chars = (texts.label_column_width / DEBUG_CHAR_WIDTH).ceil() as usize;
+
+    let texts = &mut *texts;
+    let mut spans = Vec::new();
+    for (key, entry) in texts.entries.iter_mut() {
+        let label_chars = entry
+            .label
+            .as_ref()
+            .map_or(0, |label| label.chars().count().max(label_column_chars));
+        let max_chars = line_chars.saturating_sub(label_chars).max(1);
+        let wrap = entry.wrap || texts.expanded.contains(key);
+        let (shown, rows) = fit_text(&entry.message, max_chars, wrap);
+        entry.overflows = entry.message.chars().count() > max_chars;
+
+        if let Ok(mut text) = values.get_mut(entry.value_entity)
+            && text.0 != shown
+        {
+            text.0 = shown;
+        }
+        if entry.line != PINNED_LINE {
+            spans.push((entry.line, rows));
+        }
+    }
+
+    // Spans left behind by evicted lines; tables clean up their own.
+    let live_lines: HashSet<usize> = spans
+        .iter()
+        .map(|&(line, _)| line)
+        .chain(texts.tables.values().map(|table| table.line))
+        .collect();
+    let mut moved_from = None;
+    let orphaned: Vec<usize> = texts
+        .line_spans
+        .keys()
+        .copied()
+        .filter(|line| !live_lines.contains(line))
+        .collect();
+    for line in orphaned {
+        texts.line_spans.remove(&line);
+        moved_from = Some(moved_from.map_or(line, |from: usize| from.min(line)));
+    }
+    for (line, rows) in spans {
+        let previous = if rows > 1 {
+            texts.line_spans.insert(line, rows)
+        } else {
+            texts.line_spans.remove(&line)
+        };
+        if previous.unwrap_or(1) != rows {
+            moved_from = Some(moved_from.map_or(line, |from: usize| from.min(line)));
+        }
+    }
+    if let Some(line) = moved_from {
+        texts.reposition_after(line, &mut commands, &config.safe_area);
+    }
+}
+
+/// Highlights lines that changed or appeared since the previous frame, for
+/// [`DebugVisConfig::show_diff`]. Runs after every writer, so it sees the whole frame.
+fn highlight_frame_diff(
+    config: Res<DebugVisConfig>,
+    mut texts: ResMut<DebugTexts>,
+    mut previous: Local<Option<HashMap<String, String>>>,
+    mut colors: Query<&mut TextColor>,
+) {
+    if !config.show_diff {
+        *previous = None;
+        return;
+    }
+    // The first frame after enabling only records, instead of flagging every line as new.
+    let seeding = previous.is_none();
+    let previous = previous.get_or_insert_with(HashMap::new);
+
+    previous.retain(|key, _| texts.entries.contains_key(key));
+    for (key, entry) in texts.entries.iter_mut() {
+        let text = entry.text();
+        let color = match previous.get_mut(key) {
+            Some(old) if *old == text => continue,
+            Some(old) => {
+                *old = text;
+                config.diff_color
+            }
+            None => {
+                previous.insert(key.clone(), text);
+                config.new_entry_color
+            }
+        };
+        if seeding || config.diff_linger_frames == 0 {
+            continue;
+        }
+        entry.diff_countdown = config.diff_linger_frames;
+        if let Ok(mut text_color) = colors.get_mut(entry.value_entity) {
+            text_color.0 = color;
+        }
+    }
+}
+
+fn tick_diff_highlights(mut texts: ResMut<DebugTexts>, mut colors: Query<&mut TextColor>) {
+    for entry in texts.entries.values_mut() {
+        if entry.diff_countdown == 0 {
+            continue;
+        }
+
+        entry.diff_countdown -= 1;
+        if entry.diff_countdown == 0
+            && let Ok(mut color) = colors.get_mut(entry.value_entity)
+        {
+            color.0 = entry.base_color();
+        }
+    }
+}
+
+fn tick_flashes(
+    time: Res<Time<Real>>,
+    mut texts: ResMut<DebugTexts>,
+    mut colors: Query<&mut TextColor>,
+) {
+    let dt = time.delta_secs();
+    for entry in texts.entries.values_mut() {
+        if entry.flash_remaining <= 0.0 {
+            continue;
+        }
+
+        entry.flash_remaining = (entry.flash_remaining - dt).max(0.0);
+        if let Ok(mut color) = colors.get_mut(entry.value_entity) {
+            let t = 1.0 - entry.flash_remaining / FLASH_DURATION_SECONDS;
+            color.0 = FLASH_COLOR.mix(&entry.base_color(), t);
+        }
+    }
+}
+
+/// Widens the shared label column to fit the longest visible label. Only shrinks once the
+/// longest label is well below the current width, so lines don't jitter as labels come and go.
+fn update_label_column_width(
+    mut texts: ResMut<DebugTexts>,
+    labels: Query<(&TextLayoutInfo, &InheritedVisibility), With<DebugLabelColumn>>,
+    mut nodes: Query<&mut Node, With<DebugLabelColumn>>,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let widest = labels
+        .iter()
+        .filter(|(_, visibility)| visibility.get())
+        .map(|(layout, _)| layout.size.x / layout.scale_factor.max(f32::EPSILON))
+        .fold(0.0_f32, f32::max);
+
+    let current = texts.label_column_width;
+    if widest <= current && widest >= current - LABEL_COLUMN_HYSTERESIS {
+        return;
+    }
+
+    texts.label_column_width = widest;
+    for mut node in nodes.iter_mut() {
+        node.min_width = Val::Px(widest);
+    }
+}
+
+/// Minimal ANSI SGR helper for terminal log output.
+struct AnsiColor;
+
+impl AnsiColor {
+    const GREEN: u8 = 32;
+    const WHITE: u8 = 37;
+
+    fn wrap(s: &str, code: u8) -> String {
+        format!("\x1b[{code}m{s}\x1b[0m")
+    }
+}
+
+fn write_archetype_count(
+    config: Res<DebugVisConfig>,
+    archetypes: &Archetypes,
+    mut high_watermark: Local<usize>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    // Track the peak even while hidden so enabling the line later still reports it.
+    let count = archetypes.len();
+    *high_watermark = (*high_watermark).max(count);
+
+    if !config.show_archetype_count {
+        return;
+    }
+
+    writer.write(
+        "archetype_count",
+        format!("Archetypes: {count} (peak {})", *high_watermark),
+    );
+}
+
+fn write_time_scale(
+    config: Res<DebugVisConfig>,
+    time: Res<Time<Virtual>>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    if !config.show_time_scale {
+        return;
+    }
+
+    let speed = time.relative_speed_f64();
+    // Slow motion or fast-forward left on by accident skews every other number on screen.
+    let color = if speed == 1.0 {
+        DEBUG_TEXT_COLOR
+    } else {
+        Color::srgb(1.0, 1.0, 0.0)
+    };
+    writer.write_colored("time_scale", format!("Speed: {speed:.1}×"), color);
+}
+
+/// Closes the second-long buckets of every [`DebugTextWriter::write_rate`] line and
+/// rewrites them. The rate shown is the last complete second.
+fn update_debug_rates(time: Res<Time<Real>>, mut writer: DebugTextWriter) {
+    let now = time.elapsed();
+    let none_str = writer.config.none_str.clone();
+    let mut lines = Vec::with_capacity(writer.texts.rates.len());
+    for (key, counter) in writer.texts.rates.iter_mut() {
+        let bucket_start = *counter.bucket_start.get_or_insert(now);
+        if now.saturating_sub(bucket_start) >= Duration::from_secs(1) {
+            counter.per_second.push_back(std::mem::take(&mut counter.pending));
+            if counter.per_second.len() > RATE_HISTORY_SECONDS {
+                counter.per_second.pop_front();
+            }
+            counter.bucket_start = Some(now);
+        }
+
+        let message = match counter.per_second.back() {
+            Some(rate) => {
+                let history: Vec<f32> = counter.per_second.iter().map(|&n| n as f32).collect();
+                format!("{}: {rate} {}", counter.label, sparkline(&history))
+            }
+            None => format!("{}: {none_str}", counter.label),
+        };
+        lines.push((key.clone(), message));
+    }
+    for (key, message) in lines {
+        writer.write(key, message);
+    }
+}
+
+fn periodic_log_summary(
+    config: Res<DebugVisConfig>,
+    time: Res<Time<Real>>,
+    texts: Res<DebugTexts>,
+    mut last_log: Local<Duration>,
+    mut last_logged: Local<HashMap<String, String>>,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let Some(interval) = config.log_interval else {
+        return;
+    };
+
+    let now = time.elapsed();
+    if now.saturating_sub(*last_log) < interval {
+        return;
+    }
+    *last_log = now;
+
+    let mut entries: Vec<_> = texts
+        .entries
+        .iter()
+        .filter(|(key, entry)| {
+            !config.log_only_changed || last_logged.get(*key) != Some(&entry.message)
+        })
+        .collect();
+    if config.log_only_changed {
+        *last_logged = texts
+            .entries
+            .iter()
+            .map(|(key, entry)| (key.clone(), entry.message.clone()))
+            .collect();
+    }
+    if entries.is_empty() {
+        return;
+    }
+    entries.sort_by_key(|(_, entry)| entry.line);
+
+    let summary = entries
+        .into_iter()
+        .map(|(key, entry)| {
+            if config.ansi_colors {
+                format!(
+                    "{}={}",
+                    AnsiColor::wrap(key, AnsiColor::GREEN),
+                    AnsiColor::wrap(&entry.message, AnsiColor::WHITE)
+                )
+            } else {
+                format!("{key}={}", entry.message)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    info!("debug_vis: {summary}");
+}
+
+fn update_overlay_cost_display(
+    level: Res<DebugLevel>,
+    config: Res<DebugVisConfig>,
+    mut cost: ResMut<OverlayCost>,
+    mut query: Query<(&mut Text, &mut TextColor), With<OverlayCostText>>,
+) {
+    let nanos = cost.accumulated_nanos.swap(0, Ordering::Relaxed);
+    cost.last_frame = Duration::from_nanos(nanos);
+
+    if *level != DebugLevel::Full {
+        return;
+    }
+
+    let Ok((mut text, mut color)) = query.single_mut() else {
+        return;
+    };
+
+    let cost_ms = cost.last_frame.as_secs_f64() * 1000.0;
+    text.0 = format!("Overlay cost: {:.2} ms", cost_ms);
+    color.0 = if cost_ms > config.overlay_cost_warn_ms {
+        Color::srgb(1.0, 1.0, 0.0)
+    } else {
+        DEBUG_TEXT_COLOR
+    };
+}
+
+fn setup_debug_top_gizmo_config(
+    debug_config: Res<DebugVisConfig>,
+    mut config_store: ResMut<GizmoConfigStore>,
+) {
+    let (config, _) = config_store.config_mut::<DebugTopGizmoGroup>();
+    config.depth_bias = -1.0;
+    config.line.width = debug_config.gizmo_line_width;
+    config.render_layers = debug_config
+        .overlay_camera_layer
+        .map(RenderLayers::layer)
+        .unwrap_or_default();
+}
+
+fn spawn_overlay_camera(mut commands: Commands, config: Res<DebugVisConfig>) {
+    let Some(layer) = config.overlay_camera_layer else {
+        return;
+    };
+
+    commands.spawn((
+        DebugOverlayCamera,
+        Camera2d,
+        Camera {
+            order: OVERLAY_CAMERA_ORDER,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        RenderLayers::layer(layer),
+    ));
+}
+
+/// Lifts a freshly spawned overlay root above the host's UI and, if there is one, onto the
+/// overlay camera.
+fn layer_overlay_root<C: Component>(
+    add: On<Add, C>,
+    config: Res<DebugVisConfig>,
+    overlay_camera: Query<Entity, With<DebugOverlayCamera>>,
+    view: OverlayView,
+    mut commands: Commands,
+) {
+    let mut entity = commands.entity(add.entity);
+    entity.insert_if_new(GlobalZIndex(config.overlay_z_index));
+    if let Some(camera) = overlay_camera.single().ok().or_else(|| view.ui_camera()) {
+        entity.insert(UiTargetCamera(camera));
+    }
+}
+
+/// Where the chart's bars go, in logical px. The chart's reference lines and labels are all
+/// laid out from this, so they move with it.
+struct ChartRect {
+    /// Bottom-left corner of the bars.
+    origin: Vec2,
+    width: f32,
+    height: f32,
+}
+
+impl ChartRect {
+    fn new(config: &DebugVisConfig, viewport_size: Vec2) -> Self {
+        let width = config.chart_width_px;
+        // Bottom placements leave two lines free under the bars, for the frame range label
+        // and the legend.
+        let right = viewport_size.x - width - CHART_LABEL_WIDTH - CHART_MARGIN;
+        let bottom = viewport_size.y - CHART_MARGIN - 2.0 * LINE_HEIGHT;
+        let origin = match config.chart_placement {
+            ChartPlacement::TopLeft => Vec2::new(CHART_MARGIN, 160.0),
+            ChartPlacement::TopRight => Vec2::new(right, 160.0),
+            ChartPlacement::BottomRight => Vec2::new(right, bottom),
+            ChartPlacement::BottomCenter => Vec2::new((viewport_size.x - width) / 2.0, bottom),
+            ChartPlacement::Custom(origin) => origin,
+        };
+        Self {
+            origin,
+            width,
+            height: CHART_HEIGHT,
+        }
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        (self.origin.x..=self.origin.x + self.width).contains(&point.x)
+            && (self.origin.y - self.height..=self.origin.y).contains(&point.y)
+    }
+}
+
+/// Maps viewport positions (logical px) onto a plane facing the camera just in front of it,
+/// so gizmos can be drawn at UI coordinates.
+///
+/// Three rays pin the plane down at `origin`. Viewport to world is affine on such a plane,
+/// for perspective and orthographic cameras alike, so every other point is interpolated
+/// instead of projected.
+#[derive(Clone, Copy, PartialEq)]
+struct ViewportPlane {
+    viewport_origin: Vec2,
+    origin: Vec3,
+    unit_x: Vec3,
+    unit_y: Vec3,
+}
+
+impl ViewportPlane {
+    const DEPTH: f32 = 0.5;
+
+    /// `extent` is the size of the area drawn in, per axis and with sign; the axes are
+    /// measured across all of it rather than one pixel, to keep f32 error down.
+    fn new(
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        viewport_origin: Vec2,
+        extent: Vec2,
+    ) -> Option<Self> {
+        let ray_at = |point: Vec2| camera.viewport_to_world(camera_transform, point).ok();
+        let origin = ray_at(viewport_origin)?.get_point(Self::DEPTH);
+        let plane = InfinitePlane3d::new(camera_transform.forward());
+        let on_plane = |offset: Vec2| {
+            let ray = ray_at(viewport_origin + offset)?;
+            let distance = ray.intersect_plane(origin, plane)?;
+            Some(ray.get_point(distance) - origin)
+        };
+        let across = on_plane(Vec2::new(extent.x, 0.0))?;
+        let up = on_plane(Vec2::new(0.0, extent.y))?;
+        Some(Self {
+            viewport_origin,
+            origin,
+            unit_x: across / extent.x,
+            unit_y: up / extent.y,
+        })
+    }
+
+    fn project(&self, point: Vec2) -> Vec3 {
+        let offset = point - self.viewport_origin;
+        self.origin + self.unit_x * offset.x + self.unit_y * offset.y
+    }
+}
+
+/// The chart's bars as projected gizmo lines, and its percentiles. Building them is most of
+/// the chart's cost, one line per physical pixel column and a sort of the window, so they're
+/// kept until the history, config or the chart's placement changes, and redrawn as they are
+/// otherwise.
+#[derive(Default)]
+struct ChartBarCache {
+    /// Plane and scale factor the lines were projected with.
+    projection: Option<(ViewportPlane, f32)>,
+    /// Slot index and frame time of each bar, after resampling.
+    bars: Vec<(usize, f64)>,
+    columns: Vec<(Vec3, Vec3, Color)>,
+    near_misses: Vec<[Vec3; 4]>,
+    /// [`CHART_PERCENTILES`] of the history, which sorts the whole window to find.
+    percentiles: Option<[f64; CHART_PERCENTILES.len()]>,
+}
+
+fn draw_frametime_barchart(
+    level: Res<DebugLevel>,
+    widgets: Res<DebugWidgets>,
+    config: Res<DebugVisConfig>,
+    history: Res<FrameTimeHistory>,
+    refresh_interval: Res<vsync::RefreshInterval>,
+    timeline: Res<TimelineEvents>,
+    overlay_camera: Query<(&Camera, &GlobalTransform), With<DebugOverlayCamera>>,
+    view: OverlayView,
+    mut gizmos: Gizmos<DebugTopGizmoGroup>,
+    mut writer: DebugTextWriter,
+    mut cache: Local<ChartBarCache>,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    if *level != DebugLevel::Full || !widgets.contains(DebugWidgets::CHART) {
+        return;
+    }
+
+    let Some((camera, camera_transform)) = overlay_camera
+        .single()
+        .ok()
+        .or_else(|| view.camera().map(|(_, camera, transform)| (camera, transform)))
+    else {
+        return;
+    };
+
+    let draw_raw = config.chart_series != ChartSeries::Smoothed;
+    let draw_smoothed = config.chart_series != ChartSeries::Raw;
+
+    // Both series share one scale so the line sits where it belongs relative to the bars.
+    let max_ms = history
+        .frame_times_ms
+        .iter()
+        .filter(|_| draw_raw)
+        .chain(history.smoothed_ms.iter().filter(|_| draw_smoothed))
+        .copied()
+        .fold(0.0_f64, f64::max);
+
+    let avg_ms = history.sum_seconds * 1000.0 / history.frame_times_ms.len() as f64;
+
+    // Viewport positions are logical pixels; this converts to physical ones for column fill.
+    let scale_factor = camera.target_scaling_factor().unwrap_or(1.0);
+
+    let Some(viewport_size) = camera.logical_viewport_size() else {
+        return;
+    };
+    let ChartRect {
+        origin: chart_origin,
+        width: chart_width,
+        height: max_height,
+    } = ChartRect::new(&config, viewport_size);
+    let sample_width = chart_width / history.frame_times_ms.len() as f32;
+
+    let Some(plane) = ViewportPlane::new(
+        camera,
+        camera_transform,
+        chart_origin,
+        Vec2::new(chart_width, -max_height),
+    ) else {
+        return;
+    };
+
+    // The bars run from zero up to the window's max, so those are the axis ends.
+    writer.write_widget_label(
+        DebugWidgets::CHART,
+        "chart_y_max",
+        format!("{max_ms:.1}ms"),
+        Vec2::new(chart_origin.x + 2.0, chart_origin.y - max_height),
+    );
+    writer.write_widget_label(
+        DebugWidgets::CHART,
+        "chart_y_min",
+        "0ms",
+        Vec2::new(chart_origin.x + 2.0, chart_origin.y - LINE_HEIGHT),
+    );
+
+    let projection = Some((plane, scale_factor));
+    let stale = history.is_changed()
+        || config.is_changed()
+        || refresh_interval.is_changed()
+        || cache.projection != projection;
+    if stale {
+        // More bars than physical pixels would just overdraw each other.
+        let chart_pixel_width = (chart_width * scale_factor).floor().max(1.0) as usize;
+        let bars: Vec<(usize, f64)> = if history.frame_times_ms.len() > chart_pixel_width {
+            history.resample(chart_pixel_width).into_iter().enumerate().collect()
+        } else {
+            history.iter_indexed().collect()
+        };
+        let bar_width = chart_width / bars.len() as f32;
+        let ramp_reference_ms = match config.chart_color_ramp {
+            ChartColorRamp::Budget => config.frame_budget_ms(),
+            ChartColorRamp::Average => avg_ms,
+        };
+
+        cache.projection = projection;
+        cache.columns.clear();
+        cache.near_misses.clear();
+        for &(idx, frame_time) in bars.iter().filter(|_| draw_raw) {
+            let color_ratio = if frame_time > ramp_reference_ms { 0.2 + ((frame_time / ramp_reference_ms - 1.0).clamp(0.0, 1.0) * 0.8) } else { (frame_time / ramp_reference_ms) * 0.2}; // a frame time at the reference is 20% red, twice the reference is 100% red
+            let ratio = (frame_time / max_ms).clamp(0.0, 1.0) as f32;
+            let height = max_height * ratio;
+            let slot_x = chart_origin.x + idx as f32 * bar_width;
+            let color = Color::srgb(color_ratio as f32, 1.0 - color_ratio as f32, 0.0);
+
+            // Fill the bar with one column per physical pixel, so HiDPI screens don't show gaps
+            // between them; a gap that eats the whole slot leaves a single line.
+            let columns = ((bar_width - config.chart_bar_gap) * scale_factor).floor().max(1.0) as usize;
+            for column in 0..columns {
+                let x = slot_x + column as f32 / scale_factor;
+                let base = Vec2::new(x, chart_origin.y);
+                let top = Vec2::new(x, chart_origin.y - height);
+
+                cache.columns.push((plane.project(base), plane.project(top), color));
+            }
+
+            // Under vsync a near miss costs a whole refresh, however small the overshoot
+            // looks, so those bars get an outline of their own.
+            let near_miss = refresh_interval.0.is_some_and(|interval_ms| {
+                VsyncClass::classify(frame_time, interval_ms) == VsyncClass::NearMiss
+            });
+            if near_miss {
+                let right = slot_x + (bar_width - config.chart_bar_gap).max(1.0 / scale_factor);
+                let corners = [
+                    Vec2::new(slot_x, chart_origin.y),
+                    Vec2::new(slot_x, chart_origin.y - height),
+                    Vec2::new(right, chart_origin.y - height),
+                    Vec2::new(right, chart_origin.y),
+                ];
+                cache.near_misses.push(corners.map(|corner| plane.project(corner)));
+            }
+        }
+        cache.bars = bars;
+        cache.percentiles = history.percentiles(CHART_PERCENTILES.map(|(p, _)| p));
+    }
+    for &(base, top, color) in &cache.columns {
+        gizmos.line(base, top, color);
+    }
+    for &corners in &cache.near_misses {
+        gizmos.linestrip(corners, vsync::NEAR_MISS_COLOR);
+    }
+    let bars = &cache.bars;
+    let bar_width = chart_width / bars.len() as f32;
 
-    let consistency_visibility = if *level == DebugLevel::Full {
-        Visibility::Inherited
-    } else {
-        Visibility::Hidden
-    };
+    // A marker and value over the window's worst bar.
+    let worst = bars
+        .iter()
+        .copied()
+        .filter(|_| draw_raw)
+        .max_by(|(_, a), (_, b)| a.total_cmp(b));
+    if let Some((idx, frame_time)) = worst {
+        let x = chart_origin.x + (idx as f32 + 0.5) * bar_width;
+        let top = chart_origin.y - max_height * (frame_time / max_ms).clamp(0.0, 1.0) as f32;
+        let tip = Vec2::new(x, top - 1.0);
+        let size = WORST_BAR_MARKER_SIZE;
+        let corners = [
+            tip,
+            tip + Vec2::new(-size, -size),
+            tip + Vec2::new(size, -size),
+            tip,
+        ];
+        gizmos.linestrip(corners.map(|corner| plane.project(corner)), WORST_BAR_MARKER_COLOR);
+        writer.write_widget_label(
+            DebugWidgets::CHART,
+            "chart_worst",
+            format!("{frame_time:.1}ms"),
+            Vec2::new(x + size, top - size - LINE_HEIGHT),
+        );
+        writer.set_value_color("chart_worst", WORST_BAR_MARKER_COLOR);
+    }
 
-    commands.spawn((
-        FrametimeConsistencyText,
-        Text::new(format!("Frametime avg ({}): --", FRAME_DELTA_WINDOW)),
-        TextFont {
-            font_size: 16.0,
-            ..default()
-        },
-        TextColor(Color::srgb(0.0, 1.0, 0.0)),
-        TextShadow {
-            offset: Vec2::new(1.0, 1.0),
-            color: Color::srgb(0.0, 0.0, 0.0),
-        },
-        Node {
-            position_type: PositionType::Absolute,
-            top: Val::Px(28.0),
-            left: Val::Px(8.0),
-            ..default()
-        },
-        consistency_visibility,
-    ));
+    if draw_raw {
+        writer.write_widget_label(
+            DebugWidgets::CHART,
+            "chart_legend",
+            config.chart_color_ramp.legend(config.frame_budget_ms()),
+            Vec2::new(chart_origin.x, chart_origin.y + 2.0 + LINE_HEIGHT),
+        );
+    }
 
-    commands.spawn((
-        FrametimeMaxDeltaText,
-        Text::new(format!("Frametime max ({}): --", FRAME_DELTA_WINDOW)),
-        TextFont {
-            font_size: 16.0,
-            ..default()
-        },
-        TextColor(Color::srgb(0.0, 1.0, 0.0)),
-        TextShadow {
-            offset: Vec2::new(1.0, 1.0),
-            color: Color::srgb(0.0, 0.0, 0.0),
-        },
-        Node {
-            position_type: PositionType::Absolute,
-            top: Val::Px(48.0),
-            left: Val::Px(8.0),
-            ..default()
-        },
-        consistency_visibility,
-    ));
-}
+    if draw_smoothed {
+        let points = history.smoothed_ms.iter().enumerate().map(|(idx, &smoothed)| {
+            let ratio = (smoothed / max_ms).clamp(0.0, 1.0) as f32;
+            let x = chart_origin.x + (idx as f32 + 0.5) * sample_width;
+            plane.project(Vec2::new(x, chart_origin.y - max_height * ratio))
+        });
+        gizmos.linestrip(points, SMOOTHED_LINE_COLOR);
+    }
 
-fn update_frame_time_history(
-    diagnostics: Res<DiagnosticsStore>,
-    mut history: ResMut<FrameTimeHistory>,
-) {
-    let Some(frame_time_ms) = diagnostics
-        .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
-        .and_then(|d| d.smoothed())
-    else {
+    if let Some((first, last)) = history.frame_range() {
+        for event in timeline.in_frames(first, last) {
+            let index = history.frames.partition_point(|&frame| frame < event.frame);
+            let x = chart_origin.x + (index as f32 + 0.5) * sample_width;
+            gizmos.line(
+                plane.project(Vec2::new(x, chart_origin.y)),
+                plane.project(Vec2::new(x, chart_origin.y - max_height)),
+                timeline::TIMELINE_MARKER_COLOR,
+            );
+        }
+
+        writer.write_widget_label(
+            DebugWidgets::CHART,
+            "chart_frames",
+            format!(
+                "frames {}–{}",
+                group_thousands(first),
+                group_thousands(last)
+            ),
+            Vec2::new(chart_origin.x, chart_origin.y + 2.0),
+        );
+    }
+
+    // Hovering reads out the frame under the cursor, by sample rather than by bar, so a
+    // downsampled chart still gives exact frames.
+    let hovered = view
+        .window()
+        .and_then(Window::cursor_position)
+        .filter(|&cursor| ChartRect::new(&config, viewport_size).contains(cursor))
+        .and_then(|cursor| {
+            let index = ((cursor.x - chart_origin.x) / sample_width) as usize;
+            let index = index.min(history.frame_times_ms.len().saturating_sub(1));
+            Some((index, history.frames.get(index)?, history.frame_times_ms.get(index)?))
+        });
+    if let Some((index, frame, frame_time)) = hovered {
+        let x = chart_origin.x + (index as f32 + 0.5) * sample_width;
+        gizmos.line(
+            plane.project(Vec2::new(x, chart_origin.y)),
+            plane.project(Vec2::new(x, chart_origin.y - max_height)),
+            CHART_CURSOR_COLOR,
+        );
+        writer.write_widget_label(
+            DebugWidgets::CHART,
+            "chart_hover",
+            format!("frame {}: {frame_time:.2}ms", group_thousands(*frame)),
+            Vec2::new(x + 4.0, chart_origin.y - max_height - LINE_HEIGHT),
+        );
+    }
+
+    let Some(percentiles) = cache.percentiles else {
         return;
     };
+    for ((p, color), value_ms) in CHART_PERCENTILES.into_iter().zip(percentiles) {
+        let y = chart_origin.y - max_height * (value_ms / max_ms).clamp(0.0, 1.0) as f32;
+        gizmos.line(
+            plane.project(Vec2::new(chart_origin.x, y)),
+            plane.project(Vec2::new(chart_origin.x + chart_width, y)),
+            color,
+        );
 
-    history.frame_times_ms.push_back(frame_time_ms);
-    history.sum_seconds += frame_time_ms / 1000.0;
-    if history.frame_times_ms.len() > FRAME_DELTA_WINDOW {
-        if let Some(removed) = history.frame_times_ms.pop_front() {
-            history.sum_seconds -= removed / 1000.0;
-        }
+        let key = format!("chart_p{p}");
+        writer.write_widget_label(
+            DebugWidgets::CHART,
+            key.clone(),
+            format!("P{p}: {value_ms:.1}ms"),
+            Vec2::new(chart_origin.x + chart_width + 4.0, y - LINE_HEIGHT / 2.0),
+        );
+        writer.set_value_color(&key, color);
     }
 }
 
-fn update_fps_display(
+/// Alt + left drag moves the chart, switching it to [`ChartPlacement::Custom`]. The final
+/// position is logged, for copying into the config.
+fn drag_chart(
+    mut config: ResMut<DebugVisConfig>,
     level: Res<DebugLevel>,
-    history: Res<FrameTimeHistory>,
-    mut query: Query<&mut Text, With<FpsText>>,
+    widgets: Res<DebugWidgets>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    view: OverlayView,
+    mut grab_offset: Local<Option<Vec2>>,
 ) {
-    if *level == DebugLevel::Hidden {
+    if *level != DebugLevel::Full || !widgets.contains(DebugWidgets::CHART) {
+        *grab_offset = None;
         return;
     }
-
-    let Ok(mut text) = query.single_mut() else {
+    let Some(window) = view.window() else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
         return;
     };
 
-    let mut window_seconds = 0.0;
-    let mut frames = 0usize;
-    for frame_time_ms in history.frame_times_ms.iter().rev() {
-        window_seconds += frame_time_ms / 1000.0;
-        frames += 1;
-        if window_seconds >= FPS_AVG_WINDOW_SECONDS {
-            break;
-        }
+    if mouse.just_released(MouseButton::Left) && grab_offset.take().is_some() {
+        info!("debug_vis: chart moved to {:?}", config.chart_placement);
+        return;
     }
 
-    if window_seconds > 0.0 {
-        let fps = frames as f64 / window_seconds;
-        text.0 = format!("FPS: {:.0}", fps);
+    let rect = ChartRect::new(&config, window.size());
+    if grab_offset.is_none()
+        && mouse.just_pressed(MouseButton::Left)
+        && keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+        && rect.contains(cursor)
+    {
+        *grab_offset = Some(cursor - rect.origin);
+    }
+    if let Some(offset) = *grab_offset {
+        config.chart_placement = ChartPlacement::Custom(cursor - offset);
     }
 }
 
-fn update_frametime_consistency_display(
-    level: Res<DebugLevel>,
-    history: Res<FrameTimeHistory>,
-    mut text_queries: ParamSet<(
-        Query<&mut Text, (With<FrametimeConsistencyText>, Without<FpsText>)>,
-        Query<&mut Text, (With<FrametimeMaxDeltaText>, Without<FpsText>)>,
-    )>,
+fn draw_cursor_gizmo(
+    config: Res<DebugVisConfig>,
+    view: OverlayView,
+    mut gizmos: Gizmos,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
 ) {
-    if *level != DebugLevel::Full {
+    let _cost = cost.measure();
+
+    if !config.show_cursor_world_pos {
         return;
     }
 
-    let (avg_label, max_label) = {
-        if history.frame_times_ms.is_empty() {
-            (
-                format!("Frametime avg ({}): --", FRAME_DELTA_WINDOW),
-                format!("Frametime max ({}): --", FRAME_DELTA_WINDOW),
-            )
-        } else {
-            let avg = (history.sum_seconds * 1000.0) / history.frame_times_ms.len() as f64;
-            let max_frame_time = history
-                .frame_times_ms
-                .iter()
-                .copied()
-                .fold(0.0_f64, f64::max);
-            (
-                format!("Frametime avg ({}): {:.2}", FRAME_DELTA_WINDOW, avg),
-                format!("Frametime max ({}): {:.2}", FRAME_DELTA_WINDOW, max_frame_time),
-            )
-        }
+    // Outside the window, or with the ray parallel to the plane, the line stays but blanks
+    // out rather than disappearing and shifting every line below it.
+    let Some(cursor) = view.window().and_then(Window::cursor_position) else {
+        writer.write("cursor_world_pos", format!("Cursor: {}", config.none_str));
+        return;
     };
-
-    let mut avg_query = text_queries.p0();
-    let Ok(mut avg_text) = avg_query.single_mut() else {
+    let Some((_, camera, camera_transform)) = view.camera() else {
         return;
     };
-    avg_text.0 = avg_label;
-
-    let mut max_query = text_queries.p1();
-    let Ok(mut max_text) = max_query.single_mut() else {
+    let normal = Dir3::new(config.cursor_plane_normal).unwrap_or(Dir3::Y);
+    let plane_origin = normal * config.cursor_plane_height;
+    let point = camera
+        .viewport_to_world(camera_transform, cursor)
+        .ok()
+        .and_then(|ray| {
+            ray.intersect_plane(plane_origin, InfinitePlane3d { normal })
+                .map(|distance| ray.get_point(distance))
+        });
+    let Some(point) = point else {
+        writer.write(
+            "cursor_world_pos",
+            format!(
+                "Cursor: ({:.0}, {:.0}) → world {}",
+                cursor.x, cursor.y, config.none_str
+            ),
+        );
         return;
     };
-    max_text.0 = max_label;
+
+    // Circles are drawn facing +Z; turn this one to lie flat on the plane.
+    gizmos.circle(
+        Isometry3d::new(point, Quat::from_rotation_arc(Vec3::Z, *normal)),
+        CURSOR_CIRCLE_RADIUS,
+        Color::srgb(0.0, 1.0, 0.0),
+    );
+    writer.write(
+        "cursor_world_pos",
+        format!(
+            "Cursor: ({:.0}, {:.0}) → world ({:.1}, {:.1}, {:.1})",
+            cursor.x, cursor.y, point.x, point.y, point.z
+        ),
+    );
 }
 
-// fn toggle_debug_level(
-//     mut debug_reader: MessageReader<DebugAction>,
-//     mut level: ResMut<DebugLevel>,
-// ) {
-//     for event in debug_reader.read() {
-//         match event {
-//             DebugAction::ToggleDebugLevel => {
-//                 *level = match *level {
-//                     DebugLevel::Hidden => DebugLevel::FpsOnly,
-//                     DebugLevel::FpsOnly => DebugLevel::Full,
-//                     DebugLevel::Full => DebugLevel::Hidden,
-//                 };
-//             }
-//         }
-//     }
-// }
+fn draw_contact_normals(
+    config: Res<DebugVisConfig>,
+    contacts: Query<&ContactNormal>,
+    mut gizmos: Gizmos,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
 
-fn apply_debug_visibility(
+    if !config.show_contact_normals || config.contact_normal_max_length <= 0.0 {
+        return;
+    }
+
+    for contact in contacts.iter() {
+        let Some(direction) = contact.normal.try_normalize() else {
+            continue;
+        };
+        let length = (contact.depth.abs() * config.contact_normal_scale)
+            .min(config.contact_normal_max_length);
+        let depth_ratio = length / config.contact_normal_max_length;
+        let color = Color::srgb(depth_ratio, 1.0 - depth_ratio, 0.0);
+
+        gizmos.arrow(contact.point, contact.point + direction * length, color);
+    }
+}
+
+fn draw_camera_frustum(
     level: Res<DebugLevel>,
-    mut fps_query: Query<&mut Visibility, (With<FpsText>, Without<FrametimeConsistencyText>)>,
-    mut consistency_query: Query<&mut Visibility, (With<FrametimeConsistencyText>, Without<FpsText>)>,
-    mut debug_query: Query<
-        &mut Visibility,
-        (
-            With<DebugLabel>,
-            Without<FpsText>,
-            Without<FrametimeConsistencyText>,
-        ),
-    >,
+    config: Res<DebugVisConfig>,
+    cameras: Query<&Frustum, (With<Camera>, Without<DebugOverlayCamera>)>,
+    mut gizmos: Gizmos<DebugTopGizmoGroup>,
+    cost: Res<OverlayCost>,
 ) {
-    if !level.is_changed() {
+    let _cost = cost.measure();
+
+    if *level != DebugLevel::Full || !config.show_camera_frustum {
         return;
     }
 
-    let (fps_vis, consistency_vis, debug_vis) = match *level {
-        DebugLevel::Hidden => (Visibility::Hidden, Visibility::Hidden, Visibility::Hidden),
-        DebugLevel::FpsOnly => (Visibility::Inherited, Visibility::Hidden, Visibility::Hidden),
-        DebugLevel::Full => (
-            Visibility::Inherited,
-            Visibility::Inherited,
-            Visibility::Inherited,
-        ),
+    for frustum in cameras.iter() {
+        let Some(corners) = frustum_corners(frustum) else {
+            continue;
+        };
+        // Half space `2 * axis + side` bounds the face whose corners have that bit set to
+        // `side`; the other two bits walk around it.
+        for (face, color) in FRUSTUM_FACE_COLORS.into_iter().enumerate() {
+            let (axis, side) = (face / 2, face % 2);
+            let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+            let loop_corners = [(0, 0), (1, 0), (1, 1), (0, 1), (0, 0)]
+                .map(|(a, b)| corners[(side << axis) | (a << u) | (b << v)]);
+            gizmos.linestrip(loop_corners, color);
+        }
+    }
+}
+
+/// The eight corners of `frustum`, indexed by bits: bit 0 picks left or right, bit 1 top or
+/// bottom, bit 2 near or far. `None` when planes don't meet in a point, as with no far plane.
+fn frustum_corners(frustum: &Frustum) -> Option<[Vec3; 8]> {
+    let planes = &frustum.half_spaces;
+    let mut corners = [Vec3::ZERO; 8];
+    for (bits, corner) in corners.iter_mut().enumerate() {
+        *corner = plane_intersection([
+            &planes[bits & 1],
+            &planes[2 + ((bits >> 1) & 1)],
+            &planes[4 + ((bits >> 2) & 1)],
+        ])?;
+    }
+    Some(corners)
+}
+
+/// The point on all three planes, where `normal · point + d = 0` for each.
+fn plane_intersection([a, b, c]: [&HalfSpace; 3]) -> Option<Vec3> {
+    let (na, nb, nc) = (Vec3::from(a.normal()), Vec3::from(b.normal()), Vec3::from(c.normal()));
+    let determinant = na.dot(nb.cross(nc));
+    if determinant.abs() <= f32::EPSILON {
+        return None;
+    }
+    let point =
+        -(a.d() * nb.cross(nc) + b.d() * nc.cross(na) + c.d() * na.cross(nb)) / determinant;
+    point.is_finite().then_some(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::{
+        render::{
+            RenderPlugin,
+            settings::{RenderCreation, WgpuSettings},
+        },
+        window::ExitCondition,
+        winit::WinitPlugin,
     };
 
-    for mut vis in fps_query.iter_mut() {
-        if *vis != fps_vis {
-            *vis = fps_vis;
+    /// `DefaultPlugins` without a window or GPU.
+    pub(super) fn headless_plugins() -> impl PluginGroup {
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .set(RenderPlugin {
+                render_creation: RenderCreation::Automatic(WgpuSettings {
+                    backends: None,
+                    ..default()
+                }),
+                ..default()
+            })
+            .disable::<WinitPlugin>()
+    }
+
+    #[cfg(feature = "debug-vis")]
+    fn headless_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((headless_plugins(), DebugVisPlugin::default()));
+        app.finish();
+        app.cleanup();
+        app
+    }
+
+    /// Deterministic frame times between 5 and 40 ms, with a long spike every 37 samples.
+    fn noisy_frame_times(count: usize) -> Vec<f64> {
+        let mut state = 0x2545_f491_u32;
+        (0..count)
+            .map(|i| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = f64::from(state >> 8) / f64::from(1_u32 << 24);
+                if i % 37 == 0 { 90.0 } else { 5.0 + noise * 35.0 }
+            })
+            .collect()
+    }
+
+    fn history_of(frame_times_ms: &[f64]) -> FrameTimeHistory {
+        let mut history = FrameTimeHistory::default();
+        for (frame, &frame_time_ms) in frame_times_ms.iter().enumerate() {
+            history.push(frame_time_ms, None, frame as u32);
         }
+        history
     }
-    for mut vis in consistency_query.iter_mut() {
-        if *vis != consistency_vis {
-            *vis = consistency_vis;
+
+    /// The samples still in the window, oldest first.
+    fn window_of(frame_times_ms: &[f64]) -> &[f64] {
+        &frame_times_ms[frame_times_ms.len().saturating_sub(FRAME_DELTA_WINDOW)..]
+    }
+
+    /// Seconds of pushes at `frame_time_ms` until the FPS readout is within 2% of it.
+    fn seconds_to_converge(fps_window: &mut FpsWindow, frame_time_ms: f64) -> f64 {
+        let target = 1000.0 / frame_time_ms;
+        let mut elapsed_ms = 0.0;
+        while elapsed_ms < 5000.0 {
+            fps_window.push(frame_time_ms);
+            elapsed_ms += frame_time_ms;
+            if fps_window.fps().is_some_and(|fps| (fps / target - 1.0).abs() < 0.02) {
+                break;
+            }
         }
+        elapsed_ms / 1000.0
     }
-    for mut vis in debug_query.iter_mut() {
-        if *vis != debug_vis {
-            *vis = debug_vis;
+
+    #[test]
+    fn fps_converges_after_step_change() {
+        let mut fps_window = FpsWindow::default();
+        for _ in 0..300 {
+            fps_window.push(6.9);
         }
+
+        // The step reset drops the old regime after FPS_STEP_FRAMES frames, well before the
+        // averaging window would have aged it out on its own.
+        let seconds = seconds_to_converge(&mut fps_window, 16.6);
+        let step_seconds = FPS_STEP_FRAMES as f64 * 16.6 / 1000.0;
+        assert!(seconds <= step_seconds + 1e-9, "took {seconds:.3}s to converge");
     }
-}
 
-pub fn debug_text(key: impl Into<String>, message: impl Into<String>) {
-    enqueue_request(DebugRequest {
-        key: key.into(),
-        message: message.into(),
-        persistent: false,
-    });
-}
+    #[test]
+    fn fps_keeps_window_through_single_spike() {
+        let mut fps_window = FpsWindow::default();
+        for _ in 0..60 {
+            fps_window.push(16.6);
+        }
+        let window_len = fps_window.frame_times_ms.len();
 
-pub fn debug_text_persistent(key: impl Into<String>, message: impl Into<String>) {
-    enqueue_request(DebugRequest {
-        key: key.into(),
-        message: message.into(),
-        persistent: true,
-    });
-}
+        fps_window.push(50.0);
+        for _ in 0..3 {
+            fps_window.push(16.6);
+        }
+        assert!(fps_window.frame_times_ms.len() > FPS_STEP_FRAMES);
+        assert!(fps_window.frame_times_ms.len() >= window_len - 4);
+    }
 
-struct DebugRequest {
-    key: String,
-    message: String,
-    persistent: bool,
-}
+    #[test]
+    fn fps_resets_on_present_mode_change() {
+        let mut world = World::new();
+        world.init_resource::<DebugVisConfig>();
+        world.init_resource::<Time<Real>>();
+        world.init_resource::<FpsWindow>();
+        let window = world
+            .spawn((
+                Window {
+                    present_mode: PresentMode::AutoVsync,
+                    ..default()
+                },
+                PrimaryWindow,
+            ))
+            .id();
+        let system = world.register_system(update_fps_window);
 
-static DEBUG_QUEUE: OnceLock<Mutex<Vec<DebugRequest>>> = OnceLock::new();
+        let fill = |world: &mut World| {
+            let mut fps_window = world.resource_mut::<FpsWindow>();
+            for _ in 0..30 {
+                fps_window.push(16.6);
+            }
+        };
+        world.run_system(system).unwrap();
+        fill(&mut world);
+        world.run_system(system).unwrap();
+        assert!(world.resource::<FpsWindow>().fps().is_some());
 
-fn enqueue_request(req: DebugRequest) {
-    if let Ok(mut queue) = DEBUG_QUEUE.get_or_init(|| Mutex::new(Vec::new())).lock() {
-        queue.push(req);
+        world.get_mut::<Window>(window).unwrap().present_mode = PresentMode::AutoNoVsync;
+        world.run_system(system).unwrap();
+        assert!(world.resource::<FpsWindow>().fps().is_none());
     }
-}
 
-fn drain_debug_queue(mut writer: DebugTextWriter) {
-    let Some(queue) = DEBUG_QUEUE.get() else {
-        return;
-    };
-    let mut queue = queue.lock().unwrap();
-    for req in queue.drain(..) {
-        writer.write_with_persistence(req.key, req.message, req.persistent);
+    #[test]
+    fn snapshot_matches_brute_force() {
+        let samples = noisy_frame_times(FRAME_DELTA_WINDOW * 2 + 17);
+        let window = window_of(&samples);
+        let history = history_of(&samples);
+
+        let mut sorted = window.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let nearest_rank = |p: f64| {
+            let rank = (p / 100.0 * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+        let avg_ms = window.iter().sum::<f64>() / window.len() as f64;
+        let max_ms = window.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let mut last_second_ms = 0.0;
+        let last_second = window
+            .iter()
+            .rev()
+            .take_while(|&&frame_time_ms| {
+                let counted = last_second_ms < 1000.0;
+                last_second_ms += frame_time_ms;
+                counted
+            })
+            .count();
+
+        let snapshot = history.snapshot();
+        assert_eq!(snapshot.sample_count, FRAME_DELTA_WINDOW);
+        assert!((snapshot.avg_ms.unwrap() - avg_ms).abs() < 1e-9);
+        assert_eq!(snapshot.max_ms, Some(max_ms));
+        assert_eq!(snapshot.p50_ms, Some(nearest_rank(50.0)));
+        assert_eq!(snapshot.p95_ms, Some(nearest_rank(95.0)));
+        assert_eq!(snapshot.p99_ms, Some(nearest_rank(99.0)));
+        let fps_ms: f64 = window.iter().rev().take(last_second).sum();
+        let fps_1s = last_second as f64 * 1000.0 / fps_ms;
+        assert!((snapshot.fps_1s.unwrap() - fps_1s).abs() < 1e-9);
     }
-}
 
-fn cleanup_stale_debug_texts(mut texts: ResMut<DebugTexts>, mut commands: Commands) {
-    texts.frame = texts.frame.wrapping_add(1);
-    let frame = texts.frame;
+    #[test]
+    fn snapshot_of_empty_history_is_empty() {
+        let snapshot = FrameTimeHistory::default().snapshot();
+        assert_eq!(snapshot.sample_count, 0);
+        assert_eq!(snapshot.avg_ms, None);
+        assert_eq!(snapshot.max_ms, None);
+        assert_eq!(snapshot.p99_ms, None);
+        assert_eq!(snapshot.fps_1s, None);
+    }
 
-    let mut to_remove = Vec::new();
-    for (key, entry) in texts.entries.iter() {
-        if !entry.persistent && entry.last_frame + 1 < frame {
-            commands.entity(entry.entity).despawn();
-            to_remove.push(key.clone());
+    #[cfg(feature = "debug-vis")]
+    #[derive(Resource, Default)]
+    struct SeenSpikes(Vec<FrameSpike>);
+
+    #[test]
+    #[cfg(feature = "debug-vis")]
+    fn manual_frame_times_feed_history_and_spikes() {
+        // Steady 16-16.8 ms frames with an 80 ms hitch every 100.
+        let samples: Vec<f64> = (0..1000)
+            .map(|i| if i % 100 == 50 { 80.0 } else { 16.0 + (i * 7 % 5) as f64 * 0.2 })
+            .collect();
+
+        let mut app = headless_app();
+        app.world_mut().resource_mut::<DebugVisConfig>().frame_time_source =
+            FrameTimeSource::Manual;
+        app.init_resource::<SeenSpikes>().add_systems(
+            PostUpdate,
+            |mut spikes: MessageReader<FrameSpike>, mut seen: ResMut<SeenSpikes>| {
+                seen.0.extend(spikes.read().copied());
+            },
+        );
+        for chunk in samples.chunks(10) {
+            app.world_mut().resource_mut::<ManualFrameTimes>().0.extend(chunk);
+            app.update();
         }
+
+        let window = window_of(&samples);
+        let mut sorted = window.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let history = app.world().resource::<FrameTimeHistory>();
+        assert_eq!(history.sample_count(), FRAME_DELTA_WINDOW);
+        let avg_ms = window.iter().sum::<f64>() / window.len() as f64;
+        assert!((history.avg_ms().unwrap() - avg_ms).abs() < 1e-9);
+        assert_eq!(history.max_ms(), Some(80.0));
+        assert_eq!(history.percentile(50.0), Some(sorted[149]));
+        assert_eq!(history.percentile(99.0), Some(sorted[296]));
+
+        let spikes = &app.world().resource::<SeenSpikes>().0;
+        assert_eq!(spikes.len(), 10, "{spikes:?}");
+        assert!(spikes.iter().all(|spike| spike.frame_time_ms == 80.0));
+        assert!(app.world().resource::<ManualFrameTimes>().0.is_empty());
     }
 
-    for key in to_remove {
-        texts.entries.remove(&key);
+    #[test]
+    #[cfg(feature = "debug-vis")]
+    fn recording_keeps_the_latest_snapshots() {
+        let mut app = headless_app();
+        app.world_mut().resource_mut::<DebugVisConfig>().record_snapshots = true;
+        let old = (0..MAX_RECORDED_SNAPSHOTS as u64).map(|frame| DebugSnapshot {
+            frame,
+            entries: Vec::new(),
+            fps: 0.0,
+            avg_ms: 0.0,
+        });
+        app.world_mut().resource_mut::<DebugRecording>().0.extend(old);
+        app.update();
+        app.update();
+
+        let recording = &app.world().resource::<DebugRecording>().0;
+        assert_eq!(recording.len(), MAX_RECORDED_SNAPSHOTS);
+        assert_eq!(recording.front().map(|snapshot| snapshot.frame), Some(2));
     }
-}
 
-fn setup_debug_top_gizmo_config(mut config_store: ResMut<GizmoConfigStore>) {
-    let (config, _) = config_store.config_mut::<DebugTopGizmoGroup>();
-    config.depth_bias = -1.0;
-}
+    #[test]
+    fn host_registered_frame_time_diagnostics() {
+        let mut app = App::new();
+        app.add_plugins((
+            headless_plugins(),
+            FrameTimeDiagnosticsPlugin::default(),
+            DebugVisPlugin::default(),
+        ));
+        app.finish();
+        app.cleanup();
+        app.update();
+        app.update();
 
-fn draw_frametime_barchart(
-    level: Res<DebugLevel>,
-    history: Res<FrameTimeHistory>,
-    camera_query: Query<(&Camera, &GlobalTransform)>,
-    mut gizmos: Gizmos<DebugTopGizmoGroup>,
-) {
-    if *level != DebugLevel::Full {
-        return;
+        let diagnostics = app.world().resource::<DiagnosticsStore>();
+        assert!(diagnostics.get(&FrameTimeDiagnosticsPlugin::FRAME_TIME).is_some());
     }
 
-    let Ok((camera, camera_transform)) = camera_query.single() else {
-        return;
-    };
+    #[test]
+    #[cfg(feature = "debug-vis")]
+    fn hidden_overlay_records_until_recording_is_disabled() {
+        let mut app = headless_app();
+        app.world_mut().resource_mut::<DebugVisConfig>().frame_time_source =
+            FrameTimeSource::Manual;
+        app.insert_resource(DebugLevel::Hidden);
+        let push_and_update = |app: &mut App| {
+            app.world_mut().resource_mut::<ManualFrameTimes>().0.extend([16.0; 5]);
+            app.update();
+            app.world().resource::<FrameTimeHistory>().sample_count()
+        };
 
-    let max_ms = history
-        .frame_times_ms
-        .iter()
-        .copied()
-        .fold(0.0_f64, f64::max);
+        assert_eq!(push_and_update(&mut app), 5);
+        assert_eq!(push_and_update(&mut app), 10);
 
-    let min_ms = history
-        .frame_times_ms
-        .iter()
-        .copied()
-        .fold(f64::INFINITY, f64::min);
+        app.insert_resource(RecordingEnabled(false));
+        assert_eq!(push_and_update(&mut app), 10);
+        assert_eq!(app.world().resource::<ManualFrameTimes>().0.len(), 5);
 
-    let avg_ms = history.sum_seconds * 1000.0 / history.frame_times_ms.len() as f64;
+        app.insert_resource(RecordingEnabled(true));
+        assert_eq!(push_and_update(&mut app), 20);
+    }
 
-    let chart_origin = Vec2::new(8.0, 120.0);
-    let chart_width = 300.0;
-    let bar_width = chart_width / history.frame_times_ms.len() as f32;
-    let max_height = 50.0;
-    let depth = 0.5;
+    #[test]
+    fn dbg_text_evaluates_arguments_only_with_the_feature() {
+        let evaluated = std::cell::Cell::new(0);
+        let count = || {
+            evaluated.set(evaluated.get() + 1);
+            evaluated.get()
+        };
 
-    let start_index = history
-        .frame_times_ms
-        .len()
-        .saturating_sub(FRAME_DELTA_WINDOW);
-    for (idx, frame_time) in history.frame_times_ms.iter().skip(start_index).enumerate() {
-        let color_ratio = if *frame_time > avg_ms { 0.2 + ((*frame_time / avg_ms - 1.0).clamp(0.0, 1.0) * 0.8) } else { (*frame_time / avg_ms) * 0.2}; // an avg frame time is 20% red, a 2X avg frametime is 100% red
-        let ratio = (*frame_time / max_ms).clamp(0.0, 1.0) as f32;
-        let height = max_height * ratio;
-        let x = chart_origin.x + idx as f32 * (bar_width);
-        let base = Vec2::new(x, chart_origin.y);
-        let top = Vec2::new(x, chart_origin.y - height);
-
-        let Ok(base_ray) = camera.viewport_to_world(camera_transform, base) else {
-            continue;
+        crate::dbg_text!("dbg_text_evaluation", "Evaluated: {}", count());
+
+        assert_eq!(evaluated.get(), usize::from(cfg!(feature = "debug-vis")));
+    }
+
+    #[test]
+    fn moving_max_matches_brute_force() {
+        let samples = noisy_frame_times(FRAME_DELTA_WINDOW * 3 + 11);
+        let mut history = FrameTimeHistory::default();
+        for (frame, &frame_time_ms) in samples.iter().enumerate() {
+            history.push(frame_time_ms, None, frame as u32);
+            let seen = &samples[..=frame];
+            for window in [1, 2, 36, 37, 100, FRAME_DELTA_WINDOW] {
+                let brute = seen[seen.len().saturating_sub(window)..]
+                    .iter()
+                    .copied()
+                    .fold(f64::NEG_INFINITY, f64::max);
+                assert_eq!(history.moving_max(window), Some(brute), "frame {frame}, {window}");
+            }
+        }
+
+        assert_eq!(history.moving_max(0), None);
+        assert_eq!(history.moving_max(FRAME_DELTA_WINDOW * 2), history.max_ms());
+        history.clear();
+        assert_eq!(history.moving_max(10), None);
+    }
+
+    #[test]
+    #[cfg(feature = "debug-vis")]
+    fn clear_prefix_keeps_lines_written_after_it() {
+        let mut app = headless_app();
+        // After the cleanup, so the line written once in frame 2 counts as written in frame 2
+        // and is still there after frame 3.
+        let write_lines = |mut writer: DebugTextWriter, mut frame: Local<u32>| {
+            *frame += 1;
+            writer.write("physics_contacts", "Contacts: 3");
+            if *frame == 1 {
+                writer.write("ai_enemy_1_state", "Enemy 1: idle");
+                writer.write("ai_enemy_2_state", "Enemy 2: idle");
+            } else if *frame == 2 {
+                writer.write("ai_enemy_2_state", "Enemy 2: chasing");
+                writer.clear_prefix("ai_");
+                writer.write("ai_enemy_3_state", "Enemy 3: spawned");
+            }
         };
-        let Ok(top_ray) = camera.viewport_to_world(camera_transform, top) else {
-            continue;
+        app.add_systems(Update, write_lines.after(cleanup_stale_debug_texts));
+        app.update();
+        app.update();
+        app.update();
+
+        let world = app.world_mut();
+        let lines = world
+            .run_system_cached(|reader: DebugTextsReader| {
+                ["ai_enemy_1_state", "ai_enemy_2_state", "ai_enemy_3_state"]
+                    .map(|key| reader.get(key))
+            })
+            .unwrap();
+        assert_eq!(lines, [None, None, Some("Enemy 3: spawned".to_string())]);
+        let contacts = world
+            .run_system_cached(|reader: DebugTextsReader| reader.get("physics_contacts"))
+            .unwrap();
+        assert_eq!(contacts.as_deref(), Some("Contacts: 3"));
+        let labels = world
+            .query_filtered::<&DebugLabel, Without<PooledDebugLine>>()
+            .iter(world)
+            .filter(|label| label.0.starts_with("ai_"))
+            .count();
+        assert_eq!(labels, 1);
+    }
+
+    #[test]
+    fn iter_indexed_numbers_the_window_from_its_oldest_sample() {
+        let samples = noisy_frame_times(FRAME_DELTA_WINDOW + 5);
+        let mut history = FrameTimeHistory::default();
+        for (frame, &frame_time_ms) in samples.iter().enumerate() {
+            history.push(frame_time_ms, None, frame as u32);
+        }
+        let indexed: Vec<(usize, f64)> = history.iter_indexed().collect();
+        let expected: Vec<(usize, f64)> = samples[5..].iter().copied().enumerate().collect();
+        assert_eq!(indexed, expected);
+    }
+
+    #[test]
+    fn moving_min_matches_brute_force() {
+        let samples = noisy_frame_times(FRAME_DELTA_WINDOW * 3 + 11);
+        let mut history = FrameTimeHistory::default();
+        for (frame, &frame_time_ms) in samples.iter().enumerate() {
+            history.push(frame_time_ms, None, frame as u32);
+            let seen = &samples[..=frame];
+            for window in [1, 2, 36, 100, FRAME_DELTA_WINDOW] {
+                let brute = seen[seen.len().saturating_sub(window)..]
+                    .iter()
+                    .copied()
+                    .fold(f64::INFINITY, f64::min);
+                assert_eq!(history.moving_min(window), Some(brute), "frame {frame}, {window}");
+            }
+        }
+        assert_eq!(history.moving_min(0), None);
+    }
+
+    #[test]
+    fn min_moves_on_once_it_leaves_the_window() {
+        let mut history = FrameTimeHistory::default();
+        history.push(2.0, None, 0);
+        history.push(5.0, None, 1);
+        for frame in 2..FRAME_DELTA_WINDOW as u32 {
+            history.push(16.0, None, frame);
+        }
+        assert_eq!(history.min_ms(), Some(2.0));
+
+        history.push(16.0, None, FRAME_DELTA_WINDOW as u32);
+        assert_eq!(history.min_ms(), Some(5.0));
+        history.push(16.0, None, FRAME_DELTA_WINDOW as u32 + 1);
+        assert_eq!(history.min_ms(), Some(16.0));
+    }
+
+    #[test]
+    #[cfg(feature = "debug-vis")]
+    fn frametime_line_shows_min_avg_max() {
+        let mut app = headless_app();
+        app.world_mut().resource_mut::<DebugVisConfig>().frame_time_source =
+            FrameTimeSource::Manual;
+        app.world_mut().resource_mut::<ManualFrameTimes>().0.extend([10.0, 20.0, 30.0]);
+        app.update();
+
+        let world = app.world_mut();
+        let line = world
+            .query_filtered::<&Text, With<FrametimeConsistencyText>>()
+            .single(world)
+            .unwrap();
+        assert_eq!(line.0, "Frametime (300): min 10.0 / avg 20.0 / max 30.0 ms");
+    }
+
+    #[test]
+    fn variance_matches_brute_force() {
+        let samples = noisy_frame_times(FRAME_DELTA_WINDOW + 50);
+        let window = window_of(&samples);
+        let mean = window.iter().sum::<f64>() / window.len() as f64;
+        let variance = window.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / window.len() as f64;
+
+        assert!((history_of(&samples).variance() - variance).abs() < 1e-6);
+        assert_eq!(history_of(&[16.0]).variance(), 0.0);
+    }
+
+    #[test]
+    fn resample_keeps_bucket_maxima() {
+        let samples = noisy_frame_times(FRAME_DELTA_WINDOW);
+        let history = history_of(&samples);
+
+        for n in [1, 7, 64, 299] {
+            let brute: Vec<f64> = (0..n)
+                .map(|bucket| {
+                    let start = bucket * samples.len() / n;
+                    let end = (bucket + 1) * samples.len() / n;
+                    samples[start..end].iter().copied().fold(f64::NEG_INFINITY, f64::max)
+                })
+                .collect();
+            assert_eq!(history.resample(n), brute, "n = {n}");
+        }
+        assert_eq!(history.resample(FRAME_DELTA_WINDOW + 10), samples);
+    }
+
+    #[test]
+    #[cfg(feature = "debug-vis")]
+    fn hidden_writes_wait_for_the_overlay_to_show() {
+        let mut app = headless_app();
+        app.insert_resource(DebugLevel::Hidden);
+        app.add_systems(Update, |mut writer: DebugTextWriter, mut frame: Local<u32>| {
+            *frame += 1;
+            if *frame == 1 {
+                writer.write_with_persistence("hidden_persistent", "Seed: 42", true);
+            }
+            writer.write("hidden_live", format!("Frame: {}", *frame));
+        });
+        let lines_for = |app: &mut App| {
+            let world = app.world_mut();
+            let mut keys: Vec<String> = world
+                .query_filtered::<&DebugLabel, Without<PooledDebugLine>>()
+                .iter(world)
+                .filter(|label| label.0.starts_with("hidden_"))
+                .map(|label| label.0.clone())
+                .collect();
+            keys.sort();
+            let texts = world
+                .run_system_cached(|reader: DebugTextsReader| {
+                    ["hidden_persistent", "hidden_live"].map(|key| reader.get(key))
+                })
+                .unwrap();
+            (keys, texts)
         };
 
-        let base_pos = base_ray.get_point(depth);
-        let top_pos = top_ray.get_point(depth);
-        let color = Color::srgb(color_ratio as f32, 1.0 - color_ratio as f32, 0.0);
+        for _ in 0..3 {
+            app.update();
+        }
+        let (spawned, texts) = lines_for(&mut app);
+        assert!(spawned.is_empty(), "spawned {spawned:?} while hidden");
+        assert_eq!(
+            texts,
+            [Some("Seed: 42".to_string()), Some("Frame: 3".to_string())]
+        );
 
-        gizmos.line(base_pos, top_pos, color);
+        app.insert_resource(DebugLevel::Full);
+        app.update();
+        let (spawned, texts) = lines_for(&mut app);
+        assert_eq!(spawned, ["hidden_live", "hidden_persistent"]);
+        assert_eq!(
+            texts,
+            [Some("Seed: 42".to_string()), Some("Frame: 4".to_string())]
+        );
+        assert!(app.world().resource::<DebugTexts>().deferred.is_empty());
     }
 }
@@ -0,0 +1,73 @@
+//! Lists recently hot-reloaded assets, for asset types registered with
+//! [`DebugAssetReloadsAppExt::debug_asset_reloads`].
+
+use super::{DebugTextWriter, DebugVisConfig, OverlayCost};
+use bevy::prelude::*;
+use std::time::Duration;
+
+const RELOAD_SHOWN_FOR: Duration = Duration::from_secs(10);
+
+/// Recently reloaded asset paths with the time (since startup) they changed.
+#[derive(Resource, Default)]
+pub(super) struct AssetReloadLog(Vec<(String, Duration)>);
+
+pub trait DebugAssetReloadsAppExt {
+    /// Shows "Hot reload: <path> (Ns ago)" whenever an `A` asset changes on disk, while
+    /// [`DebugVisConfig::show_hot_reload_status`] is set and the asset server is watching.
+    fn debug_asset_reloads<A: Asset>(&mut self) -> &mut Self;
+}
+
+impl DebugAssetReloadsAppExt for App {
+    fn debug_asset_reloads<A: Asset>(&mut self) -> &mut Self {
+        self.init_resource::<AssetReloadLog>()
+            .add_systems(Update, record_asset_reloads::<A>)
+    }
+}
+
+fn record_asset_reloads<A: Asset>(
+    config: Res<DebugVisConfig>,
+    time: Res<Time<Real>>,
+    asset_server: Res<AssetServer>,
+    mut events: MessageReader<AssetEvent<A>>,
+    mut log: ResMut<AssetReloadLog>,
+) {
+    if !config.show_hot_reload_status || !asset_server.watching_for_changes() {
+        events.clear();
+        return;
+    }
+
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        let Some(path) = asset_server.get_path(*id) else {
+            continue;
+        };
+        let path = path.to_string();
+        log.0.retain(|(logged, _)| *logged != path);
+        log.0.push((path, time.elapsed()));
+    }
+}
+
+pub(super) fn write_asset_reloads(
+    time: Res<Time<Real>>,
+    mut log: ResMut<AssetReloadLog>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let now = time.elapsed();
+    log.0
+        .retain(|(_, reloaded_at)| now.saturating_sub(*reloaded_at) < RELOAD_SHOWN_FOR);
+
+    for (path, reloaded_at) in &log.0 {
+        writer.write(
+            format!("hot_reload_{path}"),
+            format!(
+                "Hot reload: {path} ({}s ago)",
+                now.saturating_sub(*reloaded_at).as_secs()
+            ),
+        );
+    }
+}
@@ -0,0 +1,132 @@
+//! Moves the debug line stack out from under other UI, for [`DebugVisConfig::auto_anchor`].
+//! Only nodes that draw something count: text, images and opaque backgrounds, so full-screen
+//! layout containers don't occupy every corner. The overlay's own fixed widgets, like the FPS
+//! lines and the frame clock, count too.
+
+use super::{
+    DEBUG_CHAR_WIDTH, DebugTexts, DebugVisConfig, LEFT_PADDING, LINE_HEIGHT, OverlayAnchor,
+    OverlayView, PINNED_LINE, StackCorner, TOP_STACK_OFFSET, help_panel::HelpPanel,
+    palette::CommandPalette,
+};
+use bevy::{prelude::*, window::WindowResized};
+use std::collections::HashSet;
+
+/// Picks the corner again whenever the window resizes or a node outside the overlay is laid
+/// out, resized or removed.
+pub(super) fn auto_anchor_debug_lines(
+    mut commands: Commands,
+    config: Res<DebugVisConfig>,
+    mut texts: ResMut<DebugTexts>,
+    mut resized: MessageReader<WindowResized>,
+    mut removed: RemovedComponents<ComputedNode>,
+    changed: Query<Entity, Changed<ComputedNode>>,
+    nodes: Query<(
+        Entity,
+        &ComputedNode,
+        &UiGlobalTransform,
+        &InheritedVisibility,
+        Option<&BackgroundColor>,
+        Has<Text>,
+        Has<ImageNode>,
+    )>,
+    parents: Query<&ChildOf>,
+    overlay_roots: Query<(), Or<(With<OverlayAnchor>, With<HelpPanel>, With<CommandPalette>)>>,
+    panels: Query<(), Or<(With<HelpPanel>, With<CommandPalette>)>>,
+    view: OverlayView,
+    ui_scale: Res<UiScale>,
+) {
+    let is_foreign = |entity: Entity| !overlay_roots.contains(parents.root_ancestor(entity));
+
+    let resized = resized.read().count() > 0;
+    let removed = removed.read().count() > 0;
+    let changed = changed.iter().any(is_foreign);
+    if !(resized || removed || changed || config.is_changed()) {
+        return;
+    }
+
+    let corner = if config.auto_anchor {
+        // The lines being placed don't block themselves, and the help panel and palette
+        // cover the whole screen while open.
+        let movable: HashSet<Entity> = texts
+            .entries
+            .values()
+            .map(|entry| entry.entity)
+            .chain(texts.tables.values().map(|table| table.entity))
+            .collect();
+        free_corner(&texts, &config, &view, &ui_scale, || {
+            nodes
+                .iter()
+                .filter(|(entity, node, _, visibility, background, text, image)| {
+                    let root = parents.root_ancestor(*entity);
+                    visibility.get()
+                        && !node.is_empty()
+                        && (*text || *image || background.is_some_and(|bg| bg.0.alpha() > 0.0))
+                        && !movable.contains(&root)
+                        && !panels.contains(root)
+                })
+                .map(|(_, node, transform, ..)| {
+                    Rect::from_center_size(transform.affine().translation, node.size())
+                })
+        })
+        .unwrap_or(texts.corner)
+    } else {
+        StackCorner::default()
+    };
+
+    if corner != texts.corner {
+        texts.corner = corner;
+        texts.reposition_where(|_| true, &mut commands, &config.safe_area);
+    }
+}
+
+/// The first corner, in [`StackCorner::ALL`] order, whose stack would overlap none of
+/// `occupied`. `None` when every corner is taken or there is no window to measure.
+fn free_corner<I: Iterator<Item = Rect>>(
+    texts: &DebugTexts,
+    config: &DebugVisConfig,
+    view: &OverlayView,
+    ui_scale: &UiScale,
+    occupied: impl Fn() -> I,
+) -> Option<StackCorner> {
+    let window = view.window()?;
+    let scale = window.scale_factor() * ui_scale.0;
+    let viewport = window.physical_size().as_vec2() / scale;
+
+    // Stack size in logical px: the longest line by the rows every line and span takes.
+    let lines = texts.entries.values().filter(|entry| entry.line != PINNED_LINE);
+    let longest = lines
+        .clone()
+        .map(|entry| {
+            entry.label.as_deref().map_or(0, |label| label.chars().count())
+                + entry.message.chars().count()
+        })
+        .max()
+        .unwrap_or(0);
+    let rows = lines.map(|entry| entry.line + 1).max().unwrap_or(0)
+        + texts.line_spans.values().map(|span| span - 1).sum::<usize>();
+    if rows == 0 {
+        return Some(texts.corner);
+    }
+    let stack = Vec2::new(longest as f32 * DEBUG_CHAR_WIDTH, rows as f32 * LINE_HEIGHT);
+
+    let safe_area = &config.safe_area;
+    let inset = |val: Val| match val {
+        Val::Px(px) => px,
+        _ => 0.0,
+    };
+    StackCorner::ALL.into_iter().find(|corner| {
+        let x = if corner.is_right() {
+            viewport.x - inset(safe_area.right) - LEFT_PADDING - stack.x
+        } else {
+            inset(safe_area.left) + LEFT_PADDING
+        };
+        let y = if corner.is_top() {
+            inset(safe_area.top) + TOP_STACK_OFFSET
+        } else {
+            viewport.y - inset(safe_area.bottom) - stack.y
+        };
+        let min = Vec2::new(x, y) * scale;
+        let rect = Rect::from_corners(min, min + stack * scale);
+        occupied().all(|node| rect.intersect(node).is_empty())
+    })
+}
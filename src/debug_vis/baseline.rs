@@ -0,0 +1,327 @@
+//! Comparison against an earlier run. "Save stability report" in the command palette writes
+//! the window's statistics along with the resolution and present mode they were measured at;
+//! a report loaded with [`with_baseline`](super::DebugVisPlugin::with_baseline) is shown as
+//! deltas next to the live avg, P95 and P99.
+
+use super::{
+    DebugCommandRegistry, DebugTextWriter, FrameStatsSnapshot, FrameTimeHistory,
+    HEALTH_BAD_COLOR, HEALTH_GOOD_COLOR, OverlayCost, OverlayView,
+};
+use bevy::{diagnostic::FrameCount, prelude::*, window::PresentMode};
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Bumped whenever a field changes meaning. Fields added later are optional, so reports
+/// from older versions keep loading; reports from newer versions are rejected.
+pub const STABILITY_REPORT_VERSION: u32 = 1;
+const REPORT_PATH: &str = "stability_report.json";
+
+/// Frame statistics from one run, with the conditions they were measured under.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StabilityReport {
+    pub version: u32,
+    /// [`FrameCount`] the report was taken at.
+    #[serde(default)]
+    pub frame: u32,
+    /// Physical size of the overlay's window.
+    pub resolution: Option<UVec2>,
+    pub present_mode: Option<PresentMode>,
+    pub stats: FrameStatsSnapshot,
+    /// The window the statistics were taken over, oldest first, for
+    /// [`with_replay`](super::DebugVisPlugin::with_replay).
+    #[serde(default)]
+    pub frame_times_ms: Vec<f64>,
+}
+
+impl StabilityReport {
+    pub fn capture(history: &FrameTimeHistory, window: Option<&Window>, frame: u32) -> Self {
+        Self {
+            version: STABILITY_REPORT_VERSION,
+            frame,
+            resolution: window.map(|window| window.physical_size()),
+            present_mode: window.map(|window| window.present_mode),
+            stats: history.snapshot(),
+            frame_times_ms: history.frame_times_ms.iter().copied().collect(),
+        }
+    }
+
+    pub fn save_to_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let report: Self = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        if report.version > STABILITY_REPORT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "report version {} is newer than this build's {STABILITY_REPORT_VERSION}",
+                    report.version
+                ),
+            ));
+        }
+        Ok(report)
+    }
+
+    /// Conditions that differ from `window`'s, e.g. "resolution 1920x1080 vs 1280x720".
+    /// Anything the report didn't record isn't compared.
+    fn mismatches(&self, window: &Window) -> Vec<String> {
+        let mut mismatches = Vec::new();
+        let size = window.physical_size();
+        if let Some(resolution) = self.resolution
+            && resolution != size
+        {
+            mismatches.push(format!(
+                "resolution {}x{} vs {}x{}",
+                resolution.x, resolution.y, size.x, size.y
+            ));
+        }
+        if let Some(present_mode) = self.present_mode
+            && present_mode != window.present_mode
+        {
+            mismatches.push(format!(
+                "present mode {present_mode:?} vs {:?}",
+                window.present_mode
+            ));
+        }
+        mismatches
+    }
+}
+
+/// The report live statistics are compared against.
+#[derive(Resource)]
+pub(super) struct Baseline {
+    /// File name, for the overlay line.
+    name: String,
+    report: StabilityReport,
+}
+
+pub(super) struct DebugVisBaselinePlugin(pub(super) Option<PathBuf>);
+
+impl Plugin for DebugVisBaselinePlugin {
+    fn build(&self, app: &mut App) {
+        app.world_mut()
+            .resource_mut::<DebugCommandRegistry>()
+            .register("Save stability report", |commands| {
+                commands.run_system_cached(save_stability_report);
+            });
+
+        let Some(path) = &self.0 else {
+            return;
+        };
+        match StabilityReport::load(path) {
+            Ok(report) => {
+                let name = path
+                    .file_name()
+                    .map_or_else(|| path.display().to_string(), |name| name.display().to_string());
+                app.insert_resource(Baseline { name, report }).add_systems(
+                    Update,
+                    write_baseline_deltas.after(super::update_frame_time_history),
+                );
+            }
+            Err(err) => warn!("debug_vis: can't load baseline {}: {err}", path.display()),
+        }
+    }
+}
+
+fn save_stability_report(
+    view: OverlayView,
+    history: Res<FrameTimeHistory>,
+    frame: Res<FrameCount>,
+    mut writer: DebugTextWriter,
+) {
+    let report = StabilityReport::capture(&history, view.window(), frame.0);
+    match report.save_to_file(REPORT_PATH) {
+        Ok(()) => writer.write_with_persistence(
+            "stability_report",
+            format!("Stability report: {REPORT_PATH} (frame {})", frame.0),
+            true,
+        ),
+        Err(err) => warn!("debug_vis: can't save stability report to {REPORT_PATH}: {err}"),
+    }
+}
+
+fn write_baseline_deltas(
+    baseline: Res<Baseline>,
+    history: Res<FrameTimeHistory>,
+    view: OverlayView,
+    mut warned: Local<bool>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let report = &baseline.report;
+    writer.write(
+        "baseline",
+        format!(
+            "Baseline: {} ({} samples, frame {})",
+            baseline.name, report.stats.sample_count, report.frame
+        ),
+    );
+
+    let mismatches = view
+        .window()
+        .map(|window| report.mismatches(window))
+        .unwrap_or_default();
+    if mismatches.is_empty() {
+        *warned = false;
+    } else {
+        if !*warned {
+            *warned = true;
+            warn!(
+                "debug_vis: baseline {} was measured under different conditions: {}",
+                baseline.name,
+                mismatches.join(", ")
+            );
+        }
+        writer.write_colored(
+            "baseline_mismatch",
+            format!("Baseline mismatch: {}", mismatches.join(", ")),
+            HEALTH_BAD_COLOR,
+        );
+    }
+
+    let percentiles = history.percentiles([95.0, 99.0]);
+    let rows = [
+        ("avg", history.avg_ms(), report.stats.avg_ms),
+        ("p95", percentiles.map(|[p95, _]| p95), report.stats.p95_ms),
+        ("p99", percentiles.map(|[_, p99]| p99), report.stats.p99_ms),
+    ];
+    for (name, live_ms, baseline_ms) in rows {
+        let (Some(live_ms), Some(baseline_ms)) = (live_ms, baseline_ms) else {
+            continue;
+        };
+        writer.write_colored(
+            format!("baseline_{name}"),
+            format!("{name}: {}", format_delta(live_ms, baseline_ms)),
+            if live_ms <= baseline_ms {
+                HEALTH_GOOD_COLOR
+            } else {
+                HEALTH_BAD_COLOR
+            },
+        );
+    }
+}
+
+/// "18.3 ms (baseline 21.0, −12.9%)".
+fn format_delta(live_ms: f64, baseline_ms: f64) -> String {
+    if baseline_ms <= 0.0 {
+        return format!("{live_ms:.1} ms (baseline {baseline_ms:.1})");
+    }
+    let change = (live_ms - baseline_ms) / baseline_ms * 100.0;
+    let sign = if change < 0.0 { '−' } else { '+' };
+    format!(
+        "{live_ms:.1} ms (baseline {baseline_ms:.1}, {sign}{:.1}%)",
+        change.abs()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> StabilityReport {
+        let mut history = FrameTimeHistory::default();
+        for frame in 0..100 {
+            history.push(16.0 + f64::from(frame % 4), None, frame);
+        }
+        let window = Window {
+            present_mode: PresentMode::Fifo,
+            resolution: (1280, 720).into(),
+            ..default()
+        };
+        StabilityReport::capture(&history, Some(&window), 100)
+    }
+
+    #[test]
+    fn report_round_trips_through_json() {
+        let path = std::env::temp_dir().join("debug_vis_baseline_round_trip.json");
+        let report = report();
+        report.save_to_file(&path).unwrap();
+        assert_eq!(StabilityReport::load(&path).unwrap(), report);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn older_reports_load_and_newer_ones_do_not() {
+        let path = std::env::temp_dir().join("debug_vis_baseline_versions.json");
+        std::fs::write(&path, r#"{ "version": 1, "stats": { "sample_count": 3 } }"#).unwrap();
+        let old = StabilityReport::load(&path).unwrap();
+        assert_eq!((old.frame, old.resolution, old.stats.p99_ms), (0, None, None));
+
+        std::fs::write(&path, r#"{ "version": 99, "stats": { "sample_count": 3 } }"#).unwrap();
+        let err = StabilityReport::load(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn mismatches_name_what_changed() {
+        let report = report();
+        let mut window = Window {
+            present_mode: PresentMode::Fifo,
+            resolution: (1280, 720).into(),
+            ..default()
+        };
+        assert!(report.mismatches(&window).is_empty());
+
+        window.present_mode = PresentMode::Immediate;
+        window.resolution = (1920, 1080).into();
+        assert_eq!(
+            report.mismatches(&window),
+            ["resolution 1280x720 vs 1920x1080", "present mode Fifo vs Immediate"]
+        );
+    }
+
+    #[test]
+    fn deltas_are_signed_percentages() {
+        assert_eq!(format_delta(18.27, 21.0), "18.3 ms (baseline 21.0, −13.0%)");
+        assert_eq!(format_delta(22.0, 20.0), "22.0 ms (baseline 20.0, +10.0%)");
+        assert_eq!(format_delta(5.0, 0.0), "5.0 ms (baseline 0.0)");
+    }
+
+    #[test]
+    #[cfg(feature = "debug-vis")]
+    fn loaded_baseline_shows_deltas() {
+        use crate::debug_vis::{
+            DebugTextsReader, DebugVisConfig, DebugVisPlugin, FrameTimeSource, ManualFrameTimes,
+            tests::headless_plugins,
+        };
+
+        let path = std::env::temp_dir().join("debug_vis_baseline_deltas.json");
+        let mut report = report();
+        report.stats.avg_ms = Some(20.0);
+        report.stats.p99_ms = Some(10.0);
+        report.save_to_file(&path).unwrap();
+
+        let mut app = App::new();
+        app.add_plugins((headless_plugins(), DebugVisPlugin::default().with_baseline(&path)));
+        app.finish();
+        app.cleanup();
+        app.world_mut().resource_mut::<DebugVisConfig>().frame_time_source =
+            FrameTimeSource::Manual;
+        app.world_mut().resource_mut::<ManualFrameTimes>().0.extend([16.0; 10]);
+        app.update();
+        let _ = std::fs::remove_file(path);
+
+        let lines = app
+            .world_mut()
+            .run_system_cached(|reader: DebugTextsReader| {
+                ["baseline", "baseline_avg", "baseline_p99"].map(|key| reader.get(key))
+            })
+            .unwrap();
+        assert_eq!(
+            lines,
+            [
+                Some("Baseline: debug_vis_baseline_deltas.json (100 samples, frame 100)".into()),
+                Some("avg: 16.0 ms (baseline 20.0, −20.0%)".into()),
+                Some("p99: 16.0 ms (baseline 10.0, +60.0%)".into()),
+            ]
+        );
+    }
+}
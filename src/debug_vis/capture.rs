@@ -0,0 +1,175 @@
+//! Saves just the overlay as a small PNG for bug reports: the overlay roots are moved onto
+//! an offscreen camera with a dark background for a few frames, then moved back.
+
+use super::{
+    DebugAction, DebugOverlayCamera, DebugTextWriter, DebugVisConfig, OverlayAnchor, OverlayView,
+};
+use bevy::{
+    camera::{RenderTarget, visibility::RenderLayers},
+    diagnostic::FrameCount,
+    prelude::*,
+    render::{
+        render_resource::{TextureFormat, TextureUsages},
+        view::screenshot::{Screenshot, save_to_disk},
+    },
+};
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const CAPTURE_BACKGROUND: Color = Color::srgb(0.08, 0.08, 0.08);
+/// Frames the overlay is given to lay out against the capture camera before the shot.
+const LAYOUT_FRAMES: u8 = 1;
+/// Frames the capture camera is kept after the shot, until the readback is done.
+const READBACK_FRAMES: u8 = 2;
+const SAVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Resource, Default)]
+pub(super) enum OverlayCapture {
+    #[default]
+    Idle,
+    Rendering {
+        camera: Entity,
+        image: Handle<Image>,
+        path: PathBuf,
+        frames: u8,
+    },
+    Saving {
+        path: PathBuf,
+        /// Frame the shot was taken in, for matching the capture against spike logs.
+        frame: u32,
+        since: Duration,
+    },
+}
+
+fn capture_path() -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    PathBuf::from(format!("overlay_capture_{timestamp}.png"))
+}
+
+pub(super) fn capture_overlay(
+    mut commands: Commands,
+    mut capture: ResMut<OverlayCapture>,
+    mut debug_reader: MessageReader<DebugAction>,
+    config: Res<DebugVisConfig>,
+    time: Res<Time<Real>>,
+    frame_count: Res<FrameCount>,
+    mut images: ResMut<Assets<Image>>,
+    view: OverlayView,
+    overlay_camera: Query<Entity, With<DebugOverlayCamera>>,
+    roots: Query<Entity, With<OverlayAnchor>>,
+    mut writer: DebugTextWriter,
+) {
+    let requested = debug_reader
+        .read()
+        .any(|event| *event == DebugAction::CaptureOverlay);
+
+    match &mut *capture {
+        OverlayCapture::Idle => {
+            if !requested {
+                return;
+            }
+            let Some(window) = view.window() else {
+                return;
+            };
+
+            // Logical size, so the overlay lays out exactly as on screen but without the
+            // HiDPI multiplier.
+            let mut image = Image::new_target_texture(
+                window.width().max(1.0) as u32,
+                window.height().max(1.0) as u32,
+                TextureFormat::Rgba8UnormSrgb,
+                None,
+            );
+            image.texture_descriptor.usage |= TextureUsages::COPY_SRC;
+            let image = images.add(image);
+
+            // UI ignores render layers, so with no overlay layer the camera draws only the
+            // overlay UI. With one, it also picks up the frametime chart's gizmos.
+            let layers = config
+                .overlay_camera_layer
+                .map(RenderLayers::layer)
+                .unwrap_or_else(RenderLayers::none);
+            let camera = commands
+                .spawn((
+                    Camera2d,
+                    Camera {
+                        clear_color: ClearColorConfig::Custom(CAPTURE_BACKGROUND),
+                        ..default()
+                    },
+                    RenderTarget::Image(image.clone().into()),
+                    layers,
+                ))
+                .id();
+            for root in &roots {
+                commands.entity(root).insert(UiTargetCamera(camera));
+            }
+
+            *capture = OverlayCapture::Rendering {
+                camera,
+                image,
+                path: capture_path(),
+                frames: 0,
+            };
+        }
+        OverlayCapture::Rendering {
+            camera,
+            image,
+            path,
+            frames,
+        } => {
+            *frames += 1;
+            if *frames == LAYOUT_FRAMES {
+                commands
+                    .spawn(Screenshot::image(image.clone()))
+                    .observe(save_to_disk(path.clone()));
+            }
+            if *frames < LAYOUT_FRAMES + READBACK_FRAMES {
+                return;
+            }
+
+            let ui_camera = overlay_camera.single().ok().or_else(|| view.ui_camera());
+            for root in &roots {
+                let mut root = commands.entity(root);
+                match ui_camera {
+                    Some(ui_camera) => {
+                        root.insert(UiTargetCamera(ui_camera));
+                    }
+                    None => {
+                        root.remove::<UiTargetCamera>();
+                    }
+                }
+            }
+            commands.entity(*camera).despawn();
+
+            *capture = OverlayCapture::Saving {
+                path: std::mem::take(path),
+                frame: frame_count.0.saturating_sub(u32::from(READBACK_FRAMES)),
+                since: time.elapsed(),
+            };
+        }
+        OverlayCapture::Saving { path, frame, since } => {
+            match std::fs::metadata(&*path) {
+                Ok(metadata) if metadata.len() > 0 => {
+                    writer.write_with_persistence(
+                        "overlay_capture",
+                        format!(
+                            "Overlay capture: {} ({:.1} KB, frame {})",
+                            path.display(),
+                            metadata.len() as f64 / 1024.0,
+                            frame
+                        ),
+                        true,
+                    );
+                }
+                _ if time.elapsed().saturating_sub(*since) < SAVE_TIMEOUT => return,
+                _ => warn!("debug_vis: overlay capture {} was not saved", path.display()),
+            }
+            *capture = OverlayCapture::Idle;
+        }
+    }
+}
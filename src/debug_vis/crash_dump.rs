@@ -0,0 +1,66 @@
+//! Panic hook that writes the recent frame times to disk, so a crash report comes with
+//! the frame pacing that led up to it.
+
+use super::FRAME_DELTA_WINDOW;
+use bevy::prelude::*;
+use std::{
+    collections::VecDeque,
+    fmt::Write as _,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const CRASH_DUMP_PATH: &str = "crash_frametimes.txt";
+
+/// Copy of the frame time window, as `(frame, frame_time_ms)`, that the panic hook can reach
+/// outside the ECS.
+#[derive(Resource, Clone, Default)]
+pub(super) struct CrashDumpHistory(Arc<Mutex<VecDeque<(u32, f64)>>>);
+
+impl CrashDumpHistory {
+    pub(super) fn record(&self, frame: u32, frame_time_ms: f64) {
+        let Ok(mut frame_times) = self.0.lock() else {
+            return;
+        };
+        frame_times.push_back((frame, frame_time_ms));
+        if frame_times.len() > FRAME_DELTA_WINDOW {
+            frame_times.pop_front();
+        }
+    }
+}
+
+pub(super) fn install(app: &mut App) {
+    let history = CrashDumpHistory::default();
+    app.insert_resource(history.clone());
+
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Err(err) = write_dump(&history, &info.to_string()) {
+            eprintln!("failed to write {CRASH_DUMP_PATH}: {err}");
+        }
+        previous(info);
+    }));
+}
+
+fn write_dump(history: &CrashDumpHistory, message: &str) -> std::io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let mut contents = format!(
+        "timestamp: {timestamp}\npanic: {message}\nframe times (oldest first):\n\
+         frame,frame_time_ms\n"
+    );
+    // The panic may have happened while the lock was held; dump what we can without blocking.
+    match history.0.try_lock() {
+        Ok(frame_times) => {
+            for (frame, frame_time_ms) in frame_times.iter() {
+                let _ = writeln!(contents, "{frame},{frame_time_ms:.3}");
+            }
+        }
+        Err(_) => contents.push_str("<unavailable>\n"),
+    }
+
+    std::fs::write(CRASH_DUMP_PATH, contents)
+}
@@ -0,0 +1,96 @@
+//! FPS gauge drawn with gizmos: a 270° arc split into bad, ok and good ranges, with a needle
+//! for the same FPS the text shows. The arc is tessellated once, on a unit circle; each frame
+//! only scales, offsets and projects the points.
+
+use super::{
+    DebugLevel, DebugOverlayCamera, DebugTopGizmoGroup, DebugVisConfig, DebugWidgets,
+    FpsWindow, HEALTH_BAD_COLOR, HEALTH_GOOD_COLOR, HEALTH_WARN_COLOR, OverlayCost,
+    OverlayView, ViewportPlane,
+};
+use bevy::prelude::*;
+use std::{f32::consts::PI, sync::OnceLock};
+
+/// The needle's full scale, as a multiple of the FPS target.
+const FULL_SCALE: f64 = 1.5;
+/// Where the arc starts (0 FPS) and how far it sweeps clockwise, in radians from +X.
+const START_ANGLE: f32 = 1.25 * PI;
+const SWEEP: f32 = 1.5 * PI;
+const SEGMENTS_PER_RANGE: usize = 16;
+const NEEDLE_LENGTH: f32 = 0.85;
+const NEEDLE_COLOR: Color = Color::WHITE;
+
+/// Unit-circle points of each colored range, in viewport orientation (+Y down). Ranges split
+/// the scale at half the target and at the target, like the health dot.
+fn arc_ranges() -> &'static [(Color, Vec<Vec2>); 3] {
+    static RANGES: OnceLock<[(Color, Vec<Vec2>); 3]> = OnceLock::new();
+    RANGES.get_or_init(|| {
+        let target = (1.0 / FULL_SCALE) as f32;
+        let range = |color: Color, from: f32, to: f32| {
+            let points = (0..=SEGMENTS_PER_RANGE)
+                .map(|step| {
+                    let fraction = from + (to - from) * step as f32 / SEGMENTS_PER_RANGE as f32;
+                    dial_direction(fraction)
+                })
+                .collect();
+            (color, points)
+        };
+        [
+            range(HEALTH_BAD_COLOR, 0.0, target * 0.5),
+            range(HEALTH_WARN_COLOR, target * 0.5, target),
+            range(HEALTH_GOOD_COLOR, target, 1.0),
+        ]
+    })
+}
+
+/// Direction from the dial center to `fraction` (0..=1) of the full scale.
+fn dial_direction(fraction: f32) -> Vec2 {
+    let angle = START_ANGLE - SWEEP * fraction.clamp(0.0, 1.0);
+    Vec2::new(angle.cos(), -angle.sin())
+}
+
+pub(super) fn draw_fps_dial(
+    level: Res<DebugLevel>,
+    widgets: Res<DebugWidgets>,
+    config: Res<DebugVisConfig>,
+    fps_window: Res<FpsWindow>,
+    overlay_camera: Query<(&Camera, &GlobalTransform), With<DebugOverlayCamera>>,
+    view: OverlayView,
+    mut gizmos: Gizmos<DebugTopGizmoGroup>,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    if *level == DebugLevel::Hidden || !widgets.contains(DebugWidgets::FPS_DIAL) {
+        return;
+    }
+
+    let Some((camera, camera_transform)) = overlay_camera
+        .single()
+        .ok()
+        .or_else(|| view.camera().map(|(_, camera, transform)| (camera, transform)))
+    else {
+        return;
+    };
+
+    let center = config.fps_dial_center;
+    let radius = config.fps_dial_radius;
+    let Some(plane) = ViewportPlane::new(camera, camera_transform, center, Vec2::splat(radius))
+    else {
+        return;
+    };
+    let project = |direction: Vec2| plane.project(center + direction * radius);
+
+    for (color, points) in arc_ranges() {
+        gizmos.linestrip(points.iter().map(|&point| project(point)), *color);
+    }
+
+    let Some(fps) = fps_window.fps() else {
+        return;
+    };
+    let fraction = (fps / (config.fps_target * FULL_SCALE)) as f32;
+    gizmos.line(
+        project(Vec2::ZERO),
+        project(dial_direction(fraction) * NEEDLE_LENGTH),
+        NEEDLE_COLOR,
+    );
+}
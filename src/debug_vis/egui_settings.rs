@@ -0,0 +1,201 @@
+//! egui window over the overlay's settings. Every control writes straight into the same
+//! resources the keys and command palette use, so the three never disagree.
+
+use super::{
+    ChartColorRamp, ChartPlacement, DebugCommandRegistry, DebugLevel, DebugVisConfig,
+    DebugWidgets, FpsWindow, FrameTimeHistory, OverlayCost, TimelineCategories,
+};
+use bevy::{
+    prelude::*,
+    window::{PresentMode, PrimaryWindow},
+};
+use bevy_egui::{EguiContexts, egui};
+
+const PRESENT_MODES: [PresentMode; 5] = [
+    PresentMode::AutoVsync,
+    PresentMode::AutoNoVsync,
+    PresentMode::Fifo,
+    PresentMode::Mailbox,
+    PresentMode::Immediate,
+];
+const LEVELS: [DebugLevel; 3] = [DebugLevel::Hidden, DebugLevel::FpsOnly, DebugLevel::Full];
+
+#[derive(Resource, Default)]
+pub(super) struct EguiSettingsOpen(bool);
+
+pub(super) fn register_settings_command(app: &mut App) {
+    app.init_resource::<EguiSettingsOpen>();
+    app.world_mut()
+        .resource_mut::<DebugCommandRegistry>()
+        .register("Show/hide settings panel", |commands| {
+            commands.queue(|world: &mut World| {
+                let mut open = world.resource_mut::<EguiSettingsOpen>();
+                open.0 = !open.0;
+            });
+        });
+}
+
+/// Edits through `bypass_change_detection` and only flags what the user actually changed,
+/// so systems keyed on `resource_changed` don't rerun every frame the panel is open.
+pub(super) fn show_egui_settings(
+    mut contexts: EguiContexts,
+    mut open: ResMut<EguiSettingsOpen>,
+    mut config: ResMut<DebugVisConfig>,
+    mut level: ResMut<DebugLevel>,
+    mut widgets: ResMut<DebugWidgets>,
+    mut ui_scale: ResMut<UiScale>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+    history: Res<FrameTimeHistory>,
+    fps_window: Res<FpsWindow>,
+    cost: Res<OverlayCost>,
+) -> Result {
+    let _cost = cost.measure();
+
+    if !open.0 {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+
+    let mut still_open = true;
+    egui::Window::new("Debug settings")
+        .open(&mut still_open)
+        .resizable(true)
+        .show(ctx, |ui| {
+            ui.heading("Stats");
+            let fps = fps_window.fps().map_or("--".to_string(), |fps| format!("{fps:.0}"));
+            let snapshot = history.snapshot();
+            ui.label(format!("FPS: {fps}"));
+            if let (Some(avg), Some(p95)) = (snapshot.avg_ms, snapshot.p95_ms) {
+                ui.label(format!("Frametime avg {avg:.2} ms, p95 {p95:.2} ms"));
+            }
+            ui.label(format!("Overlay cost: {:.2} ms", cost.last_frame.as_secs_f64() * 1000.0));
+
+            ui.separator();
+            ui.heading("Overlay");
+            let current_level = *level;
+            egui::ComboBox::from_label("Level")
+                .selected_text(format!("{current_level:?}"))
+                .show_ui(ui, |ui| {
+                    for option in LEVELS {
+                        if ui
+                            .selectable_label(current_level == option, format!("{option:?}"))
+                            .clicked()
+                        {
+                            level.set_if_neq(option);
+                        }
+                    }
+                });
+            for widget in DebugWidgets::ALL {
+                let mut enabled = widgets.contains(widget);
+                if ui.checkbox(&mut enabled, widget.name()).changed() {
+                    widgets.set(widget, enabled);
+                }
+            }
+            let current_placement = config.chart_placement;
+            egui::ComboBox::from_label("Chart placement")
+                .selected_text(format!("{current_placement:?}"))
+                .show_ui(ui, |ui| {
+                    for placement in ChartPlacement::PRESETS {
+                        if ui
+                            .selectable_label(
+                                current_placement == placement,
+                                format!("{placement:?}"),
+                            )
+                            .clicked()
+                        {
+                            config.chart_placement = placement;
+                        }
+                    }
+                });
+            let current_ramp = config.chart_color_ramp;
+            egui::ComboBox::from_label("Chart colors relative to")
+                .selected_text(format!("{current_ramp:?}"))
+                .show_ui(ui, |ui| {
+                    for ramp in ChartColorRamp::ALL {
+                        if ui
+                            .selectable_label(current_ramp == ramp, format!("{ramp:?}"))
+                            .clicked()
+                        {
+                            config.chart_color_ramp = ramp;
+                        }
+                    }
+                });
+            let mut scale = ui_scale.0;
+            if ui
+                .add(egui::Slider::new(&mut scale, 0.5..=3.0).text("UI scale"))
+                .changed()
+            {
+                ui_scale.0 = scale;
+            }
+
+            ui.separator();
+            ui.heading("Thresholds");
+            let edited = config.bypass_change_detection();
+            let mut changed = false;
+            changed |= ui
+                .add(egui::Slider::new(&mut edited.fps_target, 15.0..=240.0).text("Target FPS"))
+                .changed();
+            changed |= ui
+                .add(egui::Slider::new(&mut edited.spike_k_factor, 0.5..=6.0).text("Spike k"))
+                .changed();
+            changed |= ui
+                .add(
+                    egui::Slider::new(&mut edited.overlay_cost_warn_ms, 0.05..=5.0)
+                        .text("Overlay cost warning (ms)"),
+                )
+                .changed();
+            changed |= ui
+                .add(
+                    egui::Slider::new(&mut edited.throttle_warn_percent, 1.0..=50.0)
+                        .text("Throttle warning (%)"),
+                )
+                .changed();
+
+            ui.separator();
+            ui.heading("Timeline events");
+            for category in TimelineCategories::ALL {
+                let mut enabled = edited.timeline_categories.contains(category);
+                if ui.checkbox(&mut enabled, category.name()).changed() {
+                    edited.timeline_categories.set(category, enabled);
+                    changed = true;
+                }
+            }
+            if changed {
+                config.set_changed();
+            }
+
+            let Ok(mut window) = windows.single_mut() else {
+                return;
+            };
+            ui.separator();
+            ui.heading("Window");
+            let (mut width, mut height) = (window.width(), window.height());
+            let resized = ui
+                .add(egui::Slider::new(&mut width, 320.0..=3840.0).text("Width"))
+                .changed()
+                | ui
+                    .add(egui::Slider::new(&mut height, 240.0..=2160.0).text("Height"))
+                    .changed();
+            if resized {
+                window.resolution.set(width, height);
+            }
+            let current_mode = window.present_mode;
+            egui::ComboBox::from_label("Present mode")
+                .selected_text(format!("{current_mode:?}"))
+                .show_ui(ui, |ui| {
+                    for mode in PRESENT_MODES {
+                        if ui
+                            .selectable_label(current_mode == mode, format!("{mode:?}"))
+                            .clicked()
+                        {
+                            window.present_mode = mode;
+                        }
+                    }
+                });
+        });
+    if !still_open {
+        open.0 = false;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+//! Alternative presentation of the debug lines as an egui table.
+
+use super::{DebugTexts, egui_settings};
+use bevy::prelude::*;
+use bevy_egui::{EguiContexts, EguiPlugin, EguiPrimaryContextPass, egui};
+
+pub(super) struct DebugVisEguiPlugin;
+
+impl Plugin for DebugVisEguiPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin::default());
+        }
+        egui_settings::register_settings_command(app);
+        app.add_systems(
+            EguiPrimaryContextPass,
+            (show_egui_debug_table, egui_settings::show_egui_settings),
+        );
+    }
+}
+
+fn show_egui_debug_table(mut contexts: EguiContexts, texts: Res<DebugTexts>) -> Result {
+    let ctx = contexts.ctx_mut()?;
+
+    let mut rows: Vec<_> = texts.entries.iter().collect();
+    rows.sort_by_key(|(_, entry)| entry.line);
+
+    egui::Window::new("Debug")
+        .collapsible(true)
+        .resizable(true)
+        .show(ctx, |ui| {
+            egui::Grid::new("debug_vis_table")
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Line");
+                    ui.strong("Key");
+                    ui.strong("Message");
+                    ui.end_row();
+
+                    for (key, entry) in rows {
+                        ui.label(entry.line.to_string());
+                        if ui
+                            .link(key.as_str())
+                            .on_hover_text("Click to copy")
+                            .clicked()
+                        {
+                            ui.ctx().copy_text(key.clone());
+                        }
+                        match &entry.label {
+                            Some(label) => ui.label(format!("{label}{}", entry.message)),
+                            None => ui.label(entry.message.as_str()),
+                        };
+                        ui.end_row();
+                    }
+                });
+        });
+
+    Ok(())
+}
@@ -0,0 +1,131 @@
+//! Glob filter over debug line keys. Lines that belong together share a key prefix
+//! ("ai_enemy_3_state"), so `ai_*` shows one module's lines and hides the rest.
+
+use super::{DebugTextWriter, OverlayCost};
+use bevy::prelude::*;
+
+const FILTER_KEY: &str = "line_filter";
+
+/// While set, only debug lines whose key matches the glob are shown. `*` matches any run of
+/// characters. Hidden lines keep updating, so clearing the filter brings them back current.
+#[derive(Resource, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DebugLineFilter(Option<String>);
+
+impl DebugLineFilter {
+    pub fn set(&mut self, pattern: impl Into<String>) {
+        self.0 = Some(pattern.into());
+    }
+
+    pub fn clear(&mut self) {
+        self.0 = None;
+    }
+
+    pub fn pattern(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+
+    /// Whether the line under `key` passes the filter. The line naming the active filter
+    /// always does.
+    pub fn shows(&self, key: &str) -> bool {
+        match &self.0 {
+            Some(pattern) => key == FILTER_KEY || glob_match(pattern, key),
+            None => true,
+        }
+    }
+}
+
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts: Vec<&str> = parts.collect();
+    // No `*` at all: the prefix has to be the whole key.
+    let Some(last) = parts.pop() else {
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+pub(super) fn write_line_filter(
+    filter: Res<DebugLineFilter>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    if let Some(pattern) = filter.pattern() {
+        writer.write(FILTER_KEY, format!("Filter: {pattern}"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(pattern: &str) -> DebugLineFilter {
+        let mut filter = DebugLineFilter::default();
+        filter.set(pattern);
+        filter
+    }
+
+    #[test]
+    fn no_pattern_shows_everything() {
+        assert!(DebugLineFilter::default().shows("ai_enemy_3_state"));
+        assert!(DebugLineFilter::default().shows(""));
+    }
+
+    #[test]
+    fn prefix_glob() {
+        let ai = filter("ai_*");
+        assert!(ai.shows("ai_"));
+        assert!(ai.shows("ai_enemy_3_state"));
+        assert!(!ai.shows("main_ai_state"));
+        assert!(!ai.shows("ai"));
+    }
+
+    #[test]
+    fn suffix_and_middle_globs() {
+        let state = filter("*_state");
+        assert!(state.shows("ai_enemy_3_state"));
+        assert!(!state.shows("ai_enemy_3_state_changed"));
+
+        let enemy_state = filter("ai_*_state");
+        assert!(enemy_state.shows("ai_enemy_3_state"));
+        assert!(enemy_state.shows("ai__state"));
+        assert!(!enemy_state.shows("ai_state"));
+        assert!(!enemy_state.shows("physics_enemy_state"));
+
+        let several = filter("*enemy*3*");
+        assert!(several.shows("ai_enemy_3_state"));
+        assert!(!several.shows("ai_3_enemy_state"));
+    }
+
+    #[test]
+    fn pattern_without_star_is_exact() {
+        let exact = filter("fps");
+        assert!(exact.shows("fps"));
+        assert!(!exact.shows("fps_1s"));
+        assert!(!exact.shows("gpu_fps"));
+    }
+
+    #[test]
+    fn prefix_and_suffix_do_not_overlap() {
+        assert!(!filter("ab*b").shows("ab"));
+        assert!(filter("ab*b").shows("abb"));
+        assert!(filter("*").shows(""));
+    }
+
+    #[test]
+    fn filter_line_is_always_shown() {
+        assert!(filter("ai_*").shows(FILTER_KEY));
+        assert!(filter("nothing").shows(FILTER_KEY));
+    }
+}
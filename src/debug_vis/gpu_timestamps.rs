@@ -0,0 +1,234 @@
+//! GPU frame time from wgpu timestamp queries, for the `wgpu_timestamps` feature. Two empty
+//! compute passes, submitted just before and just after the render graph, stamp the GPU clock
+//! on either side of the frame's work; the difference is read back a frame or so later.
+
+use super::{DebugTextWriter, OverlayCost};
+use bevy::{
+    prelude::*,
+    render::{
+        Render, RenderApp, RenderSystems,
+        render_resource::{BufferUsages, CommandEncoderDescriptor, MapMode, WgpuFeatures},
+        renderer::{RenderDevice, RenderQueue, render_system},
+    },
+};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+
+const BEGIN_INDEX: u32 = 0;
+const END_INDEX: u32 = 1;
+const TIMESTAMP_BUFFER_SIZE: u64 = 2 * size_of::<u64>() as u64;
+
+/// Latest GPU frame time in ms, measured in the render world and shown from the main world.
+#[derive(Resource, Clone, Default)]
+pub(super) struct SharedGpuFrameTime(Arc<Mutex<Option<f64>>>);
+
+/// Render-world query set and buffers for one frame's pair of timestamps. A new frame is only
+/// measured once the previous one has been read back, so a single readback buffer is enough.
+#[derive(Resource)]
+pub(super) struct GpuTimestampQuery {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    /// Nanoseconds per timestamp tick.
+    period_ns: f32,
+    /// Set from a frame's resolve until its readback has been read.
+    in_flight: Arc<AtomicBool>,
+    /// Set by the map callback once `readback_buffer` can be read.
+    mapped: Arc<AtomicBool>,
+    /// Whether the current frame got a begin timestamp, and so needs the end one.
+    measuring: bool,
+}
+
+impl GpuTimestampQuery {
+    fn new(device: &wgpu::Device, period_ns: f32) -> Self {
+        let buffer = |label, usage| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: TIMESTAMP_BUFFER_SIZE,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        Self {
+            query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                label: Some("debug_vis_gpu_timestamps"),
+                ty: wgpu::QueryType::Timestamp,
+                count: 2,
+            }),
+            resolve_buffer: buffer(
+                "debug_vis_gpu_timestamps_resolve",
+                BufferUsages::QUERY_RESOLVE | BufferUsages::COPY_SRC,
+            ),
+            readback_buffer: buffer(
+                "debug_vis_gpu_timestamps_readback",
+                BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            ),
+            period_ns,
+            in_flight: Arc::default(),
+            mapped: Arc::default(),
+            measuring: false,
+        }
+    }
+
+    /// Writes one timestamp from an empty compute pass. Timestamps on pass boundaries only
+    /// need `TIMESTAMP_QUERY`, unlike `CommandEncoder::write_timestamp`, so this works on
+    /// WebGPU too.
+    fn stamp(&self, encoder: &mut wgpu::CommandEncoder, index: u32) {
+        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("debug_vis_gpu_timestamp"),
+            timestamp_writes: Some(wgpu::ComputePassTimestampWrites {
+                query_set: &self.query_set,
+                beginning_of_pass_write_index: Some(index),
+                end_of_pass_write_index: None,
+            }),
+        });
+    }
+
+    /// GPU time between the two timestamps in ms, once the readback is mapped. Unmaps it and
+    /// frees the query for the next frame.
+    fn take_frame_time(&self) -> Option<f64> {
+        if !self.mapped.swap(false, Ordering::AcqRel) {
+            return None;
+        }
+        let timestamps: Vec<u64> = self
+            .readback_buffer
+            .slice(..)
+            .get_mapped_range()
+            .chunks_exact(size_of::<u64>())
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap_or_default()))
+            .collect();
+        self.readback_buffer.unmap();
+        self.in_flight.store(false, Ordering::Release);
+
+        let ticks = timestamps[END_INDEX as usize].checked_sub(timestamps[BEGIN_INDEX as usize])?;
+        Some(ticks as f64 * f64::from(self.period_ns) / 1_000_000.0)
+    }
+}
+
+pub(super) struct DebugVisGpuTimestampsPlugin;
+
+impl Plugin for DebugVisGpuTimestampsPlugin {
+    fn build(&self, app: &mut App) {
+        let shared = SharedGpuFrameTime::default();
+        app.insert_resource(shared.clone())
+            .add_systems(Update, write_gpu_frame_time);
+
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app.insert_resource(shared).add_systems(
+                Render,
+                (
+                    begin_gpu_frame.before(render_system),
+                    end_gpu_frame.after(render_system),
+                )
+                    .in_set(RenderSystems::Render),
+            );
+        }
+    }
+
+    /// The render device only exists once the renderer has started.
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+        let world = render_app.world();
+        let (Some(device), Some(queue)) = (
+            world.get_resource::<RenderDevice>(),
+            world.get_resource::<RenderQueue>(),
+        ) else {
+            return;
+        };
+        if !device.features().contains(WgpuFeatures::TIMESTAMP_QUERY) {
+            warn!("debug_vis: GPU timestamps unavailable, the device lacks TIMESTAMP_QUERY");
+            return;
+        }
+        let query = GpuTimestampQuery::new(device.wgpu_device(), queue.get_timestamp_period());
+        render_app.insert_resource(query);
+    }
+}
+
+fn begin_gpu_frame(
+    query: Option<ResMut<GpuTimestampQuery>>,
+    shared: Res<SharedGpuFrameTime>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+) {
+    let Some(mut query) = query else {
+        return;
+    };
+
+    if let Some(frame_time_ms) = query.take_frame_time()
+        && let Ok(mut shared) = shared.0.lock()
+    {
+        *shared = Some(frame_time_ms);
+    }
+
+    query.measuring = !query.in_flight.load(Ordering::Acquire);
+    if !query.measuring {
+        return;
+    }
+    let mut encoder = device.wgpu_device().create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("debug_vis_gpu_frame_begin"),
+    });
+    query.stamp(&mut encoder, BEGIN_INDEX);
+    queue.submit([encoder.finish()]);
+}
+
+fn end_gpu_frame(
+    query: Option<ResMut<GpuTimestampQuery>>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+) {
+    let Some(mut query) = query else {
+        return;
+    };
+    if !std::mem::take(&mut query.measuring) {
+        return;
+    }
+
+    let mut encoder = device.wgpu_device().create_command_encoder(&CommandEncoderDescriptor {
+        label: Some("debug_vis_gpu_frame_end"),
+    });
+    query.stamp(&mut encoder, END_INDEX);
+    encoder.resolve_query_set(
+        &query.query_set,
+        BEGIN_INDEX..END_INDEX + 1,
+        &query.resolve_buffer,
+        0,
+    );
+    encoder.copy_buffer_to_buffer(
+        &query.resolve_buffer,
+        0,
+        &query.readback_buffer,
+        0,
+        TIMESTAMP_BUFFER_SIZE,
+    );
+    queue.submit([encoder.finish()]);
+
+    query.in_flight.store(true, Ordering::Release);
+    let (mapped, in_flight) = (query.mapped.clone(), query.in_flight.clone());
+    query
+        .readback_buffer
+        .slice(..)
+        .map_async(MapMode::Read, move |result| match result {
+            Ok(()) => mapped.store(true, Ordering::Release),
+            Err(err) => {
+                warn!("debug_vis: GPU timestamp readback failed: {err}");
+                in_flight.store(false, Ordering::Release);
+            }
+        });
+}
+
+fn write_gpu_frame_time(
+    shared: Res<SharedGpuFrameTime>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let Some(frame_time_ms) = shared.0.lock().ok().and_then(|frame_time| *frame_time) else {
+        return;
+    };
+    writer.write("gpu_frame_time", format!("GPU: {frame_time_ms:.2} ms"));
+}
@@ -0,0 +1,186 @@
+//! Help panel listing every registered [`DebugKeybindings`] entry.
+
+use super::{
+    DEBUG_TEXT_COLOR, DebugVisConfig, LINE_HEIGHT,
+    keybindings::{DebugAction, DebugKeybindings},
+};
+use bevy::{prelude::*, window::PrimaryWindow};
+
+const HELP_HIGHLIGHT_SECONDS: f32 = 0.5;
+const HELP_HIGHLIGHT_COLOR: Color = Color::srgb(1.0, 1.0, 0.0);
+const HELP_PADDING: f32 = 12.0;
+const HELP_KEY_COLUMN_WIDTH: f32 = 90.0;
+
+#[derive(Resource, Debug)]
+pub(super) struct HelpPanelState {
+    pub open: bool,
+    /// While open, listed keys highlight their row instead of running their action.
+    pub discovery: bool,
+}
+
+impl Default for HelpPanelState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            discovery: true,
+        }
+    }
+}
+
+/// Row briefly highlighted by a key press in discovery mode, with seconds remaining.
+#[derive(Resource, Debug, Default)]
+pub(super) struct HelpHighlight(Option<(usize, f32)>);
+
+impl HelpHighlight {
+    pub fn set(&mut self, row: usize) {
+        self.0 = Some((row, HELP_HIGHLIGHT_SECONDS));
+    }
+}
+
+#[derive(Component)]
+pub(super) struct HelpPanel;
+
+#[derive(Component)]
+pub(super) struct HelpRow(usize);
+
+pub(super) fn handle_help_actions(
+    mut actions: MessageReader<DebugAction>,
+    mut help: ResMut<HelpPanelState>,
+) {
+    for action in actions.read() {
+        match action {
+            DebugAction::ToggleHelp => help.open = !help.open,
+            DebugAction::ToggleHelpDiscovery if help.open => help.discovery = !help.discovery,
+            _ => {}
+        }
+    }
+}
+
+pub(super) fn sync_help_panel(
+    mut commands: Commands,
+    help: Res<HelpPanelState>,
+    bindings: Res<DebugKeybindings>,
+    config: Res<DebugVisConfig>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    panels: Query<Entity, With<HelpPanel>>,
+) {
+    if !help.is_changed() && !bindings.is_changed() {
+        return;
+    }
+
+    for panel in panels.iter() {
+        commands.entity(panel).despawn();
+    }
+    if !help.open {
+        return;
+    }
+
+    let window_height = windows.single().map(|w| w.height()).unwrap_or(720.0);
+    // Title, discovery status and the "more…" line take three rows.
+    let max_rows = ((window_height - 2.0 * HELP_PADDING) / LINE_HEIGHT) as usize;
+    let max_rows = max_rows.saturating_sub(3).max(1);
+
+    let bindings: Vec<_> = bindings.iter().collect();
+    let shown = bindings.len().min(max_rows);
+
+    commands
+        .spawn((
+            HelpPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(HELP_PADDING),
+                right: Val::Px(HELP_PADDING),
+                max_height: Val::Px(window_height - 2.0 * HELP_PADDING),
+                padding: UiRect::all(Val::Px(HELP_PADDING)),
+                flex_direction: FlexDirection::Column,
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.75)),
+            // Above the rest of the overlay.
+            GlobalZIndex(config.overlay_z_index.saturating_add(1)),
+        ))
+        .with_children(|panel| {
+            panel.spawn(help_text("Debug keybindings".to_string(), DEBUG_TEXT_COLOR));
+            let discovery = if help.discovery {
+                "Discovery mode: on (keys highlight instead of running)"
+            } else {
+                "Discovery mode: off"
+            };
+            panel.spawn(help_text(discovery.to_string(), DEBUG_TEXT_COLOR));
+
+            for (row, (action, key)) in bindings.iter().take(shown).enumerate() {
+                panel
+                    .spawn((
+                        HelpRow(row),
+                        Node {
+                            flex_direction: FlexDirection::Row,
+                            ..default()
+                        },
+                    ))
+                    .with_children(|line| {
+                        line.spawn((
+                            help_text(key.to_string(), DEBUG_TEXT_COLOR),
+                            Node {
+                                width: Val::Px(HELP_KEY_COLUMN_WIDTH),
+                                ..default()
+                            },
+                        ));
+                        line.spawn(help_text(
+                            action.description().to_string(),
+                            DEBUG_TEXT_COLOR,
+                        ));
+                    });
+            }
+
+            if shown < bindings.len() {
+                panel.spawn(help_text(
+                    format!("more… (+{})", bindings.len() - shown),
+                    DEBUG_TEXT_COLOR,
+                ));
+            }
+        });
+}
+
+pub(super) fn tick_help_highlight(
+    time: Res<Time<Real>>,
+    mut highlight: ResMut<HelpHighlight>,
+    rows: Query<(&HelpRow, &Children)>,
+    mut colors: Query<&mut TextColor>,
+) {
+    let Some((row, remaining)) = highlight.0 else {
+        return;
+    };
+
+    let remaining = remaining - time.delta_secs();
+    highlight.0 = (remaining > 0.0).then_some((row, remaining));
+
+    let color = if remaining > 0.0 {
+        HELP_HIGHLIGHT_COLOR
+    } else {
+        DEBUG_TEXT_COLOR
+    };
+    for (help_row, children) in rows.iter() {
+        let color = if help_row.0 == row {
+            color
+        } else {
+            DEBUG_TEXT_COLOR
+        };
+        for child in children.iter() {
+            if let Ok(mut text_color) = colors.get_mut(child) {
+                text_color.0 = color;
+            }
+        }
+    }
+}
+
+fn help_text(text: String, color: Color) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(color),
+    )
+}
@@ -0,0 +1,102 @@
+//! Reloads [`DebugVisConfig`] from `assets/debug_vis.toml` whenever the file changes, so the
+//! overlay can be tuned without recompiling.
+
+use super::DebugVisConfig;
+use bevy::{
+    asset::{AssetLoader, AsyncReadExt, LoadContext, io::Reader},
+    prelude::*,
+};
+use std::fmt;
+
+#[cfg(target_arch = "wasm32")]
+compile_error!(
+    "the `hot_reload` feature is native only: Bevy's file watcher doesn't run on wasm32"
+);
+
+const CONFIG_PATH: &str = "debug_vis.toml";
+
+#[derive(Asset, TypePath, Debug, serde::Deserialize)]
+#[serde(transparent)]
+struct DebugVisConfigAsset(DebugVisConfig);
+
+#[derive(Default, TypePath)]
+struct DebugVisConfigLoader;
+
+#[derive(Debug)]
+enum DebugVisConfigLoadError {
+    Io(std::io::Error),
+    Toml(toml::de::Error),
+}
+
+impl fmt::Display for DebugVisConfigLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebugVisConfigLoadError::Io(err) => write!(f, "failed to read {CONFIG_PATH}: {err}"),
+            DebugVisConfigLoadError::Toml(err) => write!(f, "invalid {CONFIG_PATH}: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for DebugVisConfigLoadError {}
+
+impl AssetLoader for DebugVisConfigLoader {
+    type Asset = DebugVisConfigAsset;
+    type Settings = ();
+    type Error = DebugVisConfigLoadError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut text = String::new();
+        reader
+            .read_to_string(&mut text)
+            .await
+            .map_err(DebugVisConfigLoadError::Io)?;
+        toml::from_str(&text).map_err(DebugVisConfigLoadError::Toml)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["toml"]
+    }
+}
+
+#[derive(Resource)]
+struct DebugVisConfigHandle(Handle<DebugVisConfigAsset>);
+
+pub(super) struct DebugVisHotReloadPlugin;
+
+impl Plugin for DebugVisHotReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<DebugVisConfigAsset>()
+            .init_asset_loader::<DebugVisConfigLoader>()
+            .add_systems(Startup, load_config_file)
+            .add_systems(Update, apply_config_file);
+    }
+}
+
+fn load_config_file(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(DebugVisConfigHandle(asset_server.load(CONFIG_PATH)));
+}
+
+fn apply_config_file(
+    mut events: MessageReader<AssetEvent<DebugVisConfigAsset>>,
+    handle: Res<DebugVisConfigHandle>,
+    assets: Res<Assets<DebugVisConfigAsset>>,
+    mut config: ResMut<DebugVisConfig>,
+) {
+    for event in events.read() {
+        let (AssetEvent::Added { id } | AssetEvent::Modified { id }) = event else {
+            continue;
+        };
+        if *id != handle.0.id() {
+            continue;
+        }
+        if let Some(asset) = assets.get(*id) {
+            *config = asset.0.clone();
+            info!("Reloaded {CONFIG_PATH}");
+        }
+    }
+}
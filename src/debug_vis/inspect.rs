@@ -0,0 +1,140 @@
+//! Shows registered components of one chosen entity, see [`DebugInspectTarget`].
+
+use super::{DebugTextWriter, DebugTexts, DebugVisConfig};
+use bevy::{
+    ecs::{system::SystemState, world::EntityRef},
+    prelude::*,
+};
+use std::any::TypeId;
+
+const INSPECT_PREFIX: &str = "inspect_";
+
+/// A component that can list its state as `(label, value)` lines for the overlay.
+pub trait DebugDisplay {
+    fn debug_lines(&self) -> Vec<(String, String)>;
+}
+
+impl DebugDisplay for Transform {
+    fn debug_lines(&self) -> Vec<(String, String)> {
+        let (yaw, pitch, roll) = self.rotation.to_euler(EulerRot::YXZ);
+        vec![
+            ("translation".to_string(), format!("{:.2}", self.translation)),
+            (
+                "rotation".to_string(),
+                format!(
+                    "yaw {:.1}° pitch {:.1}° roll {:.1}°",
+                    yaw.to_degrees(),
+                    pitch.to_degrees(),
+                    roll.to_degrees()
+                ),
+            ),
+            ("scale".to_string(), format!("{:.2}", self.scale)),
+        ]
+    }
+}
+
+impl DebugDisplay for GlobalTransform {
+    fn debug_lines(&self) -> Vec<(String, String)> {
+        vec![("translation".to_string(), format!("{:.2}", self.translation()))]
+    }
+}
+
+impl DebugDisplay for Visibility {
+    fn debug_lines(&self) -> Vec<(String, String)> {
+        vec![("visibility".to_string(), format!("{self:?}"))]
+    }
+}
+
+impl DebugDisplay for Name {
+    fn debug_lines(&self) -> Vec<(String, String)> {
+        vec![("name".to_string(), self.as_str().to_string())]
+    }
+}
+
+/// The entity whose registered components the overlay lists. Remove it to stop inspecting.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugInspectTarget(pub Entity);
+
+struct DebugDisplayAccessor {
+    type_id: TypeId,
+    name: String,
+    lines: fn(EntityRef) -> Option<Vec<(String, String)>>,
+}
+
+/// Component types shown for [`DebugInspectTarget`], in registration order.
+#[derive(Resource, Default)]
+pub(super) struct DebugDisplayRegistry(Vec<DebugDisplayAccessor>);
+
+fn component_lines<T: Component + DebugDisplay>(entity: EntityRef) -> Option<Vec<(String, String)>> {
+    entity.get::<T>().map(DebugDisplay::debug_lines)
+}
+
+pub trait DebugDisplayAppExt {
+    /// Lists `T` in the overlay whenever the [`DebugInspectTarget`] entity has one.
+    fn register_debug_display<T: Component + DebugDisplay>(&mut self) -> &mut Self;
+}
+
+impl DebugDisplayAppExt for App {
+    fn register_debug_display<T: Component + DebugDisplay>(&mut self) -> &mut Self {
+        let mut registry = self
+            .world_mut()
+            .get_resource_or_init::<DebugDisplayRegistry>();
+        let type_id = TypeId::of::<T>();
+        if !registry.0.iter().any(|accessor| accessor.type_id == type_id) {
+            registry.0.push(DebugDisplayAccessor {
+                type_id,
+                name: ShortName::of::<T>().to_string(),
+                lines: component_lines::<T>,
+            });
+        }
+        self
+    }
+}
+
+pub(super) fn write_inspected_components(
+    world: &mut World,
+    writer: &mut SystemState<DebugTextWriter>,
+    mut previous: Local<Option<Entity>>,
+) {
+    let target = world
+        .get_resource::<DebugInspectTarget>()
+        .map(|target| target.0)
+        .filter(|entity| world.entities().contains(*entity));
+
+    let mut lines = Vec::new();
+    if let (Some(entity), Some(registry)) = (target, world.get_resource::<DebugDisplayRegistry>()) {
+        let entity_ref = world.entity(entity);
+        for accessor in &registry.0 {
+            for (label, value) in (accessor.lines)(entity_ref).into_iter().flatten() {
+                lines.push((
+                    format!("{INSPECT_PREFIX}{}_{label}", accessor.name),
+                    format!("{}.{label}: {value}", accessor.name),
+                ));
+            }
+        }
+    }
+
+    if *previous != target {
+        *previous = target;
+        world.resource_scope(|world, mut texts: Mut<DebugTexts>| {
+            let max_pooled_lines = world.resource::<DebugVisConfig>().max_pooled_lines;
+            texts.evict_prefix(INSPECT_PREFIX, &mut world.commands(), max_pooled_lines);
+        });
+        world.flush();
+    }
+
+    let Some(entity) = target else {
+        return;
+    };
+
+    let header = match world.get::<Name>(entity) {
+        Some(name) => format!("Inspect: {name} ({entity})"),
+        None => format!("Inspect: {entity}"),
+    };
+    let mut writer_param = writer.get_mut(world);
+    writer_param.write(format!("{INSPECT_PREFIX}entity"), header);
+    for (key, message) in lines {
+        writer_param.write(key, message);
+    }
+    writer.apply(world);
+}
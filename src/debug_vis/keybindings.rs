@@ -0,0 +1,225 @@
+//! Keyboard shortcuts for the overlay. Every shortcut is registered in [`DebugKeybindings`]
+//! rather than checked ad hoc, so the help panel can always list them accurately.
+
+use super::help_panel::{HelpHighlight, HelpPanelState};
+use bevy::prelude::*;
+use std::fmt;
+
+/// Something a debug shortcut can trigger.
+#[derive(Message, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DebugAction {
+    ToggleDebugLevel,
+    ToggleHelp,
+    ToggleHelpDiscovery,
+    ToggleRecording,
+    TogglePicking,
+    CaptureOverlay,
+    CycleProfile,
+    ToggleCommandPalette,
+    ReplayTogglePause,
+    ReplayNormalSpeed,
+    ReplayFastSpeed,
+    ReplayNextSpike,
+}
+
+impl DebugAction {
+    pub fn description(self) -> &'static str {
+        match self {
+            DebugAction::ToggleDebugLevel => "Cycle debug level",
+            DebugAction::ToggleHelp => "Show/hide this help",
+            DebugAction::ToggleHelpDiscovery => "Help: highlight keys instead of running them",
+            DebugAction::ToggleRecording => "Pause/resume frame time recording",
+            DebugAction::TogglePicking => "Click-to-inspect entity picking",
+            DebugAction::CaptureOverlay => "Save the overlay alone as a PNG",
+            DebugAction::CycleProfile => "Switch to the next overlay profile",
+            DebugAction::ToggleCommandPalette => "Open/close the command palette",
+            DebugAction::ReplayTogglePause => "Replay: pause/resume",
+            DebugAction::ReplayNormalSpeed => "Replay: play at 1×",
+            DebugAction::ReplayFastSpeed => "Replay: play at 10×",
+            DebugAction::ReplayNextSpike => "Replay: jump to the next spike",
+        }
+    }
+}
+
+/// A key plus the modifiers that must be held with it. Modifiers match exactly, so `F3`
+/// does not fire while `Ctrl+F3` is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyBinding {
+    pub const fn new(key: KeyCode) -> Self {
+        Self {
+            key,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    pub const fn with_ctrl(mut self) -> Self {
+        self.ctrl = true;
+        self
+    }
+
+    pub const fn with_shift(mut self) -> Self {
+        self.shift = true;
+        self
+    }
+
+    pub const fn with_alt(mut self) -> Self {
+        self.alt = true;
+        self
+    }
+
+    fn just_pressed(&self, keys: &ButtonInput<KeyCode>) -> bool {
+        keys.just_pressed(self.key)
+            && self.ctrl == keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+            && self.shift == keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+            && self.alt == keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+    }
+}
+
+impl From<KeyCode> for KeyBinding {
+    fn from(key: KeyCode) -> Self {
+        Self::new(key)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        write!(f, "{:?}", self.key)
+    }
+}
+
+/// Maps every [`DebugAction`] to its key, in display order. A `None` binding disables the
+/// action entirely, e.g. for shipping builds.
+#[derive(Resource, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DebugKeybindings {
+    bindings: Vec<(DebugAction, Option<KeyBinding>)>,
+}
+
+impl Default for DebugKeybindings {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (DebugAction::ToggleHelp, Some(KeyCode::F1.into())),
+                (DebugAction::ToggleHelpDiscovery, Some(KeyCode::F2.into())),
+                (DebugAction::ToggleDebugLevel, Some(KeyCode::F3.into())),
+                (DebugAction::ToggleRecording, Some(KeyCode::F4.into())),
+                (DebugAction::TogglePicking, Some(KeyCode::F5.into())),
+                (DebugAction::CaptureOverlay, Some(KeyCode::F6.into())),
+                (DebugAction::CycleProfile, Some(KeyCode::F7.into())),
+                (DebugAction::ToggleCommandPalette, Some(KeyCode::F8.into())),
+                (DebugAction::ReplayTogglePause, Some(KeyCode::F9.into())),
+                (DebugAction::ReplayNormalSpeed, Some(KeyCode::F10.into())),
+                (
+                    DebugAction::ReplayFastSpeed,
+                    Some(KeyBinding::new(KeyCode::F10).with_shift()),
+                ),
+                (
+                    DebugAction::ReplayNextSpike,
+                    Some(KeyBinding::new(KeyCode::F9).with_shift()),
+                ),
+            ],
+        }
+    }
+}
+
+impl DebugKeybindings {
+    /// Binds `action` to `binding`, replacing any previous key for that action.
+    /// Pass `None` to disable the action.
+    pub fn bind(&mut self, action: DebugAction, binding: impl Into<Option<KeyBinding>>) {
+        let binding = binding.into();
+        match self.bindings.iter_mut().find(|(bound, _)| *bound == action) {
+            Some(entry) => entry.1 = binding,
+            None => self.bindings.push((action, binding)),
+        }
+    }
+
+    pub fn with(mut self, action: DebugAction, binding: impl Into<Option<KeyBinding>>) -> Self {
+        self.bind(action, binding);
+        self
+    }
+
+    pub fn get(&self, action: DebugAction) -> Option<KeyBinding> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .and_then(|(_, binding)| *binding)
+    }
+
+    /// Bound actions, in display order. Disabled actions are skipped.
+    pub fn iter(&self) -> impl Iterator<Item = (DebugAction, KeyBinding)> + '_ {
+        self.bindings
+            .iter()
+            .filter_map(|(action, binding)| binding.map(|binding| (*action, binding)))
+    }
+
+    /// Keys assigned to more than one action, with the actions sharing each.
+    pub fn conflicts(&self) -> Vec<(KeyBinding, Vec<DebugAction>)> {
+        let mut conflicts: Vec<(KeyBinding, Vec<DebugAction>)> = Vec::new();
+        for (action, binding) in self.iter() {
+            match conflicts.iter_mut().find(|(key, _)| *key == binding) {
+                Some((_, actions)) => actions.push(action),
+                None => conflicts.push((binding, vec![action])),
+            }
+        }
+        conflicts.retain(|(_, actions)| actions.len() > 1);
+        conflicts
+    }
+}
+
+pub(super) fn warn_keybinding_conflicts(bindings: Res<DebugKeybindings>) {
+    if !bindings.is_changed() {
+        return;
+    }
+
+    for (binding, actions) in bindings.conflicts() {
+        let actions: Vec<_> = actions.iter().map(|action| action.description()).collect();
+        warn!(
+            "debug_vis: {binding} is bound to several actions: {}",
+            actions.join(", ")
+        );
+    }
+}
+
+pub(super) fn read_debug_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<DebugKeybindings>,
+    help: Res<HelpPanelState>,
+    mut highlight: ResMut<HelpHighlight>,
+    mut actions: MessageWriter<DebugAction>,
+) {
+    for (row, (action, binding)) in bindings.iter().enumerate() {
+        if !binding.just_pressed(&keys) {
+            continue;
+        }
+
+        let help_control = matches!(
+            action,
+            DebugAction::ToggleHelp | DebugAction::ToggleHelpDiscovery
+        );
+        if help.open && help.discovery && !help_control {
+            highlight.set(row);
+        } else {
+            actions.write(action);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Frame pacing bimodality. Frames alternating between two durations, e.g. 8 ms and 25 ms
+//! under vsync, average out to a healthy-looking 16.5 ms while feeling worse than a steady
+//! 25 ms. Splitting the window at its mean and measuring both halves shows the two clusters.
+
+use super::{DebugTextWriter, FrameTimeHistory, HEALTH_WARN_COLOR, OverlayCost};
+use bevy::{diagnostic::FrameCount, prelude::*};
+
+/// A frame within this fraction of a mode's frame time counts toward it.
+const MODE_TOLERANCE: f64 = 0.1;
+/// Share of the window each mode needs on its own, so one spike isn't a second mode.
+const MIN_MODE_SHARE: f64 = 0.2;
+/// Share of the window the two modes need together.
+const MIN_BIMODAL_SHARE: f64 = 0.5;
+/// How much slower the slow mode has to be than the fast one.
+const MIN_MODE_RATIO: f64 = 1.5;
+const MIN_SAMPLES: usize = 20;
+
+/// One cluster of frame times.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PacingMode {
+    /// Median of the frames on this side of the window mean.
+    pub frame_time_ms: f64,
+    /// Fraction of the window within 10% of `frame_time_ms`.
+    pub share: f64,
+}
+
+/// Frame times clustered around two distinct values instead of one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BimodalPacing {
+    pub fast: PacingMode,
+    pub slow: PacingMode,
+}
+
+impl BimodalPacing {
+    /// `None` unless both modes hold at least 20% of the frames each and half of them
+    /// together, with the slow one at least 1.5× the fast one.
+    pub fn detect(frame_times_ms: impl IntoIterator<Item = f64>) -> Option<Self> {
+        let mut frame_times: Vec<f64> = frame_times_ms.into_iter().collect();
+        if frame_times.len() < MIN_SAMPLES {
+            return None;
+        }
+        frame_times.sort_by(f64::total_cmp);
+
+        let mean = frame_times.iter().sum::<f64>() / frame_times.len() as f64;
+        let split = frame_times.partition_point(|&frame_time| frame_time < mean);
+        let (below, above) = frame_times.split_at(split);
+        if below.is_empty() || above.is_empty() {
+            return None;
+        }
+
+        let mode = |side: &[f64]| {
+            let center = side[side.len() / 2];
+            let near = frame_times
+                .iter()
+                .filter(|&&frame_time| (frame_time - center).abs() <= center * MODE_TOLERANCE)
+                .count();
+            PacingMode {
+                frame_time_ms: center,
+                share: near as f64 / frame_times.len() as f64,
+            }
+        };
+        let (fast, slow) = (mode(below), mode(above));
+
+        let bimodal = fast.share >= MIN_MODE_SHARE
+            && slow.share >= MIN_MODE_SHARE
+            && fast.share + slow.share >= MIN_BIMODAL_SHARE
+            && slow.frame_time_ms >= fast.frame_time_ms * MIN_MODE_RATIO;
+        bimodal.then_some(Self { fast, slow })
+    }
+}
+
+/// Sent when the frame time window turns bimodal, once per episode.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct BimodalPacingDetected {
+    /// [`FrameCount`] at detection.
+    pub frame: u32,
+    pub pacing: BimodalPacing,
+}
+
+pub(super) fn detect_bimodal_pacing(
+    frame: Res<FrameCount>,
+    history: Res<FrameTimeHistory>,
+    mut was_bimodal: Local<bool>,
+    mut detected: MessageWriter<BimodalPacingDetected>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let Some(pacing) = BimodalPacing::detect(history.frame_times_ms.iter().copied()) else {
+        *was_bimodal = false;
+        return;
+    };
+    if !*was_bimodal {
+        *was_bimodal = true;
+        detected.write(BimodalPacingDetected {
+            frame: frame.0,
+            pacing,
+        });
+    }
+
+    writer.write_colored(
+        "pacing",
+        format!(
+            "Pacing: bimodal ({:.0}% @ {:.1} ms, {:.0}% @ {:.1} ms)",
+            pacing.fast.share * 100.0,
+            pacing.fast.frame_time_ms,
+            pacing.slow.share * 100.0,
+            pacing.slow.frame_time_ms
+        ),
+        HEALTH_WARN_COLOR,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 300 frames following `pattern`, each nudged by up to ±3% so no two are identical.
+    fn frames(pattern: &[f64]) -> Vec<f64> {
+        (0..300)
+            .map(|i| pattern[i % pattern.len()] * (1.0 + (i * 7 % 13) as f64 / 200.0 - 0.03))
+            .collect()
+    }
+
+    #[test]
+    fn alternating_16_and_33_is_bimodal() {
+        let pacing = BimodalPacing::detect(frames(&[16.0, 33.0])).expect("bimodal");
+        assert!((pacing.fast.frame_time_ms - 16.0).abs() < 0.5, "{pacing:?}");
+        assert!((pacing.slow.frame_time_ms - 33.0).abs() < 1.0, "{pacing:?}");
+        assert_eq!(pacing.fast.share, 0.5);
+        assert_eq!(pacing.slow.share, 0.5);
+    }
+
+    #[test]
+    fn two_fast_one_slow_is_bimodal() {
+        let pacing = BimodalPacing::detect(frames(&[16.0, 16.0, 33.0])).expect("bimodal");
+        assert!((pacing.fast.share - 2.0 / 3.0).abs() < 1e-9);
+        assert!((pacing.slow.share - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn steady_frames_are_not_bimodal() {
+        assert_eq!(BimodalPacing::detect([16.6; 300]), None);
+        assert_eq!(BimodalPacing::detect(frames(&[16.6])), None);
+    }
+
+    #[test]
+    fn single_spike_is_not_a_mode() {
+        let mut frame_times = frames(&[16.6]);
+        frame_times[150] = 120.0;
+        assert_eq!(BimodalPacing::detect(frame_times), None);
+    }
+
+    #[test]
+    fn close_modes_are_not_bimodal() {
+        assert_eq!(BimodalPacing::detect(frames(&[16.0, 20.0])), None);
+    }
+
+    #[test]
+    fn short_windows_are_skipped() {
+        let frame_times = frames(&[16.0, 33.0]);
+        assert_eq!(BimodalPacing::detect(frame_times[..MIN_SAMPLES - 1].iter().copied()), None);
+        assert!(BimodalPacing::detect(frame_times[..MIN_SAMPLES].iter().copied()).is_some());
+    }
+}
@@ -0,0 +1,192 @@
+//! Command palette: a full-screen list of [`DebugCommand`]s, picked with the arrow keys and
+//! run with Enter, for debug actions that don't deserve a key of their own.
+
+use super::{
+    DEBUG_TEXT_COLOR, DebugVisConfig, FrameTimeHistory, RecordingEnabled,
+    keybindings::DebugAction,
+};
+use bevy::prelude::*;
+use std::sync::Arc;
+
+const PALETTE_SELECTED_COLOR: Color = Color::srgb(1.0, 1.0, 0.0);
+const PALETTE_PADDING: f32 = 48.0;
+
+/// An entry in the command palette.
+#[derive(Clone)]
+pub struct DebugCommand {
+    pub label: String,
+    pub action: Arc<dyn Fn(&mut Commands) + Send + Sync>,
+}
+
+/// Commands listed by the palette, in registration order. Comes with a few for the overlay
+/// itself; add your own with [`register`](Self::register).
+#[derive(Resource, Clone)]
+pub struct DebugCommandRegistry {
+    commands: Vec<DebugCommand>,
+}
+
+impl Default for DebugCommandRegistry {
+    fn default() -> Self {
+        let mut registry = Self {
+            commands: Vec::new(),
+        };
+        registry.register("Reset frame time history", |commands| {
+            commands.queue(|world: &mut World| world.resource_mut::<FrameTimeHistory>().clear());
+        });
+        registry.register("Pause/resume recording", |commands| {
+            commands.queue(|world: &mut World| {
+                let mut recording = world.resource_mut::<RecordingEnabled>();
+                recording.0 = !recording.0;
+            });
+        });
+        registry.register("Move chart to next placement", |commands| {
+            commands.queue(|world: &mut World| {
+                let mut config = world.resource_mut::<DebugVisConfig>();
+                config.chart_placement = config.chart_placement.next();
+            });
+        });
+        registry.register("Next overlay profile", |commands| {
+            commands.write_message(DebugAction::CycleProfile);
+        });
+        #[cfg(not(target_arch = "wasm32"))]
+        registry.register("Save the overlay as a PNG", |commands| {
+            commands.write_message(DebugAction::CaptureOverlay);
+        });
+        registry
+    }
+}
+
+impl DebugCommandRegistry {
+    /// Adds a command, replacing any command with the same label.
+    pub fn register(
+        &mut self,
+        label: impl Into<String>,
+        action: impl Fn(&mut Commands) + Send + Sync + 'static,
+    ) {
+        let command = DebugCommand {
+            label: label.into(),
+            action: Arc::new(action),
+        };
+        match self
+            .commands
+            .iter_mut()
+            .find(|existing| existing.label == command.label)
+        {
+            Some(existing) => *existing = command,
+            None => self.commands.push(command),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DebugCommand> {
+        self.commands.iter()
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub(super) struct PaletteState {
+    open: bool,
+    selected: usize,
+}
+
+#[derive(Component)]
+pub(super) struct CommandPalette;
+
+pub(super) fn toggle_palette(
+    mut actions: MessageReader<DebugAction>,
+    mut palette: ResMut<PaletteState>,
+) {
+    for action in actions.read() {
+        if *action == DebugAction::ToggleCommandPalette {
+            palette.open = !palette.open;
+            palette.selected = 0;
+        }
+    }
+}
+
+/// Arrow keys move the selection, Enter runs the selected command and closes the palette,
+/// Escape just closes it.
+pub(super) fn navigate_palette(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    registry: Res<DebugCommandRegistry>,
+    mut palette: ResMut<PaletteState>,
+) {
+    if !palette.open {
+        return;
+    }
+    let count = registry.commands.len();
+
+    if keys.just_pressed(KeyCode::Escape) {
+        palette.open = false;
+    } else if keys.just_pressed(KeyCode::Enter) {
+        palette.open = false;
+        if let Some(command) = registry.commands.get(palette.selected) {
+            (command.action)(&mut commands);
+        }
+    } else if count > 0 && keys.just_pressed(KeyCode::ArrowDown) {
+        palette.selected = (palette.selected + 1) % count;
+    } else if count > 0 && keys.just_pressed(KeyCode::ArrowUp) {
+        palette.selected = (palette.selected + count - 1) % count;
+    }
+}
+
+pub(super) fn sync_palette(
+    mut commands: Commands,
+    palette: Res<PaletteState>,
+    registry: Res<DebugCommandRegistry>,
+    config: Res<DebugVisConfig>,
+    panels: Query<Entity, With<CommandPalette>>,
+) {
+    if !palette.is_changed() && !registry.is_changed() {
+        return;
+    }
+
+    for panel in panels.iter() {
+        commands.entity(panel).despawn();
+    }
+    if !palette.open {
+        return;
+    }
+
+    commands
+        .spawn((
+            CommandPalette,
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                padding: UiRect::all(Val::Px(PALETTE_PADDING)),
+                flex_direction: FlexDirection::Column,
+                overflow: Overflow::clip(),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            // Above the rest of the overlay, help panel included.
+            GlobalZIndex(config.overlay_z_index.saturating_add(2)),
+        ))
+        .with_children(|panel| {
+            panel.spawn(palette_text(
+                "Commands (Up/Down, Enter to run, Esc to close)".to_string(),
+                DEBUG_TEXT_COLOR,
+            ));
+            for (index, command) in registry.commands.iter().enumerate() {
+                let (text, color) = if index == palette.selected {
+                    (format!("> {}", command.label), PALETTE_SELECTED_COLOR)
+                } else {
+                    (format!("  {}", command.label), DEBUG_TEXT_COLOR)
+                };
+                panel.spawn(palette_text(text, color));
+            }
+        });
+}
+
+fn palette_text(text: String, color: Color) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font_size: 20.0,
+            ..default()
+        },
+        TextColor(color),
+    )
+}
@@ -0,0 +1,193 @@
+//! Click-to-inspect: while picking is on, clicking in the 3D view sets
+//! [`DebugInspectTarget`] to the closest entity whose bounds the cursor ray hits.
+
+use super::{DebugAction, DebugInspectTarget, DebugTextWriter, OverlayCost, OverlayView};
+use bevy::{camera::primitives::Aabb, math::Affine3A, prelude::*};
+
+const PICKED_BOUNDS_COLOR: Color = Color::srgb(1.0, 0.5, 0.0);
+
+#[derive(Resource, Default)]
+pub(super) struct InspectPicking(bool);
+
+pub(super) fn toggle_picking(
+    mut debug_reader: MessageReader<DebugAction>,
+    mut picking: ResMut<InspectPicking>,
+) {
+    for event in debug_reader.read() {
+        if *event == DebugAction::TogglePicking {
+            picking.0 = !picking.0;
+        }
+    }
+}
+
+/// Distance along `ray` to where it enters `aabb` placed by `transform`, or `None` on a miss.
+/// The ray is moved into the box's local space so rotation and non-uniform scale are exact;
+/// the direction isn't renormalized there, which keeps the distance in world units.
+fn ray_aabb_distance(ray: Ray3d, aabb: &Aabb, transform: &GlobalTransform) -> Option<f32> {
+    let to_local = transform.affine().inverse();
+    let origin = Vec3A::from(to_local.transform_point3(ray.origin));
+    let direction = Vec3A::from(to_local.transform_vector3(*ray.direction));
+
+    let inverse_direction = direction.recip();
+    let to_min = (aabb.min() - origin) * inverse_direction;
+    let to_max = (aabb.max() - origin) * inverse_direction;
+    let enter = to_min.min(to_max).max_element().max(0.0);
+    let exit = to_min.max(to_max).min_element();
+
+    (enter <= exit).then_some(enter)
+}
+
+pub(super) fn pick_inspect_target(
+    mut commands: Commands,
+    picking: Res<InspectPicking>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    view: OverlayView,
+    bounds_query: Query<(Entity, &Aabb, &GlobalTransform)>,
+) {
+    if !picking.0 {
+        return;
+    }
+
+    if keys.just_pressed(KeyCode::Escape) {
+        commands.remove_resource::<DebugInspectTarget>();
+        return;
+    }
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+
+    let Some(cursor) = view.window().and_then(Window::cursor_position) else {
+        return;
+    };
+    let Some((_, camera, camera_transform)) = view.camera() else {
+        return;
+    };
+    // Orthographic cameras give a ray from the near plane along the view direction, so the
+    // same test covers both projections.
+    let Ok(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+        return;
+    };
+
+    let closest = bounds_query
+        .iter()
+        .filter_map(|(entity, aabb, transform)| {
+            ray_aabb_distance(ray, aabb, transform).map(|distance| (entity, distance))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    match closest {
+        Some((entity, _)) => commands.insert_resource(DebugInspectTarget(entity)),
+        None => commands.remove_resource::<DebugInspectTarget>(),
+    }
+}
+
+pub(super) fn draw_picked_bounds(
+    picking: Res<InspectPicking>,
+    target: Option<Res<DebugInspectTarget>>,
+    bounds_query: Query<(&Aabb, &GlobalTransform)>,
+    mut gizmos: Gizmos,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    if !picking.0 {
+        return;
+    }
+    writer.write("picking", "Picking: click to inspect, Esc clears");
+
+    let Some((aabb, transform)) = target.and_then(|target| bounds_query.get(target.0).ok()) else {
+        return;
+    };
+    let local = Affine3A::from_scale_rotation_translation(
+        Vec3::from(aabb.half_extents * 2.0),
+        Quat::IDENTITY,
+        Vec3::from(aabb.center),
+    );
+    gizmos.cube(transform.affine() * local, PICKED_BOUNDS_COLOR);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_cube() -> Aabb {
+        Aabb::from_min_max(Vec3::splat(-0.5), Vec3::splat(0.5))
+    }
+
+    fn distance(origin: Vec3, direction: Dir3, transform: Transform) -> Option<f32> {
+        let ray = Ray3d::new(origin, direction);
+        ray_aabb_distance(ray, &unit_cube(), &GlobalTransform::from(transform))
+    }
+
+    fn assert_near(actual: Option<f32>, expected: f32) {
+        let actual = actual.expect("ray should hit");
+        assert!((actual - expected).abs() < 1e-4, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn perspective_ray_hits_front_face() {
+        let at = Transform::from_xyz(0.0, 0.0, -2.0);
+        assert_near(distance(Vec3::ZERO, Dir3::NEG_Z, at), 1.5);
+        let diagonal = Dir3::new(Vec3::new(1.0, 0.0, -4.0)).unwrap();
+        assert_near(distance(Vec3::ZERO, diagonal, at), 1.5 * 17_f32.sqrt() / 4.0);
+
+        assert_eq!(distance(Vec3::ZERO, Dir3::Z, at), None);
+        assert_eq!(distance(Vec3::ZERO, Dir3::X, at), None);
+    }
+
+    #[test]
+    fn ray_from_inside_hits_at_zero() {
+        assert_near(distance(Vec3::splat(0.1), Dir3::X, Transform::IDENTITY), 0.0);
+    }
+
+    /// Orthographic rays are parallel and start on the near plane, which can sit far behind
+    /// the camera.
+    #[test]
+    fn orthographic_rays_hit_within_the_footprint() {
+        let at = Transform::IDENTITY;
+        for x in [-0.45, 0.0, 0.45] {
+            assert_near(distance(Vec3::new(x, 0.2, 1000.0), Dir3::NEG_Z, at), 999.5);
+        }
+        assert_eq!(distance(Vec3::new(0.55, 0.0, 1000.0), Dir3::NEG_Z, at), None);
+        assert_eq!(distance(Vec3::new(0.0, -0.55, 1000.0), Dir3::NEG_Z, at), None);
+    }
+
+    #[test]
+    fn non_uniform_scale_stretches_the_box() {
+        let wide = Transform::from_scale(Vec3::new(4.0, 1.0, 0.5));
+        assert_near(distance(Vec3::new(10.0, 0.0, 0.0), Dir3::NEG_X, wide), 8.0);
+        assert_near(distance(Vec3::new(0.0, 0.0, 10.0), Dir3::NEG_Z, wide), 9.75);
+        assert_near(distance(Vec3::new(1.9, 0.0, 10.0), Dir3::NEG_Z, wide), 9.75);
+        assert_eq!(distance(Vec3::new(2.1, 0.0, 10.0), Dir3::NEG_Z, wide), None);
+        assert_eq!(distance(Vec3::new(0.0, 0.6, 10.0), Dir3::NEG_Z, wide), None);
+    }
+
+    #[test]
+    fn rotation_turns_the_box() {
+        // A quarter turn about Z makes a diamond whose corners reach sqrt(0.5) along x and y.
+        let diamond = Transform::from_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4));
+        let corner = 0.5_f32.sqrt();
+        assert_near(distance(Vec3::new(0.0, 10.0, 0.0), Dir3::NEG_Y, diamond), 10.0 - corner);
+        assert_near(
+            distance(Vec3::new(0.6, 10.0, 0.0), Dir3::NEG_Y, diamond),
+            10.0 - (corner - 0.6),
+        );
+        // Inside the unrotated box's footprint, but past the diamond's edge.
+        assert_eq!(distance(Vec3::new(0.6, 0.6, 10.0), Dir3::NEG_Z, diamond), None);
+    }
+
+    #[test]
+    fn scale_rotation_and_translation_combine() {
+        let transform = Transform {
+            translation: Vec3::new(5.0, 0.0, 0.0),
+            rotation: Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            scale: Vec3::new(4.0, 1.0, 1.0),
+        };
+        // The long local x axis now runs along world z.
+        assert_near(distance(Vec3::new(5.0, 0.0, 10.0), Dir3::NEG_Z, transform), 8.0);
+        assert_near(distance(Vec3::new(0.0, 0.0, 1.5), Dir3::X, transform), 4.5);
+        assert_eq!(distance(Vec3::new(0.0, 0.0, 2.5), Dir3::X, transform), None);
+    }
+}
@@ -0,0 +1,79 @@
+//! Pipeline cache counts for watching shader warm-up. [`PipelineCache`] lives in the render
+//! world, so a render system copies the counts into a handle shared with the main world.
+
+use super::{DebugTextWriter, DebugVisConfig, OverlayCost};
+use bevy::{
+    prelude::*,
+    render::{
+        Render, RenderApp, RenderSystems,
+        render_resource::{CachedPipelineState, PipelineCache},
+    },
+};
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Copy, Default)]
+struct PipelineCounts {
+    queued: usize,
+    ready: usize,
+    failed: usize,
+}
+
+#[derive(Resource, Clone, Default)]
+pub(super) struct SharedPipelineCounts(Arc<Mutex<PipelineCounts>>);
+
+impl SharedPipelineCounts {
+    /// Pipelines compiled and ready, as of the last render frame.
+    pub(super) fn ready(&self) -> usize {
+        self.0.lock().map(|counts| counts.ready).unwrap_or_default()
+    }
+}
+
+pub(super) struct DebugVisPipelinesPlugin;
+
+impl Plugin for DebugVisPipelinesPlugin {
+    fn build(&self, app: &mut App) {
+        let counts = SharedPipelineCounts::default();
+        app.insert_resource(counts.clone())
+            .add_systems(Update, write_pipeline_counts);
+
+        if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
+            render_app
+                .insert_resource(counts)
+                .add_systems(Render, count_pipelines.in_set(RenderSystems::Cleanup));
+        }
+    }
+}
+
+fn count_pipelines(pipeline_cache: Res<PipelineCache>, shared: Res<SharedPipelineCounts>) {
+    let mut counts = PipelineCounts::default();
+    for pipeline in pipeline_cache.pipelines() {
+        match pipeline.state {
+            CachedPipelineState::Queued | CachedPipelineState::Creating(_) => counts.queued += 1,
+            CachedPipelineState::Ok(_) => counts.ready += 1,
+            CachedPipelineState::Err(_) => counts.failed += 1,
+        }
+    }
+    if let Ok(mut shared) = shared.0.lock() {
+        *shared = counts;
+    }
+}
+
+fn write_pipeline_counts(
+    config: Res<DebugVisConfig>,
+    shared: Res<SharedPipelineCounts>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    if !config.show_pipeline_cache {
+        return;
+    }
+    let Ok(counts) = shared.0.lock().map(|counts| *counts) else {
+        return;
+    };
+
+    writer.write("pipelines_queued", format!("Pipelines queued: {}", counts.queued));
+    writer.write("pipelines_ready", format!("Pipelines ready: {}", counts.ready));
+    writer.write("pipelines_failed", format!("Pipelines failed: {}", counts.failed));
+}
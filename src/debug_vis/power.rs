@@ -0,0 +1,95 @@
+//! Battery readout for laptop and handheld runs, where power and thermal throttling are the
+//! usual reason a run that was smooth yesterday isn't today.
+
+use super::{DebugTextWriter, FpsWindow, FrameTimeHistory, OverlayCost};
+use bevy::prelude::*;
+use std::{fs, path::Path, time::Duration};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Last battery sample. Absent on machines without a battery.
+#[derive(Resource, Debug, Clone, Copy, PartialEq)]
+pub struct PowerState {
+    /// Charge across all batteries, 0–100.
+    pub percent: f32,
+    pub charging: bool,
+}
+
+pub(super) struct DebugVisPowerPlugin;
+
+impl Plugin for DebugVisPowerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, sample_power_state);
+    }
+}
+
+const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// Reads every `type == Battery` supply under [`POWER_SUPPLY_DIR`]. Supplies without a
+/// `capacity` file (some peripherals report only a level string) are skipped.
+fn read_power_state() -> Option<PowerState> {
+    let read = |path: &Path, name: &str| {
+        fs::read_to_string(path.join(name)).map(|value| value.trim().to_string())
+    };
+
+    let mut percents = Vec::new();
+    let mut charging = false;
+    for entry in fs::read_dir(POWER_SUPPLY_DIR).ok()?.filter_map(Result::ok) {
+        let path = entry.path();
+        if read(&path, "type").ok().as_deref() != Some("Battery") {
+            continue;
+        }
+        let Some(percent) = read(&path, "capacity").ok().and_then(|c| c.parse::<f32>().ok())
+        else {
+            continue;
+        };
+        percents.push(percent);
+        charging |= matches!(read(&path, "status").ok().as_deref(), Some("Charging" | "Full"));
+    }
+    if percents.is_empty() {
+        return None;
+    }
+
+    let percent = percents.iter().sum::<f32>() / percents.len() as f32;
+    Some(PowerState { percent, charging })
+}
+
+fn sample_power_state(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    current: Option<Res<PowerState>>,
+    mut last_sample: Local<Option<Duration>>,
+    mut history: ResMut<FrameTimeHistory>,
+    mut fps_window: ResMut<FpsWindow>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let now = time.elapsed();
+    if last_sample.is_some_and(|last| now.saturating_sub(last) < SAMPLE_INTERVAL) {
+        return;
+    }
+    *last_sample = Some(now);
+
+    let Some(state) = read_power_state() else {
+        return;
+    };
+
+    // Plugging in or unplugging changes clocks; don't mix the two segments in one window.
+    if current.is_some_and(|current| current.charging != state.charging) {
+        history.clear();
+        fps_window.reset();
+    }
+
+    writer.write_with_persistence(
+        "power_state",
+        format!(
+            "Battery: {:.0}% ({})",
+            state.percent,
+            if state.charging { "charging" } else { "on battery" }
+        ),
+        true,
+    );
+    commands.insert_resource(state);
+}
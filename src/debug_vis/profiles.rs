@@ -0,0 +1,130 @@
+//! Named overlay arrangements for different tasks, switched as a whole at runtime.
+
+use super::{ChartSeries, DebugAction, DebugLevel, DebugTextWriter, DebugVisConfig, DebugWidgets};
+use bevy::prelude::*;
+
+/// A named bundle of overlay settings, applied together by [`DebugProfiles::activate`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DebugProfile {
+    pub name: String,
+    pub level: DebugLevel,
+    pub widgets: DebugWidgets,
+    pub chart_series: ChartSeries,
+    pub show_archetype_count: bool,
+}
+
+/// Registered profiles and the active one. Ships with "perf triage", "content review" and
+/// "soak"; the cycle-profile key steps through them in registration order.
+#[derive(Resource, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DebugProfiles {
+    profiles: Vec<DebugProfile>,
+    active: Option<usize>,
+}
+
+impl Default for DebugProfiles {
+    fn default() -> Self {
+        Self {
+            profiles: vec![
+                DebugProfile {
+                    name: "perf triage".to_string(),
+                    level: DebugLevel::Full,
+                    widgets: DebugWidgets::default(),
+                    chart_series: ChartSeries::Both,
+                    show_archetype_count: false,
+                },
+                DebugProfile {
+                    name: "content review".to_string(),
+                    level: DebugLevel::FpsOnly,
+                    widgets: DebugWidgets::FPS_TEXT.union(DebugWidgets::DEBUG_LINES),
+                    chart_series: ChartSeries::Raw,
+                    show_archetype_count: true,
+                },
+                DebugProfile {
+                    name: "soak".to_string(),
+                    level: DebugLevel::Full,
+                    widgets: DebugWidgets::FPS_TEXT
+                        .union(DebugWidgets::CONSISTENCY_TEXT)
+                        .union(DebugWidgets::DEBUG_LINES),
+                    chart_series: ChartSeries::Smoothed,
+                    show_archetype_count: true,
+                },
+            ],
+            active: None,
+        }
+    }
+}
+
+impl DebugProfiles {
+    /// Adds `profile`, replacing any profile with the same name.
+    pub fn register(&mut self, profile: DebugProfile) {
+        match self.profiles.iter_mut().find(|existing| existing.name == profile.name) {
+            Some(existing) => *existing = profile,
+            None => self.profiles.push(profile),
+        }
+    }
+
+    /// Switches to the profile called `name`. Returns `false` if there is none.
+    pub fn activate(&mut self, name: &str) -> bool {
+        let Some(index) = self.profiles.iter().position(|profile| profile.name == name) else {
+            return false;
+        };
+        self.active = Some(index);
+        true
+    }
+
+    pub fn active(&self) -> Option<&DebugProfile> {
+        self.profiles.get(self.active?)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &DebugProfile> {
+        self.profiles.iter()
+    }
+
+    fn cycle(&mut self) {
+        if self.profiles.is_empty() {
+            return;
+        }
+        self.active = Some(self.active.map_or(0, |index| (index + 1) % self.profiles.len()));
+    }
+}
+
+pub(super) fn cycle_profile(
+    mut debug_reader: MessageReader<DebugAction>,
+    mut profiles: ResMut<DebugProfiles>,
+) {
+    for event in debug_reader.read() {
+        if *event == DebugAction::CycleProfile {
+            profiles.cycle();
+        }
+    }
+}
+
+pub(super) fn apply_active_profile(
+    profiles: Res<DebugProfiles>,
+    mut applied: Local<Option<DebugProfile>>,
+    mut level: ResMut<DebugLevel>,
+    mut widgets: ResMut<DebugWidgets>,
+    mut config: ResMut<DebugVisConfig>,
+) {
+    let Some(profile) = profiles.active() else {
+        return;
+    };
+    // Only on a switch, so manual tweaks made afterwards stick.
+    if applied.as_ref() == Some(profile) {
+        return;
+    }
+
+    level.set_if_neq(profile.level);
+    widgets.set_if_neq(profile.widgets);
+    config.chart_series = profile.chart_series;
+    config.show_archetype_count = profile.show_archetype_count;
+    *applied = Some(profile.clone());
+}
+
+pub(super) fn write_active_profile(profiles: Res<DebugProfiles>, mut writer: DebugTextWriter) {
+    if let Some(profile) = profiles.active() {
+        writer.write("profile", format!("Profile: {}", profile.name));
+    }
+}
@@ -0,0 +1,84 @@
+//! Query iteration counting. Bevy doesn't count query iterations, so systems that want to be
+//! counted swap their `Query` for a [`DebugQuery`], which counts every `iter`/`iter_mut`.
+
+use super::{DebugTextWriter, DebugVisConfig, OverlayCost};
+use bevy::{
+    ecs::{
+        query::{QueryData, QueryFilter, QueryIter},
+        system::SystemParam,
+    },
+    prelude::*,
+};
+use std::{
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// Iterations started through [`DebugQuery`]s, counted across systems running in parallel.
+#[derive(Resource, Default, Debug)]
+pub struct QueryStats {
+    this_frame: AtomicU64,
+    last_frame: u64,
+}
+
+impl QueryStats {
+    /// Iterations started during the previous frame.
+    pub fn last_frame(&self) -> u64 {
+        self.last_frame
+    }
+}
+
+/// A [`Query`] that counts its iterations into [`QueryStats`], for
+/// [`DebugVisConfig::show_query_stats`]. Derefs to the query for everything else.
+#[derive(SystemParam)]
+pub struct DebugQuery<'w, 's, D: QueryData + 'static, F: QueryFilter + 'static = ()> {
+    query: Query<'w, 's, D, F>,
+    stats: Res<'w, QueryStats>,
+}
+
+impl<'w, 's, D: QueryData, F: QueryFilter> DebugQuery<'w, 's, D, F> {
+    pub fn iter(&self) -> QueryIter<'_, 's, D::ReadOnly, F> {
+        self.stats.this_frame.fetch_add(1, Ordering::Relaxed);
+        self.query.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> QueryIter<'_, 's, D, F> {
+        self.stats.this_frame.fetch_add(1, Ordering::Relaxed);
+        self.query.iter_mut()
+    }
+}
+
+impl<'w, 's, D: QueryData, F: QueryFilter> Deref for DebugQuery<'w, 's, D, F> {
+    type Target = Query<'w, 's, D, F>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.query
+    }
+}
+
+impl<D: QueryData, F: QueryFilter> DerefMut for DebugQuery<'_, '_, D, F> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.query
+    }
+}
+
+/// Closes the frame's count and writes it. Runs in `Last`, after every `Update` system.
+pub(super) fn write_query_stats(
+    config: Res<DebugVisConfig>,
+    mut stats: ResMut<QueryStats>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let stats = &mut *stats;
+    stats.last_frame = std::mem::take(stats.this_frame.get_mut());
+
+    if !config.show_query_stats {
+        return;
+    }
+    writer.write(
+        "query_stats",
+        format!("Query iters: {}/frame", stats.last_frame),
+    );
+}
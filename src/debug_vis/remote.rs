@@ -0,0 +1,474 @@
+//! Overlay data for watching a fullscreen test machine from another one, with the `remote`
+//! feature and [`DebugVisConfig::remote_enabled`]. A small HTTP server on its own thread
+//! answers from a snapshot that a system refreshes once a frame, so a request never waits on
+//! the main thread:
+//!
+//! - `GET /stats`: frame statistics, [`SessionStats`], the active scenario and every debug
+//!   line, as JSON.
+//! - `GET /history`: the raw frame time window and the window events over it.
+//! - `POST /command` with `{ "command": <label> }`: runs that command palette entry on the
+//!   next frame, e.g. `"Scenario: lights"` to switch scenarios.
+//!
+//! Apps that add Bevy's `RemotePlugin` themselves also get the same data as BRP methods.
+
+use super::{
+    ActiveScenario, DebugCommandRegistry, DebugLevel, DebugTexts, DebugVisConfig, FpsWindow,
+    FrameStatsSnapshot, FrameTimeHistory, SessionStats, TimelineEvents,
+};
+use bevy::{
+    prelude::*,
+    remote::{BrpError, BrpResult, RemoteMethodSystemId, RemoteMethods, RemotePlugin, error_codes},
+};
+use serde_json::{Value, json};
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+pub const SET_LEVEL_METHOD: &str = "debug_vis/set_level";
+pub const GET_STATS_METHOD: &str = "debug_vis/get_stats";
+pub const GET_HISTORY_METHOD: &str = "debug_vis/get_history";
+pub const RUN_COMMAND_METHOD: &str = "debug_vis/run_command";
+
+/// Limit on reading a request and writing its response, so a stalled client can't hold up
+/// the ones behind it.
+const REMOTE_IO_TIMEOUT: Duration = Duration::from_secs(2);
+/// Largest request body accepted, in bytes.
+const MAX_REQUEST_BODY: usize = 64 * 1024;
+
+pub(super) struct DebugVisRemotePlugin;
+
+impl Plugin for DebugVisRemotePlugin {
+    fn build(&self, app: &mut App) {
+        let config = app.world().resource::<DebugVisConfig>();
+        if !config.remote_enabled {
+            return;
+        }
+        let listener = match TcpListener::bind(("127.0.0.1", config.remote_port)) {
+            Ok(listener) => listener,
+            Err(err) => {
+                warn!("debug_vis: can't serve on port {}: {err}", config.remote_port);
+                return;
+            }
+        };
+        let shared = RemoteShared::default();
+        let server = shared.clone();
+        let address = listener.local_addr().ok();
+        let spawned = std::thread::Builder::new()
+            .name("debug_vis remote".to_string())
+            .spawn(move || serve(listener, &server));
+        if let Err(err) = spawned {
+            warn!("debug_vis: can't start the remote server: {err}");
+            return;
+        }
+        if let Some(address) = address {
+            info!("debug_vis: serving overlay data on http://{address}");
+            app.insert_resource(RemoteAddress(address));
+        }
+
+        app.insert_resource(RemoteServer(shared))
+            .add_systems(Update, run_remote_commands)
+            .add_systems(Last, update_remote_snapshot.after(super::fit_debug_lines));
+    }
+
+    fn finish(&self, app: &mut App) {
+        if !app.is_plugin_added::<RemotePlugin>() {
+            return;
+        }
+        let world = app.world_mut();
+        let set_level = world.register_system(set_level);
+        let get_stats = world.register_system(get_stats);
+        let get_history = world.register_system(get_history);
+        let run_command = world.register_system(run_command);
+
+        let mut methods = world.resource_mut::<RemoteMethods>();
+        methods.insert(SET_LEVEL_METHOD, RemoteMethodSystemId::Instant(set_level));
+        methods.insert(GET_STATS_METHOD, RemoteMethodSystemId::Instant(get_stats));
+        methods.insert(GET_HISTORY_METHOD, RemoteMethodSystemId::Instant(get_history));
+        methods.insert(RUN_COMMAND_METHOD, RemoteMethodSystemId::Instant(run_command));
+    }
+}
+
+/// What the server answers from, copied out of the world once a frame.
+#[derive(Default)]
+struct RemoteSnapshot {
+    fps: Option<f64>,
+    stats: FrameStatsSnapshot,
+    session: SessionStats,
+    scenario: Option<String>,
+    /// Debug lines as `(key, text)`, in line order.
+    lines: Vec<(String, String)>,
+    /// Command palette labels, so unknown commands are turned away right away.
+    commands: Vec<String>,
+    frames: Vec<u32>,
+    frame_times_ms: Vec<f64>,
+    /// Window events over the history, as `(frame, time_s, event)`.
+    events: Vec<(u32, f64, String)>,
+}
+
+/// State shared with the server thread.
+#[derive(Clone, Default)]
+struct RemoteShared {
+    snapshot: Arc<Mutex<RemoteSnapshot>>,
+    /// Command labels posted since the last frame.
+    commands: Arc<Mutex<Vec<String>>>,
+}
+
+#[derive(Resource)]
+pub(super) struct RemoteServer(RemoteShared);
+
+/// Where the server started by [`DebugVisConfig::remote_enabled`] listens, e.g. to find the
+/// port the OS picked for a `remote_port` of 0.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RemoteAddress(pub SocketAddr);
+
+fn update_remote_snapshot(
+    server: Res<RemoteServer>,
+    fps_window: Res<FpsWindow>,
+    history: Res<FrameTimeHistory>,
+    session: Res<SessionStats>,
+    scenario: Res<ActiveScenario>,
+    texts: Res<DebugTexts>,
+    registry: Res<DebugCommandRegistry>,
+    timeline: Res<TimelineEvents>,
+) {
+    let Ok(mut snapshot) = server.0.snapshot.lock() else {
+        return;
+    };
+    snapshot.fps = fps_window.fps();
+    snapshot.stats = history.snapshot();
+    snapshot.session = *session;
+    snapshot.scenario = scenario.name().map(str::to_string);
+    snapshot.lines = ordered_lines(&texts);
+    snapshot.commands = registry.iter().map(|command| command.label.clone()).collect();
+    copy_history(&history, &timeline, &mut snapshot);
+}
+
+/// Copies the frame time window and its events into `snapshot`, reusing its buffers.
+fn copy_history(
+    history: &FrameTimeHistory,
+    timeline: &TimelineEvents,
+    snapshot: &mut RemoteSnapshot,
+) {
+    snapshot.frames.clear();
+    snapshot.frame_times_ms.clear();
+    for (frame, frame_time_ms) in history.iter_with_frames() {
+        snapshot.frames.push(frame);
+        snapshot.frame_times_ms.push(frame_time_ms);
+    }
+    snapshot.events = history
+        .frame_range()
+        .into_iter()
+        .flat_map(|(first, last)| timeline.in_frames(first, last))
+        .map(|event| (event.frame, event.time.as_secs_f64(), event.kind.to_string()))
+        .collect();
+}
+
+/// Runs the commands posted to `/command` since the last frame.
+fn run_remote_commands(
+    server: Res<RemoteServer>,
+    registry: Res<DebugCommandRegistry>,
+    mut commands: Commands,
+) {
+    let posted = match server.0.commands.lock() {
+        Ok(mut posted) => std::mem::take(&mut *posted),
+        Err(_) => return,
+    };
+    for label in posted {
+        if let Some(command) = registry.iter().find(|command| command.label == label) {
+            (command.action)(&mut commands);
+        }
+    }
+}
+
+/// Answers one connection at a time until the app exits.
+fn serve(listener: TcpListener, shared: &RemoteShared) {
+    for stream in listener.incoming() {
+        let result = stream.and_then(|stream| answer(stream, shared));
+        if let Err(err) = result {
+            debug!("debug_vis: remote request failed: {err}");
+        }
+    }
+}
+
+fn answer(stream: TcpStream, shared: &RemoteShared) -> io::Result<()> {
+    stream.set_read_timeout(Some(REMOTE_IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(REMOTE_IO_TIMEOUT))?;
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            content_length = value.trim().parse().unwrap_or(0);
+        }
+    }
+    let mut body = vec![0; content_length.min(MAX_REQUEST_BODY)];
+    reader.read_exact(&mut body)?;
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (status, json) = route(method, path, &body, shared);
+    let body = json.to_string();
+    write!(
+        reader.get_mut(),
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+fn error(message: impl Into<String>) -> Value {
+    json!({ "error": message.into() })
+}
+
+/// The status line and JSON body answering `method path`.
+fn route(method: &str, path: &str, body: &[u8], shared: &RemoteShared) -> (&'static str, Value) {
+    let Ok(snapshot) = shared.snapshot.lock() else {
+        return ("500 Internal Server Error", error("snapshot unavailable"));
+    };
+    match (method, path) {
+        ("GET", "/stats") => ("200 OK", stats_json(&snapshot)),
+        ("GET", "/history") => ("200 OK", history_json(&snapshot)),
+        ("POST", "/command") => {
+            let label = serde_json::from_slice::<Value>(body)
+                .ok()
+                .and_then(|params| Some(params.get("command")?.as_str()?.to_string()));
+            let Some(label) = label else {
+                return (
+                    "400 Bad Request",
+                    error("expected body { \"command\": <string> }"),
+                );
+            };
+            if !snapshot.commands.contains(&label) {
+                return ("404 Not Found", error(format!("unknown command `{label}`")));
+            }
+            if let Ok(mut commands) = shared.commands.lock() {
+                commands.push(label);
+            }
+            ("202 Accepted", Value::Null)
+        }
+        (_, "/stats" | "/history" | "/command") => {
+            ("405 Method Not Allowed", error(format!("{method} not allowed on {path}")))
+        }
+        _ => ("404 Not Found", error(format!("no endpoint at {path}"))),
+    }
+}
+
+fn stats_json(snapshot: &RemoteSnapshot) -> Value {
+    let stats = &snapshot.stats;
+    let lines: Vec<Value> = snapshot
+        .lines
+        .iter()
+        .map(|(key, text)| json!({ "key": key, "text": text }))
+        .collect();
+    json!({
+        "fps": snapshot.fps,
+        "avg_ms": stats.avg_ms,
+        "max_ms": stats.max_ms,
+        "p50_ms": stats.p50_ms,
+        "p95_ms": stats.p95_ms,
+        "p99_ms": stats.p99_ms,
+        "fps_1s": stats.fps_1s,
+        "sample_count": stats.sample_count,
+        "session": {
+            "frames": snapshot.session.frames,
+            "avg_ms": snapshot.session.avg_ms(),
+            "max_ms": snapshot.session.max_ms,
+            "spikes": snapshot.session.spikes,
+        },
+        "scenario": snapshot.scenario,
+        "line_count": lines.len(),
+        "lines": lines,
+    })
+}
+
+/// The raw frame time window, oldest first, as parallel `frames` and `frame_times_ms` arrays,
+/// plus the window events recorded over it.
+fn history_json(snapshot: &RemoteSnapshot) -> Value {
+    let events: Vec<Value> = snapshot
+        .events
+        .iter()
+        .map(|(frame, time_s, event)| json!({ "frame": frame, "time_s": time_s, "event": event }))
+        .collect();
+    json!({
+        "frames": snapshot.frames,
+        "frame_times_ms": snapshot.frame_times_ms,
+        "events": events,
+    })
+}
+
+/// Every debug line as `(key, text)`, in line order. While the overlay is hidden, lines show
+/// the writes held back until it's visible again; lines first written while hidden come last,
+/// by key.
+fn ordered_lines(texts: &DebugTexts) -> Vec<(String, String)> {
+    let mut shown: Vec<_> = texts.entries.iter().collect();
+    shown.sort_by_key(|(_, entry)| entry.line);
+    let mut deferred_only: Vec<_> = texts
+        .deferred
+        .iter()
+        .filter(|(key, _)| !texts.entries.contains_key(*key))
+        .collect();
+    deferred_only.sort_by_key(|(key, _)| *key);
+
+    let shown = shown.into_iter().map(|(key, entry)| {
+        let text = match texts.deferred.get(key) {
+            Some(write) => write.text(),
+            None => entry.text(),
+        };
+        (key.clone(), text)
+    });
+    let deferred_only = deferred_only
+        .into_iter()
+        .map(|(key, write)| (key.clone(), write.text()));
+    shown.chain(deferred_only).collect()
+}
+
+/// JSON-RPC's "Invalid params", for requests that are missing a parameter or name something
+/// that doesn't exist.
+fn invalid_params(message: impl Into<String>) -> BrpError {
+    BrpError {
+        code: error_codes::INVALID_PARAMS,
+        message: message.into(),
+        data: None,
+    }
+}
+
+/// Accepts `{ "level": "full" | "fps_only" | "hidden" }`.
+fn set_level(In(params): In<Option<Value>>, mut level: ResMut<DebugLevel>) -> BrpResult {
+    let name = params
+        .as_ref()
+        .and_then(|params| params.get("level"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_params("expected params { \"level\": <string> }"))?;
+
+    *level = match name {
+        "full" => DebugLevel::Full,
+        "fps_only" => DebugLevel::FpsOnly,
+        "hidden" => DebugLevel::Hidden,
+        other => return Err(invalid_params(format!("unknown debug level `{other}`"))),
+    };
+
+    Ok(Value::Null)
+}
+
+/// The same JSON as `GET /stats`.
+fn get_stats(
+    In(_): In<Option<Value>>,
+    fps_window: Res<FpsWindow>,
+    history: Res<FrameTimeHistory>,
+    session: Res<SessionStats>,
+    scenario: Res<ActiveScenario>,
+    texts: Res<DebugTexts>,
+) -> BrpResult {
+    Ok(stats_json(&RemoteSnapshot {
+        fps: fps_window.fps(),
+        stats: history.snapshot(),
+        session: *session,
+        scenario: scenario.name().map(str::to_string),
+        lines: ordered_lines(&texts),
+        ..default()
+    }))
+}
+
+/// The same JSON as `GET /history`.
+fn get_history(
+    In(_): In<Option<Value>>,
+    history: Res<FrameTimeHistory>,
+    timeline: Res<TimelineEvents>,
+) -> BrpResult {
+    let mut snapshot = RemoteSnapshot::default();
+    copy_history(&history, &timeline, &mut snapshot);
+    Ok(history_json(&snapshot))
+}
+
+/// Accepts `{ "command": <label> }` and runs the command palette entry with that label.
+fn run_command(
+    In(params): In<Option<Value>>,
+    registry: Res<DebugCommandRegistry>,
+    mut commands: Commands,
+) -> BrpResult {
+    let label = params
+        .as_ref()
+        .and_then(|params| params.get("command"))
+        .and_then(Value::as_str)
+        .ok_or_else(|| invalid_params("expected params { \"command\": <string> }"))?;
+    let command = registry
+        .iter()
+        .find(|command| command.label == label)
+        .ok_or_else(|| invalid_params(format!("unknown command `{label}`")))?;
+
+    (command.action)(&mut commands);
+    Ok(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_checked_before_queueing() {
+        let shared = RemoteShared::default();
+        shared.snapshot.lock().unwrap().commands = vec!["Scenario: lights".to_string()];
+
+        let body = br#"{ "command": "Scenario: lights" }"#;
+        assert_eq!(route("POST", "/command", body, &shared).0, "202 Accepted");
+        let body = br#"{ "command": "Scenario: fog" }"#;
+        assert_eq!(route("POST", "/command", body, &shared).0, "404 Not Found");
+        assert_eq!(route("POST", "/command", b"lights", &shared).0, "400 Bad Request");
+        assert_eq!(route("GET", "/command", b"", &shared).0, "405 Method Not Allowed");
+        assert_eq!(route("GET", "/", b"", &shared).0, "404 Not Found");
+        assert_eq!(*shared.commands.lock().unwrap(), ["Scenario: lights"]);
+    }
+
+    #[test]
+    #[cfg(feature = "debug-vis")]
+    fn serves_stats_and_runs_commands() {
+        use crate::debug_vis::{DebugVisPlugin, ScenarioAppExt, tests::headless_plugins};
+
+        let mut app = App::new();
+        app.insert_resource(DebugVisConfig {
+            remote_enabled: true,
+            remote_port: 0,
+            ..default()
+        })
+        .add_plugins((headless_plugins(), DebugVisPlugin::default()))
+        .register_scenario("lights", |_: &mut World| Ok(()));
+        app.update();
+        let address = app.world().resource::<RemoteAddress>().0;
+
+        let request = |request: String| {
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(request.as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let command = r#"{ "command": "Scenario: lights" }"#;
+        let response = request(format!(
+            "POST /command HTTP/1.1\r\nContent-Length: {}\r\n\r\n{command}",
+            command.len()
+        ));
+        assert!(response.starts_with("HTTP/1.1 202 Accepted\r\n"), "{response}");
+        app.update();
+        app.update();
+
+        let response = request("GET /stats HTTP/1.1\r\n\r\n".to_string());
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"), "{head}");
+        let stats: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(stats["scenario"], "lights");
+        assert!(stats["session"]["frames"].as_u64().unwrap() > 0, "{stats}");
+        let lines = stats["lines"].as_array().unwrap();
+        assert!(lines.contains(&json!({ "key": "scenario", "text": "Scenario: lights" })));
+    }
+}
@@ -0,0 +1,394 @@
+//! Replay of a recorded frame time trace, for triaging captures from other machines. The
+//! samples go through [`ManualFrameTimes`], so the chart, statistics and spike detection see
+//! them exactly as they would live frames; live recording is off for the whole run.
+
+use super::{
+    DebugAction, DebugKeybindings, DebugTextWriter, DebugVisConfig, FRAME_DELTA_WINDOW,
+    FrameTimeSource, ManualFrameTimes, OverlayCost,
+};
+use bevy::prelude::*;
+use std::{io, path::Path};
+
+/// Most recorded time one frame of playback can owe, in seconds of wall time, so a stall
+/// (a window drag, a breakpoint) doesn't dump thousands of samples at once.
+const MAX_CATCH_UP_SECONDS: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReplaySpeed {
+    Paused,
+    Normal,
+    Fast,
+}
+
+impl ReplaySpeed {
+    /// Recorded ms played per ms of wall time.
+    fn rate(self) -> f64 {
+        match self {
+            Self::Paused => 0.0,
+            Self::Normal => 1.0,
+            Self::Fast => 10.0,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::Paused => "paused",
+            Self::Normal => "1×",
+            Self::Fast => "10×",
+        }
+    }
+}
+
+/// The trace being played and how far along it is.
+#[derive(Resource, Debug)]
+pub(super) struct Replay {
+    /// File name, for the overlay line.
+    name: String,
+    frame_times_ms: Vec<f64>,
+    /// Samples already handed to [`ManualFrameTimes`].
+    cursor: usize,
+    speed: ReplaySpeed,
+    /// Recorded time owed to playback, in ms.
+    owed_ms: f64,
+}
+
+impl Replay {
+    fn new(name: String, frame_times_ms: Vec<f64>) -> Self {
+        Self {
+            name,
+            frame_times_ms,
+            cursor: 0,
+            speed: ReplaySpeed::Normal,
+            owed_ms: 0.0,
+        }
+    }
+
+    /// Samples due after `elapsed_ms` of wall time: each plays for as long as it took when
+    /// recorded, scaled by the speed.
+    fn advance(&mut self, elapsed_ms: f64) -> &[f64] {
+        let rate = self.speed.rate();
+        self.owed_ms = (self.owed_ms + elapsed_ms * rate).min(MAX_CATCH_UP_SECONDS * 1000.0 * rate);
+        let start = self.cursor;
+        while let Some(&frame_time_ms) = self.frame_times_ms.get(self.cursor)
+            && frame_time_ms <= self.owed_ms
+        {
+            self.owed_ms -= frame_time_ms;
+            self.cursor += 1;
+        }
+        &self.frame_times_ms[start..self.cursor]
+    }
+
+    /// Every sample up to and including the next spike, pausing on it. Nothing when no
+    /// spike is left.
+    fn skip_to_next_spike(&mut self, k_factor: f64) -> &[f64] {
+        let Some(spike) = next_spike(&self.frame_times_ms, self.cursor, k_factor) else {
+            return &[];
+        };
+        let start = self.cursor;
+        self.cursor = spike + 1;
+        self.speed = ReplaySpeed::Paused;
+        self.owed_ms = 0.0;
+        &self.frame_times_ms[start..self.cursor]
+    }
+}
+
+/// Index of the first sample from `from` on that [`FrameSpike`](super::FrameSpike) detection
+/// would flag: more than `k_factor` standard deviations above the average of the window
+/// ending at it.
+fn next_spike(frame_times_ms: &[f64], from: usize, k_factor: f64) -> Option<usize> {
+    let (mut sum, mut sum_sq) = (0.0, 0.0);
+    for &frame_time_ms in &frame_times_ms[from.saturating_sub(FRAME_DELTA_WINDOW - 1)..from] {
+        sum += frame_time_ms;
+        sum_sq += frame_time_ms * frame_time_ms;
+    }
+    for (index, &frame_time_ms) in frame_times_ms.iter().enumerate().skip(from) {
+        sum += frame_time_ms;
+        sum_sq += frame_time_ms * frame_time_ms;
+        if let Some(&dropped) = index.checked_sub(FRAME_DELTA_WINDOW).map(|i| &frame_times_ms[i]) {
+            sum -= dropped;
+            sum_sq -= dropped * dropped;
+        }
+        let count = (index + 1).min(FRAME_DELTA_WINDOW) as f64;
+        let avg = sum / count;
+        let std_dev = (sum_sq / count - avg * avg).max(0.0).sqrt();
+        if frame_time_ms > avg + k_factor * std_dev {
+            return Some(index);
+        }
+    }
+    None
+}
+
+/// Frame times from a trace file: CSV with a `frame_time_ms` column, as written by soak
+/// exports and crash dumps, or, with the `serde` feature, JSON with a `frame_times_ms` array,
+/// as in stability reports and `debug_vis/get_history` responses.
+pub(super) fn load_trace(path: &Path) -> io::Result<Vec<f64>> {
+    let text = std::fs::read_to_string(path)?;
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+    let frame_times_ms = if is_json { parse_json(&text)? } else { parse_csv(&text)? };
+    if frame_times_ms.is_empty() {
+        return Err(invalid_data("no frame times in trace".to_string()));
+    }
+    Ok(frame_times_ms)
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Skips blank lines and `#` comments; the first remaining line is the header.
+fn parse_csv(text: &str) -> io::Result<Vec<f64>> {
+    let mut rows = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+    let column = rows
+        .next()
+        .and_then(|(_, header)| header.split(',').position(|name| name.trim() == "frame_time_ms"))
+        .ok_or_else(|| invalid_data("CSV header has no frame_time_ms column".to_string()))?;
+
+    rows.map(|(index, row)| {
+        row.split(',')
+            .nth(column)
+            .and_then(|cell| cell.trim().parse::<f64>().ok())
+            .filter(|frame_time_ms| frame_time_ms.is_finite() && *frame_time_ms >= 0.0)
+            .ok_or_else(|| invalid_data(format!("line {}: no frame time", index + 1)))
+    })
+    .collect()
+}
+
+#[cfg(feature = "serde")]
+fn parse_json(text: &str) -> io::Result<Vec<f64>> {
+    let value: serde_json::Value = serde_json::from_str(text)?;
+    let samples = value
+        .get("frame_times_ms")
+        .and_then(serde_json::Value::as_array)
+        .ok_or_else(|| invalid_data("JSON has no frame_times_ms array".to_string()))?;
+    samples
+        .iter()
+        .map(|sample| {
+            sample
+                .as_f64()
+                .ok_or_else(|| invalid_data(format!("frame time {sample} is not a number")))
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "serde"))]
+fn parse_json(_text: &str) -> io::Result<Vec<f64>> {
+    Err(invalid_data("JSON traces need the `serde` feature".to_string()))
+}
+
+pub(super) struct DebugVisReplayPlugin(pub(super) std::path::PathBuf);
+
+impl Plugin for DebugVisReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let path = &self.0;
+        let frame_times_ms = match load_trace(path) {
+            Ok(frame_times_ms) => frame_times_ms,
+            Err(err) => {
+                warn!("debug_vis: can't replay {}: {err}", path.display());
+                return;
+            }
+        };
+        let name = path
+            .file_name()
+            .map_or_else(|| path.display().to_string(), |name| name.display().to_string());
+
+        app.world_mut()
+            .resource_mut::<DebugVisConfig>()
+            .frame_time_source = FrameTimeSource::Manual;
+        app.insert_resource(Replay::new(name, frame_times_ms))
+            .add_systems(
+                Update,
+                (
+                    control_replay.after(super::keybindings::read_debug_keys),
+                    play_replay
+                        .after(control_replay)
+                        .before(super::update_frame_time_history),
+                    write_replay_status.after(play_replay),
+                ),
+            );
+    }
+}
+
+fn control_replay(
+    mut actions: MessageReader<DebugAction>,
+    mut replay: ResMut<Replay>,
+    mut manual: ResMut<ManualFrameTimes>,
+    config: Res<DebugVisConfig>,
+) {
+    for action in actions.read() {
+        match action {
+            DebugAction::ReplayTogglePause => {
+                replay.speed = match replay.speed {
+                    ReplaySpeed::Paused => ReplaySpeed::Normal,
+                    _ => ReplaySpeed::Paused,
+                };
+            }
+            DebugAction::ReplayNormalSpeed => replay.speed = ReplaySpeed::Normal,
+            DebugAction::ReplayFastSpeed => replay.speed = ReplaySpeed::Fast,
+            DebugAction::ReplayNextSpike => {
+                let samples = replay.skip_to_next_spike(config.spike_k_factor);
+                manual.0.extend(samples);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn play_replay(
+    time: Res<Time<Real>>,
+    mut replay: ResMut<Replay>,
+    mut manual: ResMut<ManualFrameTimes>,
+    mut config: ResMut<DebugVisConfig>,
+) {
+    // Settings panels and profiles can switch the source back; the trace has to stay the
+    // only input.
+    if config.frame_time_source != FrameTimeSource::Manual {
+        config.frame_time_source = FrameTimeSource::Manual;
+    }
+    let samples = replay.advance(time.delta_secs_f64() * 1000.0);
+    manual.0.extend(samples);
+}
+
+fn write_replay_status(
+    replay: Res<Replay>,
+    bindings: Res<DebugKeybindings>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    writer.write(
+        "replay",
+        format!(
+            "REPLAY: {} (frame {}/{})",
+            replay.name,
+            replay.cursor,
+            replay.frame_times_ms.len()
+        ),
+    );
+    let keys: Vec<String> = [
+        (DebugAction::ReplayTogglePause, "pause"),
+        (DebugAction::ReplayNormalSpeed, "1×"),
+        (DebugAction::ReplayFastSpeed, "10×"),
+        (DebugAction::ReplayNextSpike, "next spike"),
+    ]
+    .into_iter()
+    .filter_map(|(action, name)| Some(format!("{} {name}", bindings.get(action)?)))
+    .collect();
+    writer.write(
+        "replay_speed",
+        format!("Replay speed: {} ({})", replay.speed.label(), keys.join(", ")),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_reads_the_frame_time_column() {
+        let soak_export = "# frame: 1200\n# uptime_s: 20\n# avg_ms: 22.133\n# fps_1s: --\n\
+            frame,frame_time_ms,smoothed_ms,events\n\
+            1198,16.700,16.650,\n\
+            1199,33.100,18.200,resized 1280x720\n\
+            1200,16.600,18.000,focus lost;focus gained\n";
+        assert_eq!(parse_csv(soak_export).unwrap(), [16.7, 33.1, 16.6]);
+
+        let crash_dump = "frame,frame_time_ms\n10,8.5\n11,9.25\n";
+        assert_eq!(parse_csv(crash_dump).unwrap(), [8.5, 9.25]);
+    }
+
+    #[test]
+    fn csv_errors_name_the_problem() {
+        let err = parse_csv("frame,fps\n1,60\n").unwrap_err();
+        assert!(err.to_string().contains("frame_time_ms"), "{err}");
+
+        let err = parse_csv("frame,frame_time_ms\n1,16.6\n2,oops\n").unwrap_err();
+        assert_eq!(err.to_string(), "line 3: no frame time");
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn json_reads_embedded_samples() {
+        let history = r#"{ "frames": [4, 5], "frame_times_ms": [16.6, 40.0], "events": [] }"#;
+        assert_eq!(parse_json(history).unwrap(), [16.6, 40.0]);
+        assert!(parse_json(r#"{ "frames": [4, 5] }"#).is_err());
+    }
+
+    #[test]
+    fn playback_follows_recorded_durations() {
+        let mut replay = Replay::new("trace.csv".to_string(), vec![10.0, 20.0, 10.0, 5.0]);
+        assert_eq!(replay.advance(5.0), [] as [f64; 0]);
+        assert_eq!(replay.advance(5.0), [10.0]);
+        assert_eq!(replay.advance(25.0), [20.0]);
+        assert_eq!(replay.advance(0.0), [] as [f64; 0]);
+
+        replay.speed = ReplaySpeed::Paused;
+        assert_eq!(replay.advance(100.0), [] as [f64; 0]);
+
+        replay.speed = ReplaySpeed::Fast;
+        assert_eq!(replay.advance(1.5), [10.0, 5.0]);
+        assert_eq!(replay.cursor, 4);
+        assert_eq!(replay.advance(1000.0), [] as [f64; 0]);
+    }
+
+    #[test]
+    fn a_stall_only_catches_up_a_little() {
+        let mut replay = Replay::new("trace.csv".to_string(), vec![10.0; 1000]);
+        assert_eq!(replay.advance(60_000.0).len(), 25);
+    }
+
+    #[test]
+    fn next_spike_pauses_on_it() {
+        let mut frame_times_ms = vec![16.0; 500];
+        frame_times_ms[120] = 50.0;
+        frame_times_ms[400] = 80.0;
+        let mut replay = Replay::new("trace.csv".to_string(), frame_times_ms);
+
+        assert_eq!(replay.skip_to_next_spike(2.0).len(), 121);
+        assert_eq!(replay.speed, ReplaySpeed::Paused);
+        assert_eq!(replay.skip_to_next_spike(2.0).len(), 280);
+        assert_eq!(replay.cursor, 401);
+        assert!(replay.skip_to_next_spike(2.0).is_empty());
+        assert_eq!(replay.cursor, 401);
+    }
+
+    #[test]
+    #[cfg(feature = "debug-vis")]
+    fn replayed_trace_drives_the_overlay() {
+        use crate::debug_vis::{
+            DebugTextsReader, DebugVisPlugin, FpsWindow, FrameTimeHistory, tests::headless_plugins,
+        };
+
+        let path = std::env::temp_dir().join("debug_vis_replay_trace.csv");
+        let mut csv = "frame,frame_time_ms\n".to_string();
+        for frame in 0..200 {
+            let frame_time_ms = if frame == 150 { 90.0 } else { 20.0 };
+            csv.push_str(&format!("{frame},{frame_time_ms}\n"));
+        }
+        std::fs::write(&path, csv).unwrap();
+
+        let mut app = App::new();
+        app.add_plugins((headless_plugins(), DebugVisPlugin::default().with_replay(&path)));
+        app.finish();
+        app.cleanup();
+        let _ = std::fs::remove_file(path);
+        app.world_mut().write_message(DebugAction::ReplayNextSpike);
+        app.update();
+
+        let history = app.world().resource::<FrameTimeHistory>();
+        assert_eq!(history.sample_count(), 151);
+        assert_eq!(history.max_ms(), Some(90.0));
+        let fps = app.world().resource::<FpsWindow>().fps().unwrap();
+        assert!(fps < 50.0, "FPS {fps} should follow the trace, not the headless loop");
+        let line = app
+            .world_mut()
+            .run_system_cached(|reader: DebugTextsReader| reader.get("replay"))
+            .unwrap();
+        assert_eq!(line.as_deref(), Some("REPLAY: debug_vis_replay_trace.csv (frame 151/200)"));
+    }
+}
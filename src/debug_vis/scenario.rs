@@ -0,0 +1,219 @@
+//! Named workloads to measure, e.g. "lights" or "particles", registered with
+//! [`ScenarioAppExt::register_scenario`]. One is active at a time: switching despawns the
+//! previous one's entities and resets [`FrameTimeHistory`], so its frames never count
+//! towards the next.
+
+use super::{DebugCommandRegistry, DebugTextWriter, FrameTimeHistory, OverlayCost};
+use bevy::{ecs::entity::EntityHashSet, prelude::*};
+use std::sync::Arc;
+
+type ScenarioSetup = Arc<dyn Fn(&mut World) -> Result<(), String> + Send + Sync>;
+
+/// Marks entities owned by the active scenario, which are despawned when it ends. Entities
+/// spawned by the scenario's setup get it automatically; ones its systems spawn later need it
+/// added.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct ScenarioEntity;
+
+/// Write this from a scenario's systems when it can't go on, e.g. when one of its assets
+/// fails to load. A sweep records the reason and moves on to the next scenario.
+#[derive(Message, Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioFailed(pub String);
+
+/// Registered scenarios, in registration order.
+#[derive(Resource, Default, Clone)]
+pub struct ScenarioRegistry {
+    scenarios: Vec<(String, ScenarioSetup)>,
+}
+
+impl ScenarioRegistry {
+    /// Adds a scenario, replacing any scenario with the same name.
+    fn register(&mut self, name: String, setup: ScenarioSetup) {
+        match self.scenarios.iter_mut().find(|(existing, _)| *existing == name) {
+            Some(existing) => existing.1 = setup,
+            None => self.scenarios.push((name, setup)),
+        }
+    }
+
+    fn setup(&self, name: &str) -> Option<ScenarioSetup> {
+        self.scenarios
+            .iter()
+            .find(|(existing, _)| existing == name)
+            .map(|(_, setup)| setup.clone())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.scenarios.iter().map(|(name, _)| name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.scenarios.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenarios.is_empty()
+    }
+}
+
+/// The scenario currently set up, if any.
+#[derive(Resource, Default, Debug, Clone, PartialEq, Eq)]
+pub struct ActiveScenario(Option<String>);
+
+impl ActiveScenario {
+    pub fn name(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+}
+
+/// Run condition that passes while `name` is the active scenario, for the systems that
+/// drive it.
+pub fn scenario_active(name: &'static str) -> impl FnMut(Res<ActiveScenario>) -> bool + Clone {
+    move |active: Res<ActiveScenario>| active.name() == Some(name)
+}
+
+pub trait ScenarioAppExt {
+    /// Registers a scenario. `setup` spawns its entities and can fail with a reason, e.g. when
+    /// an asset it needs is missing. Each scenario also gets a "Scenario: name" command in the
+    /// command palette that switches to it.
+    fn register_scenario(
+        &mut self,
+        name: impl Into<String>,
+        setup: impl Fn(&mut World) -> Result<(), String> + Send + Sync + 'static,
+    ) -> &mut Self;
+}
+
+impl ScenarioAppExt for App {
+    fn register_scenario(
+        &mut self,
+        name: impl Into<String>,
+        setup: impl Fn(&mut World) -> Result<(), String> + Send + Sync + 'static,
+    ) -> &mut Self {
+        let name = name.into();
+        self.init_resource::<ActiveScenario>()
+            .add_message::<ScenarioFailed>();
+        self.world_mut()
+            .get_resource_or_init::<ScenarioRegistry>()
+            .register(name.clone(), Arc::new(setup));
+
+        let label = format!("Scenario: {name}");
+        self.world_mut()
+            .get_resource_or_init::<DebugCommandRegistry>()
+            .register(label, move |commands| {
+                let name = name.clone();
+                commands.queue(move |world: &mut World| {
+                    if let Err(err) = start_scenario(world, &name) {
+                        warn!("debug_vis: scenario {name} failed to start: {err}");
+                    }
+                });
+            });
+        self
+    }
+}
+
+/// Despawns the active scenario's entities and resets the frame time history.
+pub(super) fn end_scenario(world: &mut World) {
+    let entities: Vec<Entity> = world
+        .query_filtered::<Entity, With<ScenarioEntity>>()
+        .iter(world)
+        .collect();
+    for entity in entities {
+        // Children go with their parent, so some are already gone.
+        let _ = world.try_despawn(entity);
+    }
+    world.resource_mut::<FrameTimeHistory>().clear();
+    world.resource_mut::<ActiveScenario>().0 = None;
+}
+
+/// Ends the active scenario and sets up `name`. Everything the setup spawned is tagged
+/// [`ScenarioEntity`], even when it fails partway, so the next switch still cleans it up.
+pub(super) fn start_scenario(world: &mut World, name: &str) -> Result<(), String> {
+    end_scenario(world);
+    let setup = world
+        .resource::<ScenarioRegistry>()
+        .setup(name)
+        .ok_or_else(|| format!("no scenario named {name}"))?;
+
+    let existing: EntityHashSet = world.query::<Entity>().iter(world).collect();
+    world.resource_mut::<ActiveScenario>().0 = Some(name.to_string());
+    let result = setup(world);
+    world.flush();
+    let spawned: Vec<Entity> = world
+        .query::<Entity>()
+        .iter(world)
+        .filter(|entity| !existing.contains(entity))
+        .collect();
+    for entity in spawned {
+        world.entity_mut(entity).insert(ScenarioEntity);
+    }
+    result
+}
+
+pub(super) fn write_active_scenario(
+    active: Res<ActiveScenario>,
+    registry: Res<ScenarioRegistry>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    if registry.is_empty() || !active.is_changed() {
+        return;
+    }
+    let name = active.name().unwrap_or("(none)");
+    writer.write_with_persistence("scenario", format!("Scenario: {name}"), true);
+}
+
+#[cfg(all(test, feature = "debug-vis"))]
+mod tests {
+    use super::*;
+    use crate::debug_vis::{DebugVisPlugin, tests::headless_plugins};
+
+    fn spawn_named(name: &'static str) -> impl Fn(&mut World) -> Result<(), String> {
+        move |world: &mut World| {
+            world.spawn(Name::new(name)).with_child(Name::new(format!("{name} child")));
+            Ok(())
+        }
+    }
+
+    fn names(app: &mut App) -> Vec<String> {
+        let mut names: Vec<String> = app
+            .world_mut()
+            .query_filtered::<&Name, With<ScenarioEntity>>()
+            .iter(app.world())
+            .map(|name| name.to_string())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn switching_replaces_the_previous_scenario() {
+        let mut app = App::new();
+        app.add_plugins((headless_plugins(), DebugVisPlugin::default()))
+            .register_scenario("cubes", spawn_named("cubes"))
+            .register_scenario("lights", spawn_named("lights"));
+        app.update();
+
+        start_scenario(app.world_mut(), "cubes").unwrap();
+        assert_eq!(names(&mut app), ["cubes", "cubes child"]);
+        start_scenario(app.world_mut(), "lights").unwrap();
+        assert_eq!(names(&mut app), ["lights", "lights child"]);
+        assert_eq!(app.world().resource::<ActiveScenario>().name(), Some("lights"));
+        assert_eq!(app.world().resource::<FrameTimeHistory>().sample_count(), 0);
+
+        let err = start_scenario(app.world_mut(), "fog").unwrap_err();
+        assert_eq!(err, "no scenario named fog");
+        assert!(names(&mut app).is_empty());
+        assert_eq!(app.world().resource::<ActiveScenario>().name(), None);
+    }
+
+    #[test]
+    fn palette_lists_every_scenario() {
+        let mut app = App::new();
+        app.add_plugins((headless_plugins(), DebugVisPlugin::default()))
+            .register_scenario("cubes", spawn_named("cubes"));
+        let registry = app.world().resource::<DebugCommandRegistry>();
+        assert!(registry.iter().any(|command| command.label == "Scenario: cubes"));
+        assert!(registry.iter().any(|command| command.label == "Reset frame time history"));
+    }
+}
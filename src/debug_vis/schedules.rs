@@ -0,0 +1,73 @@
+//! System counts of the core schedules, a cheap proxy for how much the app has grown. Read
+//! in `Last`, when the schedules it counts are back in [`Schedules`] rather than running.
+
+use super::{DebugTextWriter, DebugVisConfig};
+use bevy::{
+    app::{FixedUpdate, PostUpdate, Update},
+    ecs::schedule::{LogLevel, Schedules},
+    prelude::*,
+};
+
+#[derive(Default, PartialEq, Eq)]
+pub(super) struct ScheduleCounts {
+    systems: [Option<usize>; 3],
+    /// Conflicting system pairs, when the host turned ambiguity detection on anywhere.
+    ambiguities: Option<usize>,
+}
+
+const SCHEDULE_NAMES: [&str; 3] = ["Update", "PostUpdate", "FixedUpdate"];
+
+fn count_schedules(schedules: &Schedules) -> ScheduleCounts {
+    let core = [
+        schedules.get(Update),
+        schedules.get(PostUpdate),
+        schedules.get(FixedUpdate),
+    ];
+    let mut counts = ScheduleCounts::default();
+    for (count, schedule) in counts.systems.iter_mut().zip(core) {
+        let Some(schedule) = schedule else {
+            continue;
+        };
+        *count = Some(schedule.systems_len());
+        if schedule.get_build_settings().ambiguity_detection != LogLevel::Ignore {
+            *counts.ambiguities.get_or_insert(0) +=
+                schedule.graph().conflicting_systems().0.len();
+        }
+    }
+    counts
+}
+
+/// Writes the counts as persistent lines, rewriting them only when a schedule gained or lost
+/// systems since the last check, e.g. when a state added some.
+pub(super) fn write_schedule_counts(
+    config: Res<DebugVisConfig>,
+    schedules: Res<Schedules>,
+    mut last: Local<Option<ScheduleCounts>>,
+    mut writer: DebugTextWriter,
+) {
+    if !config.show_schedule_counts {
+        return;
+    }
+    let counts = count_schedules(&schedules);
+    if last.as_ref() == Some(&counts) {
+        return;
+    }
+
+    let total: usize = counts.systems.iter().flatten().sum();
+    let per_schedule = SCHEDULE_NAMES
+        .into_iter()
+        .zip(counts.systems)
+        .filter_map(|(name, count)| Some(format!("{name} {}", count?)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let summary = format!("Systems: {total} ({per_schedule})");
+    info!("debug_vis: {summary}");
+    writer.write_with_persistence("schedule_systems", summary, true);
+
+    if let Some(ambiguities) = counts.ambiguities {
+        let message = format!("Ambiguities: {ambiguities}");
+        info!("debug_vis: {message}");
+        writer.write_with_persistence("schedule_ambiguities", message, true);
+    }
+    *last = Some(counts);
+}
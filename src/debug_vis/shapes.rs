@@ -0,0 +1,239 @@
+//! Rays, hits and capsules drawn from anywhere, like [`debug_text`](super::debug_text): the
+//! free functions queue a shape and [`draw_debug_shapes`] draws it through
+//! [`DebugWorldGizmoGroup`] in [`DebugLevel::Full`]. Plain calls last one frame, the `_for`
+//! variants keep the shape up for that many seconds.
+
+use super::{DebugLevel, DebugTextWriter, OverlayCost, OverlayView};
+use bevy::prelude::*;
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+const CIRCLE_SEGMENTS: usize = 24;
+/// Every this many circle points makes one arrowhead edge.
+const ARROWHEAD_STEP: usize = 3;
+/// Arrowhead length as a fraction of the ray, up to [`ARROWHEAD_MAX_LENGTH`].
+const ARROWHEAD_FRACTION: f32 = 0.15;
+const ARROWHEAD_MAX_LENGTH: f32 = 0.5;
+/// Arrowhead base radius relative to its length.
+const ARROWHEAD_WIDTH: f32 = 0.4;
+const HIT_DISC_RADIUS: f32 = 0.15;
+const HIT_NORMAL_LENGTH: f32 = 0.4;
+
+/// Gizmo group the shapes draw in. Unlike the overlay's own gizmos it is depth tested by
+/// default; change `depth_bias` in its [`GizmoConfig`] to draw through geometry.
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct DebugWorldGizmoGroup;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DebugShapeKind {
+    /// A line from `origin` along `dir` for `len`, with an arrowhead at the end.
+    Ray { origin: Vec3, dir: Vec3, len: f32 },
+    /// A disc facing `normal` at `point`, with a short line along `normal`.
+    Hit { point: Vec3, normal: Vec3 },
+    /// A capsule whose axis runs from `a` to `b`.
+    Capsule { a: Vec3, b: Vec3, radius: f32 },
+}
+
+/// One queued shape, for [`debug_shape`]. The `debug_ray`-style functions cover the usual
+/// cases; build one of these to add a label.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugShape {
+    pub kind: DebugShapeKind,
+    pub color: Color,
+    /// Short text drawn next to the shape while it's on screen.
+    pub label: Option<String>,
+    /// How long the shape stays up. `None` draws it for one frame.
+    pub duration: Option<Duration>,
+}
+
+impl DebugShape {
+    pub fn new(kind: DebugShapeKind, color: impl Into<Color>) -> Self {
+        Self {
+            kind,
+            color: color.into(),
+            label: None,
+            duration: None,
+        }
+    }
+
+    pub fn ray(origin: Vec3, dir: Vec3, len: f32, color: impl Into<Color>) -> Self {
+        Self::new(DebugShapeKind::Ray { origin, dir, len }, color)
+    }
+
+    pub fn hit(point: Vec3, normal: Vec3, color: impl Into<Color>) -> Self {
+        Self::new(DebugShapeKind::Hit { point, normal }, color)
+    }
+
+    pub fn capsule(a: Vec3, b: Vec3, radius: f32, color: impl Into<Color>) -> Self {
+        Self::new(DebugShapeKind::Capsule { a, b, radius }, color)
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Keeps the shape up for `secs` seconds of real time.
+    pub fn for_secs(mut self, secs: f32) -> Self {
+        self.duration = Some(Duration::from_secs_f32(secs.max(0.0)));
+        self
+    }
+
+    /// Where the label goes: the ray's tip, the end of the hit normal, the capsule's middle.
+    fn label_anchor(&self) -> Vec3 {
+        match self.kind {
+            DebugShapeKind::Ray { origin, dir, len } => origin + dir.normalize_or_zero() * len,
+            DebugShapeKind::Hit { point, normal } => {
+                point + normal.normalize_or_zero() * HIT_NORMAL_LENGTH
+            }
+            DebugShapeKind::Capsule { a, b, .. } => a.midpoint(b),
+        }
+    }
+}
+
+pub fn debug_shape(shape: DebugShape) {
+    if !cfg!(feature = "debug-vis") {
+        return;
+    }
+    if let Ok(mut queue) = DEBUG_SHAPE_QUEUE.get_or_init(|| Mutex::new(Vec::new())).lock() {
+        queue.push(shape);
+    }
+}
+
+pub fn debug_ray(origin: Vec3, dir: Vec3, len: f32, color: impl Into<Color>) {
+    debug_shape(DebugShape::ray(origin, dir, len, color));
+}
+
+pub fn debug_ray_for(origin: Vec3, dir: Vec3, len: f32, color: impl Into<Color>, secs: f32) {
+    debug_shape(DebugShape::ray(origin, dir, len, color).for_secs(secs));
+}
+
+pub fn debug_hit(point: Vec3, normal: Vec3, color: impl Into<Color>) {
+    debug_shape(DebugShape::hit(point, normal, color));
+}
+
+pub fn debug_hit_for(point: Vec3, normal: Vec3, color: impl Into<Color>, secs: f32) {
+    debug_shape(DebugShape::hit(point, normal, color).for_secs(secs));
+}
+
+pub fn debug_capsule(a: Vec3, b: Vec3, radius: f32, color: impl Into<Color>) {
+    debug_shape(DebugShape::capsule(a, b, radius, color));
+}
+
+pub fn debug_capsule_for(a: Vec3, b: Vec3, radius: f32, color: impl Into<Color>, secs: f32) {
+    debug_shape(DebugShape::capsule(a, b, radius, color).for_secs(secs));
+}
+
+static DEBUG_SHAPE_QUEUE: OnceLock<Mutex<Vec<DebugShape>>> = OnceLock::new();
+/// Unit circle in the XY plane, shared by arrowheads and hit discs.
+static UNIT_CIRCLE: OnceLock<[Vec2; CIRCLE_SEGMENTS]> = OnceLock::new();
+
+fn unit_circle() -> &'static [Vec2; CIRCLE_SEGMENTS] {
+    UNIT_CIRCLE.get_or_init(|| {
+        std::array::from_fn(|i| {
+            Vec2::from_angle(i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU)
+        })
+    })
+}
+
+/// Shapes still on screen, each with the real time it expires at.
+#[derive(Resource, Default)]
+pub(super) struct DebugShapes(Vec<(DebugShape, Option<Duration>)>);
+
+/// Drains the queue every frame, drawing only in [`DebugLevel::Full`], so shapes queued
+/// while the overlay is hidden don't pile up and timed ones still expire on schedule.
+pub(super) fn draw_debug_shapes(
+    level: Res<DebugLevel>,
+    time: Res<Time<Real>>,
+    mut shapes: ResMut<DebugShapes>,
+    mut gizmos: Gizmos<DebugWorldGizmoGroup>,
+    view: OverlayView,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let now = time.elapsed();
+    if let Some(queue) = DEBUG_SHAPE_QUEUE.get() {
+        let mut queue = queue.lock().unwrap();
+        shapes.0.extend(queue.drain(..).map(|shape| {
+            let expires = shape.duration.map(|duration| now + duration);
+            (shape, expires)
+        }));
+    }
+
+    if *level == DebugLevel::Full {
+        let camera = view.camera();
+        // Keyed by label text rather than position in the list, so a label keeps its line as
+        // shapes before it expire. Repeats of a label get numbered.
+        let mut repeats: HashMap<&str, usize> = HashMap::new();
+        for (shape, _) in &shapes.0 {
+            draw_shape(&mut gizmos, shape);
+
+            let (Some(label), Some((_, camera, camera_transform))) = (&shape.label, camera) else {
+                continue;
+            };
+            let repeat = repeats.entry(label).or_default();
+            let key = format!("shape_{label}_{repeat}");
+            *repeat += 1;
+            if let Ok(position) = camera.world_to_viewport(camera_transform, shape.label_anchor())
+            {
+                writer.write_at_pixel(key, label.clone(), position);
+            }
+        }
+    }
+
+    shapes
+        .0
+        .retain(|(_, expires)| expires.is_some_and(|expires| expires > now));
+}
+
+fn draw_shape(gizmos: &mut Gizmos<DebugWorldGizmoGroup>, shape: &DebugShape) {
+    let color = shape.color;
+    match shape.kind {
+        DebugShapeKind::Ray { origin, dir, len } => {
+            let Some(dir) = dir.try_normalize() else {
+                return;
+            };
+            if len <= 0.0 {
+                return;
+            }
+            let tip = origin + dir * len;
+            gizmos.line(origin, tip, color);
+
+            let head_length = (len * ARROWHEAD_FRACTION).min(ARROWHEAD_MAX_LENGTH);
+            let base = tip - dir * head_length;
+            let (u, v) = dir.any_orthonormal_pair();
+            let radius = head_length * ARROWHEAD_WIDTH;
+            for point in unit_circle().iter().step_by(ARROWHEAD_STEP) {
+                gizmos.line(base + (u * point.x + v * point.y) * radius, tip, color);
+            }
+        }
+        DebugShapeKind::Hit { point, normal } => {
+            let Some(normal) = normal.try_normalize() else {
+                return;
+            };
+            let (u, v) = normal.any_orthonormal_pair();
+            let rim = unit_circle()
+                .iter()
+                .chain(unit_circle().first())
+                .map(|p| point + (u * p.x + v * p.y) * HIT_DISC_RADIUS);
+            gizmos.linestrip(rim, color);
+            gizmos.line(point, point + normal * HIT_NORMAL_LENGTH, color);
+        }
+        DebugShapeKind::Capsule { a, b, radius } => {
+            let axis = b - a;
+            let rotation = axis
+                .try_normalize()
+                .map_or(Quat::IDENTITY, |axis| Quat::from_rotation_arc(Vec3::Y, axis));
+            gizmos.primitive_3d(
+                &Capsule3d::new(radius, axis.length()),
+                Isometry3d::new(a.midpoint(b), rotation),
+                color,
+            );
+        }
+    }
+}
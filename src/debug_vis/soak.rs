@@ -0,0 +1,308 @@
+//! Unattended long runs: the frame time window is exported on a timer into a small ring of
+//! files, spikes are appended to a log as they happen, and a collapse in frame rate gets a
+//! screenshot and report of its own. With the `serde` feature each export also gets a JSON
+//! [`StabilityReport`](super::StabilityReport) next to it. Every export is written to a temp
+//! file and renamed, so after a crash the newest file is complete.
+
+use super::{DebugTextWriter, FrameSpike, FrameTimeHistory, OverlayCost, TimelineEvents};
+use bevy::{
+    diagnostic::FrameCount,
+    prelude::*,
+    render::view::screenshot::{Screenshot, save_to_disk},
+};
+use std::{
+    fmt::Write as _,
+    fs::OpenOptions,
+    io::Write as _,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const SPIKE_LOG: &str = "spikes.csv";
+
+/// Settings for [`DebugVisPlugin::with_soak`](super::DebugVisPlugin::with_soak).
+#[derive(Resource, Debug, Clone)]
+pub struct SoakConfig {
+    /// Directory all soak files are written to. Created if missing.
+    pub dir: PathBuf,
+    pub export_interval: Duration,
+    /// Periodic exports kept on disk; the oldest is overwritten once there are this many.
+    pub export_ring: usize,
+    /// FPS below which the run counts as collapsing.
+    pub fps_floor: f64,
+    /// How long FPS has to stay below `fps_floor` before a collapse report is written.
+    pub collapse_after: Duration,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("soak"),
+            export_interval: Duration::from_secs(5 * 60),
+            export_ring: 6,
+            fps_floor: 20.0,
+            collapse_after: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub(super) struct SoakState {
+    last_export: Duration,
+    exports: usize,
+    below_floor_since: Option<Duration>,
+    /// Set once a collapse has been reported, until FPS recovers above the floor.
+    collapse_reported: bool,
+}
+
+pub(super) struct DebugVisSoakPlugin(pub(super) SoakConfig);
+
+impl Plugin for DebugVisSoakPlugin {
+    fn build(&self, app: &mut App) {
+        if let Err(err) = std::fs::create_dir_all(&self.0.dir) {
+            warn!("debug_vis: can't create soak directory {}: {err}", self.0.dir.display());
+        }
+        app.insert_resource(self.0.clone())
+            .init_resource::<SoakState>()
+            .add_systems(
+                Update,
+                (export_periodically, log_spikes, report_collapse, write_soak_status),
+            );
+    }
+}
+
+/// Writes `contents` to `path` through a temp file in the same directory, so readers never
+/// see a half-written file.
+fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    let temp = path.with_extension("tmp");
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, path)
+}
+
+/// The frame time window as CSV, with summary statistics as leading `# key: value` comment
+/// lines, `--` for a statistic with no value. Window events recorded in a frame go in its
+/// `events` column, separated by `;`.
+fn frame_times_csv(
+    history: &FrameTimeHistory,
+    timeline: &TimelineEvents,
+    frame: u32,
+    uptime: Duration,
+) -> String {
+    let snapshot = history.snapshot();
+    let mut csv = format!("# frame: {frame}\n# uptime_s: {:.0}\n", uptime.as_secs_f64());
+    let _ = writeln!(csv, "# sample_count: {}", snapshot.sample_count);
+    for (key, value) in [
+        ("avg_ms", snapshot.avg_ms),
+        ("max_ms", snapshot.max_ms),
+        ("p50_ms", snapshot.p50_ms),
+        ("p95_ms", snapshot.p95_ms),
+        ("p99_ms", snapshot.p99_ms),
+        ("fps_1s", snapshot.fps_1s),
+    ] {
+        let _ = match value {
+            Some(value) => writeln!(csv, "# {key}: {value:.3}"),
+            None => writeln!(csv, "# {key}: --"),
+        };
+    }
+    csv.push_str("frame,frame_time_ms,smoothed_ms,events\n");
+    for ((frame, frame_time_ms), smoothed_ms) in
+        history.iter_with_frames().zip(&history.smoothed_ms)
+    {
+        let events = timeline
+            .in_frames(frame, frame)
+            .map(|event| event.kind.to_string())
+            .collect::<Vec<_>>()
+            .join(";");
+        let _ = writeln!(csv, "{frame},{frame_time_ms:.3},{smoothed_ms:.3},{events}");
+    }
+    csv
+}
+
+fn format_hms(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+fn export_periodically(
+    config: Res<SoakConfig>,
+    mut state: ResMut<SoakState>,
+    time: Res<Time<Real>>,
+    frame: Res<FrameCount>,
+    history: Res<FrameTimeHistory>,
+    timeline: Res<TimelineEvents>,
+    #[cfg(feature = "serde")] view: super::OverlayView,
+    mut writer: DebugTextWriter,
+) {
+    let now = time.elapsed();
+    if now.saturating_sub(state.last_export) < config.export_interval {
+        return;
+    }
+    state.last_export = now;
+
+    let slot = state.exports % config.export_ring.max(1);
+    state.exports += 1;
+    let path = config.dir.join(format!("export_{slot}.csv"));
+    match write_atomic(&path, &frame_times_csv(&history, &timeline, frame.0, now)) {
+        Ok(()) => writer.write_with_persistence(
+            "soak_last_export",
+            format!("Soak export: {} (frame {})", path.display(), frame.0),
+            true,
+        ),
+        Err(err) => warn!("debug_vis: soak export to {} failed: {err}", path.display()),
+    }
+
+    #[cfg(feature = "serde")]
+    {
+        let report = super::StabilityReport::capture(&history, view.window(), frame.0);
+        let path = path.with_extension("json");
+        let result = serde_json::to_string_pretty(&report)
+            .map_err(std::io::Error::from)
+            .and_then(|json| write_atomic(&path, &json));
+        if let Err(err) = result {
+            warn!("debug_vis: soak export to {} failed: {err}", path.display());
+        }
+    }
+}
+
+fn log_spikes(
+    config: Res<SoakConfig>,
+    time: Res<Time<Real>>,
+    mut spikes: MessageReader<FrameSpike>,
+) {
+    if spikes.is_empty() {
+        return;
+    }
+
+    let path = config.dir.join(SPIKE_LOG);
+    let is_new = !path.exists();
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| {
+            if is_new {
+                writeln!(
+                    file,
+                    "frame,uptime_s,frame_time_ms,threshold_ms,entity_delta,assets_changed,pipelines_compiled,window_events"
+                )?;
+            }
+            for spike in spikes.read() {
+                let cause = spike.attribution;
+                writeln!(
+                    file,
+                    "{},{:.1},{:.3},{:.3},{},{},{},{}",
+                    spike.frame,
+                    time.elapsed_secs_f64(),
+                    spike.frame_time_ms,
+                    spike.threshold_ms,
+                    cause.entity_delta,
+                    cause.assets_changed,
+                    cause.pipelines_compiled,
+                    cause.window_events
+                )?;
+            }
+            Ok(())
+        });
+    if let Err(err) = result {
+        warn!("debug_vis: can't append to {}: {err}", path.display());
+    }
+}
+
+fn report_collapse(
+    mut commands: Commands,
+    config: Res<SoakConfig>,
+    mut state: ResMut<SoakState>,
+    time: Res<Time<Real>>,
+    frame: Res<FrameCount>,
+    history: Res<FrameTimeHistory>,
+    timeline: Res<TimelineEvents>,
+    mut writer: DebugTextWriter,
+) {
+    let now = time.elapsed();
+    let below_floor = history
+        .fps_1s()
+        .is_some_and(|fps| fps < config.fps_floor);
+    if !below_floor {
+        state.below_floor_since = None;
+        state.collapse_reported = false;
+        return;
+    }
+    let since = *state.below_floor_since.get_or_insert(now);
+    if state.collapse_reported || now.saturating_sub(since) < config.collapse_after {
+        return;
+    }
+    state.collapse_reported = true;
+
+    let report = config.dir.join(format!("collapse_{}.csv", frame.0));
+    if let Err(err) = write_atomic(&report, &frame_times_csv(&history, &timeline, frame.0, now)) {
+        warn!("debug_vis: collapse report to {} failed: {err}", report.display());
+    }
+    let screenshot = config.dir.join(format!("collapse_{}.png", frame.0));
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_to_disk(screenshot));
+    writer.write_with_persistence(
+        "soak_collapse",
+        format!(
+            "Soak: FPS below {:.0} for {:.0}s, report {} (frame {})",
+            config.fps_floor,
+            config.collapse_after.as_secs_f64(),
+            report.display(),
+            frame.0
+        ),
+        true,
+    );
+}
+
+fn write_soak_status(
+    config: Res<SoakConfig>,
+    state: Res<SoakState>,
+    time: Res<Time<Real>>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let now = time.elapsed();
+    let next_export = (state.last_export + config.export_interval).saturating_sub(now);
+    let next_export = next_export.as_secs();
+    writer.write(
+        "soak_status",
+        format!(
+            "Soak: up {}, next export in {}:{:02}",
+            format_hms(now),
+            next_export / 60,
+            next_export % 60
+        ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_lists_each_stat_on_its_own_line() {
+        let mut history = FrameTimeHistory::default();
+        for frame in 0..4 {
+            history.push(16.0 + frame as f64, None, frame);
+        }
+        let csv = frame_times_csv(&history, &TimelineEvents::default(), 3, Duration::from_secs(7));
+        let comments: Vec<&str> = csv.lines().take_while(|line| line.starts_with('#')).collect();
+        assert_eq!(
+            comments,
+            [
+                "# frame: 3",
+                "# uptime_s: 7",
+                "# sample_count: 4",
+                "# avg_ms: 17.500",
+                "# max_ms: 19.000",
+                "# p50_ms: 17.000",
+                "# p95_ms: 19.000",
+                "# p99_ms: 19.000",
+                "# fps_1s: 57.143",
+            ]
+        );
+        assert_eq!(csv.lines().nth(comments.len()), Some("frame,frame_time_ms,smoothed_ms,events"));
+    }
+}
@@ -0,0 +1,151 @@
+//! Frame spike detection with cheap attribution: alongside each spike, whatever the engine
+//! reported during that frame that commonly causes one.
+
+use super::{DebugTextWriter, DebugVisConfig, FrameTimeHistory, OverlayCost};
+use bevy::{ecs::entity::Entities, prelude::*, window::WindowEvent};
+use std::time::Duration;
+
+const SPIKE_LINE_DURATION: Duration = Duration::from_secs(3);
+const SPIKE_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+
+/// Engine activity seen in the frame of a spike.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpikeAttribution {
+    /// Net change in live entities.
+    pub entity_delta: i64,
+    /// Mesh, image and shader assets added, modified or removed.
+    pub assets_changed: usize,
+    /// Render pipelines that finished compiling. Always 0 on wasm.
+    pub pipelines_compiled: usize,
+    pub window_events: usize,
+}
+
+impl SpikeAttribution {
+    fn describe(&self) -> Vec<String> {
+        let mut causes = Vec::new();
+        if self.entity_delta != 0 {
+            causes.push(format!("{:+} entities", self.entity_delta));
+        }
+        if self.assets_changed > 0 {
+            causes.push(format!("{} assets changed", self.assets_changed));
+        }
+        match self.pipelines_compiled {
+            0 => {}
+            1 => causes.push("pipeline compiled".to_string()),
+            n => causes.push(format!("{n} pipelines compiled")),
+        }
+        if self.window_events > 0 {
+            causes.push(format!("{} window events", self.window_events));
+        }
+        causes
+    }
+}
+
+/// Sent for every frame time more than [`DebugVisConfig::spike_k_factor`] standard
+/// deviations above the window average.
+#[derive(Message, Debug, Clone, Copy, PartialEq)]
+pub struct FrameSpike {
+    /// [`FrameCount`](bevy::diagnostic::FrameCount) the slow frame was recorded at.
+    pub frame: u32,
+    pub frame_time_ms: f64,
+    pub threshold_ms: f64,
+    pub attribution: SpikeAttribution,
+}
+
+/// Counters from the previous frame, to turn totals into per-frame deltas.
+#[derive(Default)]
+pub(super) struct SpikeBaseline {
+    entities: Option<u32>,
+    pipelines_ready: Option<usize>,
+    samples: u64,
+}
+
+pub(super) fn detect_frame_spikes(
+    history: Res<FrameTimeHistory>,
+    config: Res<DebugVisConfig>,
+    entities: &Entities,
+    mut mesh_events: MessageReader<AssetEvent<Mesh>>,
+    mut image_events: MessageReader<AssetEvent<Image>>,
+    mut shader_events: MessageReader<AssetEvent<Shader>>,
+    mut window_events: MessageReader<WindowEvent>,
+    #[cfg(not(target_arch = "wasm32"))] pipelines: Option<
+        Res<super::pipelines::SharedPipelineCounts>,
+    >,
+    mut baseline: Local<SpikeBaseline>,
+    mut spikes: MessageWriter<FrameSpike>,
+) {
+    let entity_count = entities.len();
+    #[cfg(not(target_arch = "wasm32"))]
+    let pipelines_ready = pipelines.map(|pipelines| pipelines.ready());
+    #[cfg(target_arch = "wasm32")]
+    let pipelines_ready: Option<usize> = None;
+
+    let attribution = SpikeAttribution {
+        entity_delta: baseline
+            .entities
+            .map_or(0, |previous| i64::from(entity_count) - i64::from(previous)),
+        assets_changed: mesh_events.read().count()
+            + image_events.read().count()
+            + shader_events.read().count(),
+        pipelines_compiled: match (baseline.pipelines_ready, pipelines_ready) {
+            (Some(previous), Some(ready)) => ready.saturating_sub(previous),
+            _ => 0,
+        },
+        window_events: window_events.read().count(),
+    };
+    baseline.entities = Some(entity_count);
+    baseline.pipelines_ready = pipelines_ready;
+
+    let new_samples = (history.pushed - baseline.samples).min(history.sample_count() as u64);
+    baseline.samples = history.pushed;
+    let Some(avg_ms) = history.avg_ms() else {
+        return;
+    };
+    let threshold_ms = avg_ms + config.spike_k_factor * history.variance().sqrt();
+
+    let recent = history.iter_with_frames().rev().take(new_samples as usize);
+    let slow = recent.filter(|&(_, frame_time_ms)| frame_time_ms > threshold_ms);
+    for (frame, frame_time_ms) in slow {
+        spikes.write(FrameSpike {
+            frame,
+            frame_time_ms,
+            threshold_ms,
+            attribution,
+        });
+    }
+}
+
+pub(super) fn write_frame_spikes(
+    time: Res<Time<Real>>,
+    mut spikes: MessageReader<FrameSpike>,
+    mut latest: Local<Option<(String, Duration)>>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let now = time.elapsed();
+    if let Some(spike) = spikes.read().last() {
+        let causes = spike.attribution.describe();
+        let message = if causes.is_empty() {
+            format!("SPIKE {:.0} ms (frame {})", spike.frame_time_ms, spike.frame)
+        } else {
+            format!(
+                "SPIKE {:.0} ms (frame {}): {}",
+                spike.frame_time_ms,
+                spike.frame,
+                causes.join(", ")
+            )
+        };
+        *latest = Some((message, now));
+    }
+
+    let Some((message, at)) = &*latest else {
+        return;
+    };
+    if now.saturating_sub(*at) > SPIKE_LINE_DURATION {
+        *latest = None;
+        return;
+    }
+    writer.write_colored("frame_spike", message.clone(), SPIKE_COLOR);
+}
@@ -0,0 +1,37 @@
+//! Shows the current value of registered [`States`] types, see
+//! [`DebugStateAppExt::debug_state`].
+
+use super::{DebugTextWriter, OverlayCost};
+use bevy::{prelude::*, state::state::StateTransitionEvent};
+
+pub trait DebugStateAppExt {
+    /// Keeps a "State<S>: Value" line in the overlay, updated on every transition of `S`.
+    /// Each registered state type gets its own line.
+    fn debug_state<S: States>(&mut self) -> &mut Self;
+}
+
+impl DebugStateAppExt for App {
+    fn debug_state<S: States>(&mut self) -> &mut Self {
+        self.add_message::<StateTransitionEvent<S>>()
+            .add_systems(Update, write_state_transitions::<S>)
+    }
+}
+
+fn write_state_transitions<S: States>(
+    mut transitions: MessageReader<StateTransitionEvent<S>>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let Some(transition) = transitions.read().last() else {
+        return;
+    };
+
+    let name = ShortName::of::<S>();
+    let value = match &transition.entered {
+        Some(state) => format!("{state:?}"),
+        None => "(none)".to_string(),
+    };
+    writer.write_with_persistence(format!("state_{name}"), format!("State<{name}>: {value}"), true);
+}
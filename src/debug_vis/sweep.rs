@@ -0,0 +1,490 @@
+//! Release qualification runs: every registered scenario in turn, each warmed up and then
+//! measured for a fixed time, followed by a combined report and exit. A scenario that fails
+//! is recorded as such and the sweep goes on with the next one.
+
+use super::{
+    DebugTextWriter, FrameStatsSnapshot, FrameTimeHistory, OverlayCost,
+    scenario::{self, ScenarioFailed, ScenarioRegistry},
+};
+#[cfg(feature = "report_upload")]
+use super::upload::ReportUpload;
+use bevy::{ecs::message::Messages, prelude::*};
+use std::{fmt::Write as _, path::PathBuf, time::Duration};
+
+/// Bumped whenever a field of [`SweepReport`] changes meaning.
+pub const SWEEP_REPORT_VERSION: u32 = 1;
+
+/// Settings for [`DebugVisPlugin::with_sweep`](super::DebugVisPlugin::with_sweep).
+#[derive(Resource, Debug, Clone)]
+pub struct SweepConfig {
+    /// How long each scenario runs before measuring starts, so shader compiles and asset
+    /// loads are out of the way.
+    pub warmup: Duration,
+    /// How long each scenario is measured for.
+    pub duration: Duration,
+    /// Where the JSON report is written, with the `serde` feature.
+    pub report: PathBuf,
+    /// Where to POST the JSON report once the sweep is done, with the `report_upload`
+    /// feature. Plain `http://` only; the `Authorization` header is taken from
+    /// [`REPORT_AUTH_ENV`](super::REPORT_AUTH_ENV) when it's set.
+    #[cfg(feature = "report_upload")]
+    pub report_url: Option<String>,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        Self {
+            warmup: Duration::from_secs(5),
+            duration: Duration::from_secs(30),
+            report: PathBuf::from("sweep_report.json"),
+            #[cfg(feature = "report_upload")]
+            report_url: None,
+        }
+    }
+}
+
+/// How one scenario of a sweep went.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScenarioResult {
+    pub name: String,
+    /// Statistics over every frame measured. Empty when the scenario failed before
+    /// measuring started.
+    pub stats: FrameStatsSnapshot,
+    /// Why the scenario failed, if it did.
+    pub error: Option<String>,
+}
+
+/// Results of a whole sweep, in scenario order. Inserted as a resource once the sweep is
+/// done.
+#[derive(Resource, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SweepReport {
+    pub version: u32,
+    pub warmup_s: f64,
+    pub duration_s: f64,
+    pub scenarios: Vec<ScenarioResult>,
+}
+
+impl SweepReport {
+    pub fn failed(&self) -> bool {
+        self.scenarios.iter().any(|result| result.error.is_some())
+    }
+
+    /// One row per scenario, for stdout.
+    pub fn table(&self) -> String {
+        let width = self
+            .scenarios
+            .iter()
+            .map(|result| result.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max("scenario".len());
+        let mut table = format!(
+            "{:width$}  {:>7}  {:>7}  {:>7}  {:>7}  {:>6}  result\n",
+            "scenario", "avg ms", "p95 ms", "p99 ms", "max ms", "frames"
+        );
+        let ms = |value: Option<f64>| value.map_or("-".to_string(), |ms| format!("{ms:.2}"));
+        for result in &self.scenarios {
+            let stats = &result.stats;
+            let outcome = match &result.error {
+                Some(err) => format!("failed: {err}"),
+                None => "ok".to_string(),
+            };
+            let _ = writeln!(
+                table,
+                "{:width$}  {:>7}  {:>7}  {:>7}  {:>7}  {:>6}  {outcome}",
+                result.name,
+                ms(stats.avg_ms),
+                ms(stats.p95_ms),
+                ms(stats.p99_ms),
+                ms(stats.max_ms),
+                stats.sample_count
+            );
+        }
+        table
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn save_to_file(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+    }
+}
+
+/// Statistics over a whole measurement, which is usually longer than the
+/// [`FrameTimeHistory`] window. Same definitions as [`FrameTimeHistory::snapshot`].
+fn measured_stats(frame_times_ms: &[f64]) -> FrameStatsSnapshot {
+    if frame_times_ms.is_empty() {
+        return FrameStatsSnapshot::default();
+    }
+    let mut sorted = frame_times_ms.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let percentile = |p: f64| {
+        let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+        sorted[rank.clamp(1, sorted.len()) - 1]
+    };
+
+    let mut last_second_ms = 0.0;
+    let mut last_second_frames = 0usize;
+    for frame_time_ms in frame_times_ms.iter().rev() {
+        last_second_ms += frame_time_ms;
+        last_second_frames += 1;
+        if last_second_ms >= 1000.0 {
+            break;
+        }
+    }
+
+    FrameStatsSnapshot {
+        sample_count: frame_times_ms.len(),
+        avg_ms: Some(frame_times_ms.iter().sum::<f64>() / frame_times_ms.len() as f64),
+        max_ms: sorted.last().copied(),
+        p50_ms: Some(percentile(50.0)),
+        p95_ms: Some(percentile(95.0)),
+        p99_ms: Some(percentile(99.0)),
+        fps_1s: (last_second_ms > 0.0)
+            .then(|| last_second_frames as f64 * 1000.0 / last_second_ms),
+    }
+}
+
+/// How long the upload's outcome stays on screen before the app exits.
+#[cfg(feature = "report_upload")]
+const UPLOAD_OUTCOME_SHOWN_FOR: Duration = Duration::from_secs(3);
+
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+enum SweepPhase {
+    /// Setting up the scenario at `Sweep::index` on the next update.
+    #[default]
+    Starting,
+    Warmup { until: Duration },
+    Measuring { until: Duration },
+    Finished,
+}
+
+#[derive(Resource, Default, Debug)]
+pub(super) struct Sweep {
+    /// Registry index of the current scenario.
+    index: usize,
+    phase: SweepPhase,
+    /// Frame times recorded since measuring started.
+    measured_ms: Vec<f64>,
+    /// [`FrameTimeHistory::pushed`] as of the last update, to pick out new samples.
+    pushed: u64,
+    results: Vec<ScenarioResult>,
+    /// The report upload, until its outcome is in.
+    #[cfg(feature = "report_upload")]
+    upload: Option<ReportUpload>,
+    /// The upload's outcome line, and when it came in.
+    #[cfg(feature = "report_upload")]
+    upload_outcome: Option<(String, Duration)>,
+    /// Exit held back until the upload's outcome has been shown.
+    #[cfg(feature = "report_upload")]
+    exit: Option<AppExit>,
+}
+
+impl Sweep {
+    fn record(&mut self, name: String, error: Option<String>) {
+        if let Some(err) = &error {
+            warn!("debug_vis: sweep scenario {name} failed: {err}");
+        }
+        self.results.push(ScenarioResult {
+            name,
+            stats: measured_stats(&self.measured_ms),
+            error,
+        });
+        self.measured_ms.clear();
+        self.index += 1;
+        self.phase = SweepPhase::Starting;
+    }
+}
+
+pub(super) struct DebugVisSweepPlugin(pub(super) SweepConfig);
+
+impl Plugin for DebugVisSweepPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.0.clone())
+            .init_resource::<Sweep>()
+            .add_systems(
+                Update,
+                (
+                    run_sweep.after(super::update_frame_time_history),
+                    write_sweep_status.after(run_sweep),
+                ),
+            );
+    }
+}
+
+fn run_sweep(world: &mut World) {
+    let now = world.resource::<Time<Real>>().elapsed();
+    let failure = world
+        .resource_mut::<Messages<ScenarioFailed>>()
+        .drain()
+        .last()
+        .map(|failed| failed.0);
+    let config = world.resource::<SweepConfig>().clone();
+
+    world.resource_scope(|world, mut sweep: Mut<Sweep>| {
+        let name = world
+            .resource::<ScenarioRegistry>()
+            .names()
+            .nth(sweep.index)
+            .map(str::to_string);
+
+        let history = world.resource::<FrameTimeHistory>();
+        let new_samples = (history.pushed - sweep.pushed) as usize;
+        if matches!(sweep.phase, SweepPhase::Measuring { .. }) {
+            let skip = history.frame_times_ms.len().saturating_sub(new_samples);
+            let samples = history.frame_times_ms.iter().skip(skip);
+            sweep.measured_ms.extend(samples);
+        }
+        sweep.pushed = history.pushed;
+
+        match (sweep.phase, name) {
+            (SweepPhase::Finished, _) => {
+                #[cfg(feature = "report_upload")]
+                finish_upload(world, &mut sweep, now);
+            }
+            (SweepPhase::Starting, None) => {
+                scenario::end_scenario(world);
+                sweep.phase = SweepPhase::Finished;
+                finish_sweep(world, &config, &mut sweep);
+            }
+            (SweepPhase::Starting, Some(name)) => match scenario::start_scenario(world, &name) {
+                Ok(()) => {
+                    sweep.phase = SweepPhase::Warmup {
+                        until: now + config.warmup,
+                    }
+                }
+                Err(err) => sweep.record(name, Some(err)),
+            },
+            (SweepPhase::Warmup { .. } | SweepPhase::Measuring { .. }, Some(name))
+                if failure.is_some() =>
+            {
+                sweep.record(name, failure);
+            }
+            (SweepPhase::Warmup { until }, _) if now >= until => {
+                world.resource_mut::<FrameTimeHistory>().clear();
+                sweep.phase = SweepPhase::Measuring {
+                    until: now + config.duration,
+                };
+            }
+            (SweepPhase::Measuring { until }, Some(name)) if now >= until => {
+                sweep.record(name, None);
+            }
+            _ => {}
+        }
+    });
+}
+
+/// Prints the table, writes the JSON report and exits, with an error code if any scenario
+/// failed. With a [`SweepConfig::report_url`], the exit waits for the upload.
+fn finish_sweep(world: &mut World, config: &SweepConfig, sweep: &mut Sweep) {
+    let report = SweepReport {
+        version: SWEEP_REPORT_VERSION,
+        warmup_s: config.warmup.as_secs_f64(),
+        duration_s: config.duration.as_secs_f64(),
+        scenarios: std::mem::take(&mut sweep.results),
+    };
+    println!("{}", report.table());
+
+    #[cfg(feature = "serde")]
+    match report.save_to_file(&config.report) {
+        Ok(()) => println!("Sweep report: {}", config.report.display()),
+        Err(err) => warn!("debug_vis: can't write {}: {err}", config.report.display()),
+    }
+
+    let exit = if report.failed() {
+        AppExit::error()
+    } else {
+        AppExit::Success
+    };
+
+    #[cfg(feature = "report_upload")]
+    if let Some(url) = &config.report_url {
+        match serde_json::to_string(&report) {
+            Ok(json) => {
+                sweep.upload = Some(ReportUpload::start(url.clone(), json));
+                sweep.exit = Some(exit);
+                world.insert_resource(report);
+                return;
+            }
+            Err(err) => warn!("debug_vis: can't upload the sweep report: {err}"),
+        }
+    }
+
+    world.insert_resource(report);
+    world.write_message(exit);
+}
+
+/// Prints the upload's outcome once it's in, then exits once it has been on screen for
+/// [`UPLOAD_OUTCOME_SHOWN_FOR`]. The exit code is the sweep's, whatever the upload did.
+#[cfg(feature = "report_upload")]
+fn finish_upload(world: &mut World, sweep: &mut Sweep, now: Duration) {
+    if let Some(outcome) = sweep.upload.as_ref().and_then(ReportUpload::outcome) {
+        println!("{outcome}");
+        sweep.upload = None;
+        sweep.upload_outcome = Some((outcome, now));
+    }
+    if let Some((_, shown_at)) = &sweep.upload_outcome
+        && now.saturating_sub(*shown_at) >= UPLOAD_OUTCOME_SHOWN_FOR
+        && let Some(exit) = sweep.exit.take()
+    {
+        world.write_message(exit);
+    }
+}
+
+fn write_sweep_status(
+    sweep: Res<Sweep>,
+    config: Res<SweepConfig>,
+    registry: Res<ScenarioRegistry>,
+    time: Res<Time<Real>>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let now = time.elapsed();
+    let left = match sweep.phase {
+        SweepPhase::Starting => config.warmup + config.duration,
+        SweepPhase::Warmup { until } => until.saturating_sub(now) + config.duration,
+        SweepPhase::Measuring { until } => until.saturating_sub(now),
+        SweepPhase::Finished => {
+            #[cfg(feature = "report_upload")]
+            if sweep.upload.is_some() {
+                writer.write("sweep", "Sweep finished, uploading the report");
+                return;
+            } else if let Some((outcome, _)) = &sweep.upload_outcome {
+                writer.write("sweep", format!("Sweep finished. {outcome}"));
+                return;
+            }
+            writer.write("sweep", "Sweep finished");
+            return;
+        }
+    };
+    let name = registry.names().nth(sweep.index).unwrap_or("-");
+    writer.write(
+        "sweep",
+        format!(
+            "Sweep {}/{}: {name} — {} s left",
+            sweep.index + 1,
+            registry.len(),
+            left.as_secs_f64().ceil()
+        ),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measured_stats_cover_every_sample() {
+        let mut frame_times_ms = vec![10.0; 990];
+        frame_times_ms.extend([50.0; 10]);
+        let stats = measured_stats(&frame_times_ms);
+        assert_eq!(stats.sample_count, 1000);
+        assert_eq!(stats.avg_ms, Some(10.4));
+        assert_eq!(stats.p50_ms, Some(10.0));
+        assert_eq!(stats.p99_ms, Some(10.0));
+        assert_eq!(stats.max_ms, Some(50.0));
+        assert_eq!(measured_stats(&[]), FrameStatsSnapshot::default());
+    }
+
+    #[test]
+    fn table_marks_failures() {
+        let report = SweepReport {
+            version: SWEEP_REPORT_VERSION,
+            warmup_s: 0.0,
+            duration_s: 1.0,
+            scenarios: vec![
+                ScenarioResult {
+                    name: "lights".to_string(),
+                    stats: measured_stats(&[16.0, 17.0]),
+                    error: None,
+                },
+                ScenarioResult {
+                    name: "particles".to_string(),
+                    stats: FrameStatsSnapshot::default(),
+                    error: Some("missing smoke.png".to_string()),
+                },
+            ],
+        };
+        assert!(report.failed());
+        let table = report.table();
+        let rows: Vec<&str> = table.lines().collect();
+        assert_eq!(rows.len(), 3);
+        assert!(rows[0].starts_with("scenario    avg ms"), "{table}");
+        assert!(rows[1].starts_with("lights       16.50"), "{table}");
+        assert!(rows[1].ends_with("2  ok"), "{table}");
+        assert!(rows[2].ends_with("0  failed: missing smoke.png"), "{table}");
+    }
+
+    #[test]
+    #[cfg(feature = "debug-vis")]
+    fn sweep_runs_every_scenario_and_exits() {
+        use crate::debug_vis::{
+            DebugTextsReader, DebugVisConfig, DebugVisPlugin, FrameTimeSource, ManualFrameTimes,
+            scenario::{ScenarioAppExt, ScenarioEntity, scenario_active},
+            tests::headless_plugins,
+        };
+
+        let config = SweepConfig {
+            warmup: Duration::ZERO,
+            duration: Duration::ZERO,
+            report: std::env::temp_dir().join("debug_vis_sweep_report.json"),
+            #[cfg(feature = "report_upload")]
+            report_url: None,
+        };
+        let mut app = App::new();
+        app.add_plugins((headless_plugins(), DebugVisPlugin::default().with_sweep(config)))
+            .register_scenario("cubes", |world: &mut World| {
+                world.spawn(Name::new("cube"));
+                Ok(())
+            })
+            .register_scenario("missing", |world: &mut World| {
+                world.spawn(Name::new("half set up"));
+                Err("missing.glb not found".to_string())
+            })
+            .register_scenario("flaky", |_: &mut World| Ok(()))
+            .add_systems(
+                Update,
+                (|mut failed: MessageWriter<ScenarioFailed>| {
+                    failed.write(ScenarioFailed("device lost".to_string()));
+                })
+                .run_if(scenario_active("flaky")),
+            );
+        app.world_mut()
+            .resource_mut::<DebugVisConfig>()
+            .frame_time_source = FrameTimeSource::Manual;
+
+        app.update();
+        let line = app
+            .world_mut()
+            .run_system_cached(|reader: DebugTextsReader| reader.get("sweep"))
+            .unwrap();
+        assert_eq!(line.as_deref(), Some("Sweep 1/3: cubes — 0 s left"));
+
+        let mut exit = None;
+        for _ in 0..20 {
+            app.world_mut().resource_mut::<ManualFrameTimes>().push(20.0);
+            app.update();
+            exit = app.world_mut().resource_mut::<Messages<AppExit>>().drain().last();
+            if exit.is_some() {
+                break;
+            }
+        }
+        let _ = std::fs::remove_file(std::env::temp_dir().join("debug_vis_sweep_report.json"));
+        assert_eq!(exit, Some(AppExit::error()));
+
+        let report = app.world().resource::<SweepReport>();
+        let names: Vec<&str> = report.scenarios.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["cubes", "missing", "flaky"]);
+        assert_eq!(report.scenarios[0].error, None);
+        assert_eq!(report.scenarios[0].stats.avg_ms, Some(20.0));
+        assert_eq!(report.scenarios[1].error.as_deref(), Some("missing.glb not found"));
+        assert_eq!(report.scenarios[2].error.as_deref(), Some("device lost"));
+
+        let mut leftovers = app.world_mut().query_filtered::<(), With<ScenarioEntity>>();
+        assert_eq!(leftovers.iter(app.world()).count(), 0);
+    }
+}
@@ -0,0 +1,95 @@
+//! Long-horizon check for thermal or power throttling: under a constant load, a throttling
+//! machine shows up as frame times creeping up minute after minute.
+
+use super::{DebugTextWriter, DebugVisConfig, OverlayCost};
+use bevy::prelude::*;
+use std::{collections::VecDeque, time::Duration};
+
+const MINUTE: Duration = Duration::from_secs(60);
+/// A minute may come in this much faster than the one before and still count as decaying,
+/// so noise doesn't reset the trend.
+const MONOTONIC_TOLERANCE: f64 = 0.01;
+const THROTTLE_WARNING_COLOR: Color = Color::srgb(1.0, 1.0, 0.0);
+
+/// Per-minute median frame times, oldest first, kept for
+/// [`DebugVisConfig::throttle_window_minutes`] minutes.
+#[derive(Resource, Default, Debug)]
+pub struct ThrottleDetector {
+    minute_start: Option<Duration>,
+    current_minute_ms: Vec<f64>,
+    minute_medians_ms: VecDeque<f64>,
+}
+
+impl ThrottleDetector {
+    pub(super) fn record(&mut self, frame_time_ms: f64) {
+        self.current_minute_ms.push(frame_time_ms);
+    }
+
+    /// Median frame time of each complete minute in the window, oldest first, for plotting
+    /// the decay curve offline.
+    pub fn minute_medians_ms(&self) -> impl Iterator<Item = f64> + '_ {
+        self.minute_medians_ms.iter().copied()
+    }
+
+    /// How much achievable FPS dropped across the window, as a fraction, when every minute
+    /// was at least as slow as the one before. `None` until the window is full.
+    pub fn fps_decline(&self, window_minutes: usize) -> Option<f64> {
+        if window_minutes < 2 || self.minute_medians_ms.len() < window_minutes {
+            return None;
+        }
+
+        let mut medians = self.minute_medians_ms.iter().copied();
+        let first = medians.next()?;
+        let mut previous = first;
+        for median in medians {
+            if median < previous * (1.0 - MONOTONIC_TOLERANCE) {
+                return None;
+            }
+            previous = median;
+        }
+
+        (previous > first).then(|| 1.0 - first / previous)
+    }
+}
+
+pub(super) fn analyze_throttling(
+    config: Res<DebugVisConfig>,
+    time: Res<Time<Real>>,
+    mut detector: ResMut<ThrottleDetector>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let now = time.elapsed();
+    let minute_start = *detector.minute_start.get_or_insert(now);
+    if now.saturating_sub(minute_start) >= MINUTE {
+        detector.minute_start = Some(now);
+        let mut minute = std::mem::take(&mut detector.current_minute_ms);
+        if !minute.is_empty() {
+            minute.sort_by(f64::total_cmp);
+            let median = minute[minute.len() / 2];
+            detector.minute_medians_ms.push_back(median);
+            while detector.minute_medians_ms.len() > config.throttle_window_minutes {
+                detector.minute_medians_ms.pop_front();
+            }
+        }
+    }
+
+    let Some(decline) = detector.fps_decline(config.throttle_window_minutes) else {
+        return;
+    };
+    if decline * 100.0 < config.throttle_warn_percent {
+        return;
+    }
+
+    writer.write_colored(
+        "throttling",
+        format!(
+            "Possible throttling: −{:.0}% over {} min",
+            decline * 100.0,
+            config.throttle_window_minutes
+        ),
+        THROTTLE_WARNING_COLOR,
+    );
+}
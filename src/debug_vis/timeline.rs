@@ -0,0 +1,181 @@
+//! Window and cursor events recorded next to the frame times, so a trace read offline shows
+//! where the window was resized or lost focus. Each event keeps the [`FrameCount`] it was
+//! seen in, for lining it up with the samples.
+
+use super::DebugVisConfig;
+use bevy::{
+    diagnostic::FrameCount,
+    prelude::*,
+    window::{CursorEntered, CursorLeft, WindowFocused, WindowResized, WindowScaleFactorChanged},
+};
+use std::{collections::VecDeque, fmt, time::Duration};
+
+const MAX_TIMELINE_EVENTS: usize = 1000;
+/// Chart marker color, apart from the bar colors and percentile lines.
+pub(super) const TIMELINE_MARKER_COLOR: Color = Color::srgb(0.3, 0.6, 1.0);
+
+/// Which event categories are recorded into [`TimelineEvents`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimelineCategories(u8);
+
+impl TimelineCategories {
+    pub const RESIZE: Self = Self(1 << 0);
+    pub const FOCUS: Self = Self(1 << 1);
+    pub const SCALE_FACTOR: Self = Self(1 << 2);
+    pub const CURSOR: Self = Self(1 << 3);
+
+    pub const ALL: [Self; 4] = [Self::RESIZE, Self::FOCUS, Self::SCALE_FACTOR, Self::CURSOR];
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn contains(self, categories: Self) -> bool {
+        self.0 & categories.0 == categories.0
+    }
+
+    pub fn set(&mut self, categories: Self, enabled: bool) {
+        if enabled {
+            self.0 |= categories.0;
+        } else {
+            self.0 &= !categories.0;
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::RESIZE => "resize",
+            Self::FOCUS => "focus",
+            Self::SCALE_FACTOR => "scale_factor",
+            Self::CURSOR => "cursor",
+            _ => "mixed",
+        }
+    }
+}
+
+impl Default for TimelineCategories {
+    fn default() -> Self {
+        Self(Self::ALL.iter().fold(0, |bits, category| bits | category.0))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimelineEventKind {
+    Resized { width: f32, height: f32 },
+    Focused(bool),
+    ScaleFactorChanged(f64),
+    CursorEntered,
+    CursorLeft,
+}
+
+impl TimelineEventKind {
+    pub fn category(self) -> TimelineCategories {
+        match self {
+            Self::Resized { .. } => TimelineCategories::RESIZE,
+            Self::Focused(_) => TimelineCategories::FOCUS,
+            Self::ScaleFactorChanged(_) => TimelineCategories::SCALE_FACTOR,
+            Self::CursorEntered | Self::CursorLeft => TimelineCategories::CURSOR,
+        }
+    }
+}
+
+/// Compact form for CSV cells and JSON strings, e.g. `resized 1280x720`.
+impl fmt::Display for TimelineEventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Resized { width, height } => write!(f, "resized {width:.0}x{height:.0}"),
+            Self::Focused(true) => f.write_str("focused"),
+            Self::Focused(false) => f.write_str("unfocused"),
+            Self::ScaleFactorChanged(scale) => write!(f, "scale factor {scale}"),
+            Self::CursorEntered => f.write_str("cursor entered"),
+            Self::CursorLeft => f.write_str("cursor left"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimelineEvent {
+    pub frame: u32,
+    /// Real time since startup.
+    pub time: Duration,
+    pub window: Entity,
+    pub kind: TimelineEventKind,
+}
+
+/// The last 1000 recorded window events, oldest first.
+#[derive(Resource, Debug, Default)]
+pub struct TimelineEvents(VecDeque<TimelineEvent>);
+
+impl TimelineEvents {
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &TimelineEvent> + '_ {
+        self.0.iter()
+    }
+
+    /// Events seen in frames `first..=last`, oldest first.
+    pub fn in_frames(&self, first: u32, last: u32) -> impl Iterator<Item = &TimelineEvent> + '_ {
+        let start = self.0.partition_point(|event| event.frame < first);
+        self.0
+            .range(start..)
+            .take_while(move |event| event.frame <= last)
+    }
+
+    fn push(&mut self, event: TimelineEvent) {
+        self.0.push_back(event);
+        if self.0.len() > MAX_TIMELINE_EVENTS {
+            self.0.pop_front();
+        }
+    }
+}
+
+pub(super) fn record_timeline_events(
+    config: Res<DebugVisConfig>,
+    frame: Res<FrameCount>,
+    time: Res<Time<Real>>,
+    mut resized: MessageReader<WindowResized>,
+    mut focused: MessageReader<WindowFocused>,
+    mut scale_factor: MessageReader<WindowScaleFactorChanged>,
+    mut entered: MessageReader<CursorEntered>,
+    mut left: MessageReader<CursorLeft>,
+    mut timeline: ResMut<TimelineEvents>,
+) {
+    let kinds = resized
+        .read()
+        .map(|event| {
+            let kind = TimelineEventKind::Resized {
+                width: event.width,
+                height: event.height,
+            };
+            (event.window, kind)
+        })
+        .chain(
+            focused
+                .read()
+                .map(|event| (event.window, TimelineEventKind::Focused(event.focused))),
+        )
+        .chain(scale_factor.read().map(|event| {
+            let kind = TimelineEventKind::ScaleFactorChanged(event.scale_factor);
+            (event.window, kind)
+        }))
+        .chain(
+            entered
+                .read()
+                .map(|event| (event.window, TimelineEventKind::CursorEntered)),
+        )
+        .chain(
+            left.read()
+                .map(|event| (event.window, TimelineEventKind::CursorLeft)),
+        );
+
+    for (window, kind) in kinds {
+        if !config.timeline_categories.contains(kind.category()) {
+            continue;
+        }
+        timeline.push(TimelineEvent {
+            frame: frame.0,
+            time: time.elapsed(),
+            window,
+            kind,
+        });
+    }
+}
@@ -0,0 +1,202 @@
+//! Posting a finished sweep's report to a collector, with the `report_upload` feature. This is
+//! a bare HTTP/1.1 client over [`TcpStream`]: plain `http://` URLs only, one request per
+//! connection. It runs on its own thread, so the overlay keeps drawing while it waits.
+
+use std::{
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// Environment variable holding the `Authorization` header value, e.g. `Bearer <token>`.
+pub const REPORT_AUTH_ENV: &str = "STABILITY_REPORT_AUTH";
+/// Limit on connecting, and separately on sending and on each read of the response.
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// An upload in flight on a background thread.
+#[derive(Debug)]
+pub(super) struct ReportUpload(Arc<Mutex<Option<String>>>);
+
+impl ReportUpload {
+    pub(super) fn start(url: String, body: String) -> Self {
+        let outcome = Arc::new(Mutex::new(None));
+        let (slot, thread_url) = (outcome.clone(), url.clone());
+        let spawned = std::thread::Builder::new()
+            .name("debug_vis report upload".to_string())
+            .spawn(move || {
+                let auth = std::env::var(REPORT_AUTH_ENV).ok();
+                let result = post_with_retry(&thread_url, &body, auth.as_deref());
+                if let Ok(mut slot) = slot.lock() {
+                    *slot = Some(describe(&thread_url, result));
+                }
+            });
+        if let Err(err) = spawned
+            && let Ok(mut slot) = outcome.lock()
+        {
+            *slot = Some(describe(&url, Err(err)));
+        }
+        Self(outcome)
+    }
+
+    /// The outcome line, e.g. "Report upload to http://…: HTTP 201", once the upload is done.
+    /// Taken by the first call.
+    pub(super) fn outcome(&self) -> Option<String> {
+        self.0.lock().ok()?.take()
+    }
+}
+
+fn describe(url: &str, result: io::Result<u16>) -> String {
+    match result {
+        Ok(status) => format!("Report upload to {url}: HTTP {status}"),
+        Err(err) => format!("Report upload to {url} failed: {err}"),
+    }
+}
+
+/// Posts once more, after [`RETRY_DELAY`], when the first attempt fails to connect or gets a
+/// 5xx. Returns the last attempt's status.
+fn post_with_retry(url: &str, body: &str, auth: Option<&str>) -> io::Result<u16> {
+    match post_json(url, body, auth) {
+        Ok(status) if status < 500 => Ok(status),
+        _ => {
+            std::thread::sleep(RETRY_DELAY);
+            post_json(url, body, auth)
+        }
+    }
+}
+
+/// `http://host[:port]/path` split into the address to connect to, the `Host` header and the
+/// request path.
+fn parse_url(url: &str) -> io::Result<(String, &str, &str)> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "only http:// URLs are supported")
+    })?;
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    if host.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "URL has no host"));
+    }
+    let address = if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{host}:80")
+    };
+    Ok((address, host, if path.is_empty() { "/" } else { path }))
+}
+
+fn post_json(url: &str, body: &str, auth: Option<&str>) -> io::Result<u16> {
+    let (address, host, path) = parse_url(url)?;
+    let address = address.to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, format!("{host} has no address"))
+    })?;
+    let mut stream = TcpStream::connect_timeout(&address, UPLOAD_TIMEOUT)?;
+    stream.set_write_timeout(Some(UPLOAD_TIMEOUT))?;
+    stream.set_read_timeout(Some(UPLOAD_TIMEOUT))?;
+
+    let mut request = format!(
+        "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n",
+        body.len()
+    );
+    if let Some(auth) = auth {
+        request.push_str(&format!("Authorization: {auth}\r\n"));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+
+    // Only the status line matters, and it's the first thing the server sends.
+    let mut response = Vec::new();
+    let mut buf = [0; 256];
+    while !response.windows(2).any(|window| window == b"\r\n") {
+        let read = stream.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        response.extend_from_slice(&buf[..read]);
+    }
+    String::from_utf8_lossy(&response)
+        .split(' ')
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "no HTTP status line in the response")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io::BufRead, net::TcpListener};
+
+    /// Accepts one connection per entry of `responses`, answering with it (or hanging up
+    /// without a word for `None`), and returns the requests received.
+    fn serve(
+        responses: Vec<Option<&'static str>>,
+    ) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/reports", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    let (stream, _) = listener.accept().unwrap();
+                    let mut reader = io::BufReader::new(stream);
+                    let mut request = String::new();
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if let Some(length) = line.strip_prefix("Content-Length: ") {
+                            content_length = length.trim().parse().unwrap();
+                        }
+                        request.push_str(&line);
+                        if line == "\r\n" {
+                            break;
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    reader.read_exact(&mut body).unwrap();
+                    request.push_str(&String::from_utf8(body).unwrap());
+                    if let Some(response) = response {
+                        reader.get_mut().write_all(response.as_bytes()).unwrap();
+                    }
+                    request
+                })
+                .collect()
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn posts_the_report_with_auth() {
+        let (url, server) = serve(vec![Some("HTTP/1.1 201 Created\r\n\r\n")]);
+        let status = post_with_retry(&url, r#"{"version":1}"#, Some("Bearer abc"));
+        assert_eq!(status.unwrap(), 201);
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].starts_with("POST /reports HTTP/1.1\r\n"), "{}", requests[0]);
+        assert!(requests[0].contains("\r\nAuthorization: Bearer abc\r\n"), "{}", requests[0]);
+        assert!(requests[0].ends_with("\r\n\r\n{\"version\":1}"), "{}", requests[0]);
+    }
+
+    #[test]
+    fn retries_once_after_a_failure() {
+        let (url, server) = serve(vec![
+            None,
+            Some("HTTP/1.1 503 Service Unavailable\r\n\r\n"),
+        ]);
+        let result = post_with_retry(&url, "{}", None);
+        assert_eq!(server.join().unwrap().len(), 2);
+        assert_eq!(describe(&url, result), format!("Report upload to {url}: HTTP 503"));
+    }
+
+    #[test]
+    fn only_plain_http_is_supported() {
+        let err = post_json("https://example.com/reports", "{}", None).unwrap_err();
+        assert_eq!(err.to_string(), "only http:// URLs are supported");
+        let parsed = parse_url("http://localhost").unwrap();
+        assert_eq!(parsed, ("localhost:80".to_string(), "localhost", "/"));
+    }
+}
@@ -0,0 +1,120 @@
+//! Near-miss analysis for vsync. A frame that runs even slightly past the refresh interval
+//! waits for the next vblank and is presented a whole interval late, so 17.1 ms at 60 Hz
+//! judders like 33.3 ms. Frames are classed against the interval so those show up.
+
+use super::{DebugTextWriter, DebugVisConfig, FrameTimeHistory, HEALTH_WARN_COLOR, OverlayCost};
+use bevy::{
+    prelude::*,
+    window::{Monitor, PresentMode, PrimaryMonitor, PrimaryWindow},
+};
+
+/// A frame up to this fraction over the refresh interval counts as a near miss.
+const NEAR_MISS_TOLERANCE: f64 = 0.15;
+/// Outline drawn around near-miss bars in the frametime chart.
+pub(super) const NEAR_MISS_COLOR: Color = Color::srgb(1.0, 0.0, 1.0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VsyncClass {
+    Hit,
+    /// Over the interval by less than 15%: cheap to fix, and costs a full interval anyway.
+    NearMiss,
+    Missed,
+}
+
+impl VsyncClass {
+    pub fn classify(frame_time_ms: f64, refresh_interval_ms: f64) -> Self {
+        if frame_time_ms <= refresh_interval_ms {
+            Self::Hit
+        } else if frame_time_ms <= refresh_interval_ms * (1.0 + NEAR_MISS_TOLERANCE) {
+            Self::NearMiss
+        } else {
+            Self::Missed
+        }
+    }
+}
+
+/// Frames of the [`FrameTimeHistory`] window per [`VsyncClass`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct VsyncMisses {
+    pub hit: usize,
+    pub near_miss: usize,
+    pub missed: usize,
+}
+
+impl VsyncMisses {
+    pub fn count(frame_times_ms: impl IntoIterator<Item = f64>, refresh_interval_ms: f64) -> Self {
+        let mut misses = Self::default();
+        for frame_time_ms in frame_times_ms {
+            match VsyncClass::classify(frame_time_ms, refresh_interval_ms) {
+                VsyncClass::Hit => misses.hit += 1,
+                VsyncClass::NearMiss => misses.near_miss += 1,
+                VsyncClass::Missed => misses.missed += 1,
+            }
+        }
+        misses
+    }
+
+    /// Near misses as a percentage of all frames; the figure that tracks perceived judder
+    /// best under vsync.
+    pub fn near_miss_percent(&self) -> f64 {
+        let total = self.hit + self.near_miss + self.missed;
+        if total == 0 {
+            return 0.0;
+        }
+        self.near_miss as f64 * 100.0 / total as f64
+    }
+}
+
+/// Refresh interval the analysis runs against, in ms. `None` while the primary window isn't
+/// vsynced, or when neither [`DebugVisConfig::refresh_interval_ms`] nor the primary monitor
+/// gives one.
+#[derive(Resource, Debug, Default, PartialEq)]
+pub(super) struct RefreshInterval(pub(super) Option<f64>);
+
+/// The primary monitor stands in for the window's own, which Bevy doesn't expose; set
+/// [`DebugVisConfig::refresh_interval_ms`] when the window lives on another display.
+pub(super) fn update_refresh_interval(
+    config: Res<DebugVisConfig>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    monitors: Query<&Monitor, With<PrimaryMonitor>>,
+    mut interval: ResMut<RefreshInterval>,
+) {
+    let vsync = windows.single().is_ok_and(|window| {
+        matches!(
+            window.present_mode,
+            PresentMode::AutoVsync | PresentMode::Fifo | PresentMode::FifoRelaxed
+        )
+    });
+    let interval_ms = config.refresh_interval_ms.or_else(|| {
+        let millihertz = monitors.single().ok()?.refresh_rate_millihertz?;
+        (millihertz > 0).then(|| 1_000_000.0 / millihertz as f64)
+    });
+    interval.set_if_neq(RefreshInterval(interval_ms.filter(|_| vsync)));
+}
+
+pub(super) fn write_vsync_misses(
+    interval: Res<RefreshInterval>,
+    history: Res<FrameTimeHistory>,
+    mut writer: DebugTextWriter,
+    cost: Res<OverlayCost>,
+) {
+    let _cost = cost.measure();
+
+    let Some(interval_ms) = interval.0 else {
+        return;
+    };
+    let misses = history.vsync_misses(interval_ms);
+    writer.write(
+        "vsync_misses",
+        format!(
+            "Vsync {interval_ms:.1}ms: {} hit, {} near miss ({:.1}%), {} missed",
+            misses.hit,
+            misses.near_miss,
+            misses.near_miss_percent(),
+            misses.missed
+        ),
+    );
+    if misses.near_miss > 0 {
+        writer.set_value_color("vsync_misses", HEALTH_WARN_COLOR);
+    }
+}
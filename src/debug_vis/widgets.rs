@@ -0,0 +1,106 @@
+//! Per-widget switches layered on top of [`DebugLevel`](super::DebugLevel).
+
+use super::keybindings::{DebugAction, DebugKeybindings};
+use bevy::prelude::*;
+
+/// Which overlay widgets are shown. [`DebugLevel`](super::DebugLevel) still decides whether a
+/// widget is shown at all; a cleared flag hides it even at the level that would show it.
+///
+/// While the level toggle key is held, pressing `1`..`5` flips the widget with that index.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DebugWidgets(u8);
+
+impl DebugWidgets {
+    pub const FPS_TEXT: Self = Self(1 << 0);
+    pub const CONSISTENCY_TEXT: Self = Self(1 << 1);
+    pub const CHART: Self = Self(1 << 2);
+    pub const DEBUG_LINES: Self = Self(1 << 3);
+    /// Gauge alternative to the FPS text. Off by default.
+    pub const FPS_DIAL: Self = Self(1 << 4);
+
+    /// Flags in chord order: holding the level toggle key and pressing `1` flips the first.
+    pub const ALL: [Self; 5] = [
+        Self::FPS_TEXT,
+        Self::CONSISTENCY_TEXT,
+        Self::CHART,
+        Self::DEBUG_LINES,
+        Self::FPS_DIAL,
+    ];
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn union(self, widgets: Self) -> Self {
+        Self(self.0 | widgets.0)
+    }
+
+    pub const fn contains(self, widgets: Self) -> bool {
+        self.0 & widgets.0 == widgets.0
+    }
+
+    pub fn set(&mut self, widgets: Self, enabled: bool) {
+        if enabled {
+            self.0 |= widgets.0;
+        } else {
+            self.0 &= !widgets.0;
+        }
+    }
+
+    pub fn toggle(&mut self, widgets: Self) {
+        self.0 ^= widgets.0;
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::FPS_TEXT => "fps_text",
+            Self::CONSISTENCY_TEXT => "consistency_text",
+            Self::CHART => "chart",
+            Self::DEBUG_LINES => "debug_lines",
+            Self::FPS_DIAL => "fps_dial",
+            _ => "widgets",
+        }
+    }
+}
+
+impl Default for DebugWidgets {
+    fn default() -> Self {
+        Self::ALL
+            .into_iter()
+            .filter(|&widget| widget != Self::FPS_DIAL)
+            .fold(Self::empty(), Self::union)
+    }
+}
+
+const CHORD_KEYS: [KeyCode; 5] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+];
+
+pub(super) fn toggle_widget_chords(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<DebugKeybindings>,
+    mut widgets: ResMut<DebugWidgets>,
+) {
+    let Some(toggle) = bindings.get(DebugAction::ToggleDebugLevel) else {
+        return;
+    };
+    if !keys.pressed(toggle.key) {
+        return;
+    }
+
+    for (key, widget) in CHORD_KEYS.into_iter().zip(DebugWidgets::ALL) {
+        if keys.just_pressed(key) {
+            widgets.toggle(widget);
+            info!(
+                "Debug widget {} {}",
+                widget.name(),
+                if widgets.contains(widget) { "on" } else { "off" }
+            );
+        }
+    }
+}
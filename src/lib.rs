@@ -0,0 +1,22 @@
+//! Frame time and stability overlay for Bevy apps. Most apps only need the [`prelude`]; the
+//! rest of the API lives in [`debug_vis`].
+
+// Systems take everything they touch as parameters, and overlay queries need long filter
+// tuples to stay disjoint, so both lints fire on ordinary Bevy code.
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+pub mod debug_vis;
+
+/// The plugin, its settings, the writer and the free functions and macro for debug lines and
+/// shapes, in one import.
+pub mod prelude {
+    pub use crate::dbg_text;
+    pub use crate::debug_vis::{
+        ContactNormal, Cycle, DebugAssetReloadsAppExt, DebugDisplayAppExt, DebugLevel,
+        DebugLevelChanged, DebugOverlayCamera, DebugStateAppExt, DebugText, DebugTextWriter,
+        DebugTextsReader, DebugVisConfig, DebugVisPlugin, DebugWidgets, FrameTimeHistory,
+        RecordingEnabled, ScenarioAppExt, debug_capsule, debug_capsule_for, debug_hit,
+        debug_hit_for, debug_ray, debug_ray_for, debug_text, debug_text_once,
+        debug_text_persistent, on_debug_level,
+    };
+}
@@ -1,12 +1,45 @@
-mod debug_vis;
-
 use bevy::{
     prelude::*,
     window::{Window, WindowPlugin},
 };
-use debug_vis::DebugVisPlugin;
+use bevy_stability_test::prelude::*;
+
+const FULL_OVERLAY_TINT: Color = Color::srgb(0.05, 0.05, 0.12);
 
 fn main() {
+    let overlay = DebugVisPlugin::default();
+    // Unattended overnight runs: periodic exports and collapse reports under `soak/`.
+    #[cfg(not(target_arch = "wasm32"))]
+    let overlay = if std::env::args().any(|arg| arg == "--soak") {
+        overlay.with_soak(bevy_stability_test::debug_vis::SoakConfig::default())
+    } else {
+        overlay
+    };
+    // Deltas against a report saved earlier from the command palette.
+    #[cfg(all(feature = "serde", not(target_arch = "wasm32")))]
+    let overlay = match arg_value("--baseline") {
+        Some(path) => overlay.with_baseline(path),
+        None => overlay,
+    };
+    // A recorded trace played through the overlay instead of live frames.
+    #[cfg(not(target_arch = "wasm32"))]
+    let overlay = match arg_value("--replay") {
+        Some(path) => overlay.with_replay(path),
+        None => overlay,
+    };
+    // Every scenario below in turn, then a report and exit.
+    #[cfg(not(target_arch = "wasm32"))]
+    let overlay = if std::env::args().any(|arg| arg == "--sweep") {
+        overlay.with_sweep(bevy_stability_test::debug_vis::SweepConfig {
+            // The report also goes to a collector, with STABILITY_REPORT_AUTH as credentials.
+            #[cfg(feature = "report_upload")]
+            report_url: arg_value("--report-url"),
+            ..default()
+        })
+    } else {
+        overlay
+    };
+
     App::new()
         .add_plugins(
             DefaultPlugins
@@ -21,11 +54,85 @@ fn main() {
                     ..default()
                 })
         )
-        .add_plugins(DebugVisPlugin)
+        .add_plugins(overlay)
+        .register_scenario("cubes", spawn_cubes)
+        .register_scenario("lights", spawn_lights)
         .add_systems(Startup, startup)
+        .add_systems(Update, tint_clear_color_while_full)
         .run();
 }
 
+/// The argument following `name`, e.g. the path in `--baseline report.json`.
+#[cfg(not(target_arch = "wasm32"))]
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args();
+    args.find(|arg| arg == name)?;
+    args.next()
+}
+
 fn startup(mut commands: Commands) {
     commands.spawn(Camera3d::default());
 }
+
+/// A grid of lit cubes in front of the camera.
+fn spawn_cubes(world: &mut World) -> Result<(), String> {
+    let mesh = world.resource_mut::<Assets<Mesh>>().add(Cuboid::default());
+    let material = world
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(Color::srgb(0.8, 0.7, 0.6));
+    world.spawn((
+        DirectionalLight::default(),
+        Transform::default().looking_to(Vec3::new(-0.5, -1.0, -1.0), Vec3::Y),
+    ));
+    for x in -10..10 {
+        for y in -5..5 {
+            world.spawn((
+                Mesh3d(mesh.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::from_xyz(x as f32 * 1.5, y as f32 * 1.5, -30.0),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// A wall lit by a ring of point lights, to load the forward lighting path.
+fn spawn_lights(world: &mut World) -> Result<(), String> {
+    let mesh = world
+        .resource_mut::<Assets<Mesh>>()
+        .add(Plane3d::new(Vec3::Z, Vec2::splat(20.0)));
+    let material = world
+        .resource_mut::<Assets<StandardMaterial>>()
+        .add(Color::WHITE);
+    world.spawn((
+        Mesh3d(mesh),
+        MeshMaterial3d(material),
+        Transform::from_xyz(0.0, 0.0, -20.0),
+    ));
+    for index in 0..32 {
+        let angle = index as f32 / 32.0 * std::f32::consts::TAU;
+        world.spawn((
+            PointLight {
+                color: Color::hsl(angle.to_degrees(), 1.0, 0.5),
+                range: 10.0,
+                ..default()
+            },
+            Transform::from_xyz(angle.cos() * 8.0, angle.sin() * 8.0, -18.0),
+        ));
+    }
+    Ok(())
+}
+
+/// Darkens the background slightly while the full overlay is open, so it stays readable.
+fn tint_clear_color_while_full(
+    mut changes: MessageReader<DebugLevelChanged>,
+    mut clear_color: ResMut<ClearColor>,
+) {
+    if let Some(change) = changes.read().last() {
+        clear_color.0 = if change.to == DebugLevel::Full {
+            FULL_OVERLAY_TINT
+        } else {
+            ClearColor::default().0
+        };
+    }
+}
@@ -0,0 +1,36 @@
+//! Shared setup for the integration tests.
+
+use bevy::{
+    prelude::*,
+    render::{
+        RenderPlugin,
+        settings::{RenderCreation, WgpuSettings},
+    },
+    window::ExitCondition,
+    winit::WinitPlugin,
+};
+use bevy_stability_test::prelude::*;
+
+/// The full plugin set without a window or GPU, so the overlay builds the way it does in a
+/// real app.
+pub fn headless_app() -> App {
+    let mut app = App::new();
+    app.add_plugins(
+        DefaultPlugins
+            .set(WindowPlugin {
+                primary_window: None,
+                exit_condition: ExitCondition::DontExit,
+                ..default()
+            })
+            .set(RenderPlugin {
+                render_creation: RenderCreation::Automatic(WgpuSettings {
+                    backends: None,
+                    ..default()
+                }),
+                ..default()
+            })
+            .disable::<WinitPlugin>(),
+    )
+    .add_plugins(DebugVisPlugin::default());
+    app
+}
@@ -0,0 +1,83 @@
+//! Steady-state recording must not allocate: an allocation in the middle of a frame shows up
+//! in the very frame times being recorded.
+
+use bevy_stability_test::prelude::*;
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Counts allocations made on threads that opted in, so the test harness's own threads
+/// don't show up in the count.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn count() {
+    if COUNTING.try_with(Cell::get).unwrap_or(false) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count();
+        unsafe { System.alloc_zeroed(layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Allocations made by `f` on this thread.
+fn allocations_in(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    COUNTING.with(|counting| counting.set(true));
+    f();
+    COUNTING.with(|counting| counting.set(false));
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// Frame time for sample `i`: mostly steady with a spike now and then, so the sliding
+/// max/min candidate queues both grow and drain.
+fn frame_time_ms(i: u32) -> f64 {
+    if i.is_multiple_of(97) {
+        45.0
+    } else {
+        16.0 + f64::from(i % 7) * 0.5
+    }
+}
+
+#[test]
+fn frame_time_history_push_does_not_allocate() {
+    const PUSHES: u32 = 10_000;
+    let mut history = FrameTimeHistory::default();
+
+    let allocations = allocations_in(|| {
+        for i in 0..PUSHES {
+            let smoothed_ms = i.is_multiple_of(2).then_some(16.0);
+            history.push(frame_time_ms(i), smoothed_ms, i);
+        }
+    });
+
+    assert_eq!(allocations, 0, "{allocations} allocations across {PUSHES} pushes");
+}
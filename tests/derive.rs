@@ -0,0 +1,75 @@
+//! `#[derive(DebugText)]` from outside the crate, the way a host app uses it.
+// Without `debug-vis` the writer drops every line, so there is nothing to read back.
+#![cfg(feature = "debug-vis")]
+
+mod common;
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use bevy_stability_test::{debug_vis::dump_debug_text_components, prelude::*};
+use common::headless_app;
+
+#[derive(DebugText)]
+struct Player {
+    health: u32,
+    name: &'static str,
+}
+
+#[derive(DebugText)]
+struct Velocity(f32, f32);
+
+#[derive(Component, DebugText)]
+struct Ammo<T: std::fmt::Debug + Send + Sync + 'static> {
+    count: T,
+}
+
+fn read(app: &mut App, key: &'static str) -> Option<String> {
+    app.world_mut()
+        .run_system_once(move |reader: DebugTextsReader| reader.get(key))
+        .unwrap()
+}
+
+#[test]
+fn derived_fields_write_through_writer() {
+    let mut app = headless_app();
+    app.add_systems(Update, |mut writer: DebugTextWriter| {
+        let player = Player {
+            health: 80,
+            name: "ada",
+        };
+        player.debug_dump(&mut writer, "player");
+        Velocity(1.5, -2.0).debug_dump(&mut writer, "velocity");
+    });
+    app.finish();
+    app.cleanup();
+    app.update();
+
+    assert_eq!(read(&mut app, "player_health").as_deref(), Some("80"));
+    assert_eq!(read(&mut app, "player_name").as_deref(), Some("\"ada\""));
+    assert_eq!(read(&mut app, "velocity_0").as_deref(), Some("1.5"));
+    assert_eq!(read(&mut app, "velocity_1").as_deref(), Some("-2.0"));
+}
+
+#[test]
+fn derived_components_dump_per_entity() {
+    let mut app = headless_app();
+    app.add_systems(Update, dump_debug_text_components::<Ammo<u8>>);
+    app.finish();
+    app.cleanup();
+    let entity = app.world_mut().spawn(Ammo { count: 12_u8 }).id();
+    app.update();
+
+    let lines = app
+        .world_mut()
+        .run_system_once(|reader: DebugTextsReader| {
+            reader
+                .iter()
+                .map(|(key, text)| (key.to_string(), text))
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+    let key = format!("Ammo<u8>[{entity}]_count");
+    assert!(
+        lines.iter().any(|(k, text)| *k == key && text == "12"),
+        "no `{key}` line in {lines:?}"
+    );
+}
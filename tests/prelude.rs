@@ -0,0 +1,36 @@
+//! The prelude alone is enough to add the overlay, write lines and read them back.
+// Without `debug-vis` the writer drops every line, so there is nothing to read back.
+#![cfg(feature = "debug-vis")]
+
+mod common;
+
+use bevy::{ecs::system::RunSystemOnce, prelude::*};
+use bevy_stability_test::prelude::*;
+use common::headless_app;
+
+#[test]
+fn writer_and_queue_read_back_through_reader() {
+    let mut app = headless_app();
+    app.add_systems(Update, |mut writer: DebugTextWriter| {
+        writer.write("prelude_writer", "Writer: 1");
+    });
+    app.finish();
+    app.cleanup();
+
+    debug_text("prelude_queue", "Queue: 2");
+    app.update();
+
+    let (writer_line, queue_line, count) = app
+        .world_mut()
+        .run_system_once(|reader: DebugTextsReader| {
+            (
+                reader.get("prelude_writer"),
+                reader.get("prelude_queue"),
+                reader.entry_count(),
+            )
+        })
+        .unwrap();
+    assert_eq!(writer_line.as_deref(), Some("Writer: 1"));
+    assert_eq!(queue_line.as_deref(), Some("Queue: 2"));
+    assert!(count >= 2);
+}